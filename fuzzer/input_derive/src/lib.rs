@@ -0,0 +1,800 @@
+//! Derives the boilerplate of an [`Input`] for a utility that is configured by flags and options only.
+//!
+//! Annotate the fields of a struct named `<Prefix>Input` with
+//! - `#[stdin]` for the `Vec<u8>` passed to `stdin`
+//! - `#[flag("-d", "--decode")]` for a `bool` that adds the option when set
+//! - `#[opt("-w", "--wrap", range = 0..=76)]` for an `Option<T>` that adds the option with its value when set. `T`
+//!   implements `OptionValue`, which is either an integer type or `NumericArg` for a number passed as a raw string. The
+//!   range bounds the generated values and defaults to the full range of `T`.
+//! - `#[spelling]` for a `u64` seeding how the options are written, see `render_args`
+//!
+//! Flags and options take a short spelling, a long one, or both. If there is a `#[spelling]` field, list all long options
+//! the utility accepts in GNU or uutils with `#[util_input(long_options("--decode", ..))]` on the struct, as long
+//! options are only abbreviated as far as is unambiguous among them.
+//! The generated code refers to the runtime support in `crate::generic`. Outside of the fuzzer, point it to the crate
+//! providing the `generic` module with `#[util_input(crate = path)]` on the struct.
+//! Fields without an annotation are set to their [`Default`] by the generator and are not passed to the utility.
+//!
+//! `#[derive(UtilInput)]` then implements [`Input`], [`Display`], `ExtractsToCommand`, `HasLen`, which is the length of
+//! `stdin` or of the arguments if there is none, and, if there is a `#[stdin]` field, `HasByteFields` for the struct,
+//! as well as `HasArgSpelling` and `Shrinkable`, which drops set flags and options and shortens `stdin` and raw option
+//! values. It also creates
+//! - `<Prefix>Generator`, which generates `stdin` according to a `GeneratorType` and sets each flag and option with a
+//!   probability of one half
+//! - `<Prefix>Flip<Field>Mutator` for each flag and option, which toggles it
+//! - `<Prefix><Field>ContentMutator` for each option, which mutates its value if it is set
+//! - `<prefix>_option_mutators()`, returning all of these mutators and the `SpellingMutator` if there is a
+//!   `#[spelling]` field, and its return type `<Prefix>OptionMutatorsType`
+//!
+//! [`Input`]: https://docs.rs/libafl/latest/libafl/inputs/trait.Input.html
+//! [`Display`]: std::fmt::Display
+
+use std::collections::BTreeSet;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input, Data, DeriveInput, Error, Expr, Fields, GenericArgument, Ident, LitStr,
+    Path, PathArguments, Token, Type,
+};
+
+/// The short and long spelling of a flag or option.
+struct Spellings {
+    short: Option<LitStr>,
+    long: Option<LitStr>,
+}
+
+impl Parse for Spellings {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut spellings = Self {
+            short: None,
+            long: None,
+        };
+        while input.peek(LitStr) {
+            let spelling: LitStr = input.parse()?;
+            let slot = if spelling.value().starts_with("--") {
+                &mut spellings.long
+            } else {
+                &mut spellings.short
+            };
+            if slot.replace(spelling.clone()).is_some() {
+                return Err(Error::new(
+                    spelling.span(),
+                    "expected at most one short and one long spelling",
+                ));
+            }
+            if input.peek(Token![,]) && input.peek2(LitStr) {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        if spellings.short.is_none() && spellings.long.is_none() {
+            return Err(input.error("expected the spelling of the option"));
+        }
+        Ok(spellings)
+    }
+}
+
+/// The arguments of `#[opt(..)]`.
+struct OptArgs {
+    spellings: Spellings,
+    range: Option<Expr>,
+}
+
+impl Parse for OptArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let spellings = input.parse()?;
+        let mut range = None;
+        while input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            if key != "range" {
+                return Err(Error::new(key.span(), "expected `range`"));
+            }
+            range = Some(input.parse()?);
+        }
+        Ok(Self { spellings, range })
+    }
+}
+
+enum FieldKind {
+    Stdin,
+    Flag(Spellings),
+    Opt {
+        spellings: Spellings,
+        value_type: Box<Type>,
+        range: Box<Expr>,
+    },
+    Spelling,
+    Other,
+}
+
+struct InputField {
+    ident: Ident,
+    kind: FieldKind,
+}
+
+#[proc_macro_derive(UtilInput, attributes(util_input, stdin, flag, opt, spelling))]
+pub fn derive_util_input(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let UtilInputArgs {
+        krate,
+        long_options,
+    } = parse_util_input(input)?;
+    let krate = &krate;
+    let fields = parse_fields(input, krate)?;
+    let name = &input.ident;
+    let vis = &input.vis;
+    let prefix = name
+        .to_string()
+        .strip_suffix("Input")
+        .filter(|p| !p.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| Error::new(name.span(), "the struct's name must end in `Input`"))?;
+    let stdin = fields
+        .iter()
+        .filter(|f| matches!(f.kind, FieldKind::Stdin))
+        .map(|f| &f.ident)
+        .collect::<Vec<_>>();
+    if stdin.len() > 1 {
+        return Err(Error::new(
+            stdin[1].span(),
+            "only a single field can be passed to stdin",
+        ));
+    }
+    let stdin = stdin.first().copied();
+    let spelling = fields
+        .iter()
+        .find(|f| matches!(f.kind, FieldKind::Spelling))
+        .map(|f| &f.ident);
+    if let (Some(spelling), None) = (spelling, &long_options) {
+        return Err(Error::new(
+            spelling.span(),
+            "abbreviating options needs all long options of the utility, list them with \
+             `#[util_input(long_options(..))]`",
+        ));
+    }
+
+    let display = expand_display(krate, name, &fields, stdin);
+    let extracts = expand_extracts_to_command(
+        krate,
+        name,
+        &fields,
+        long_options.as_deref().unwrap_or_default(),
+        stdin,
+        spelling,
+    );
+    let byte_fields = stdin.map(|stdin| expand_byte_fields(krate, name, stdin));
+    let len = expand_len(krate, name, stdin);
+    let shrinkable = expand_shrinkable(krate, name, &fields, stdin);
+    let generator = expand_generator(krate, name, vis, &prefix, &fields, stdin);
+    let mutators = expand_mutators(krate, name, vis, &prefix, &fields, spelling);
+
+    Ok(quote! {
+        impl ::libafl::inputs::Input for #name {
+            fn generate_name(&self, _id: ::core::option::Option<::libafl::corpus::CorpusId>) -> ::std::string::String {
+                use ::std::hash::{Hash, Hasher};
+                let mut hasher = ::std::hash::DefaultHasher::new();
+                self.hash(&mut hasher);
+                ::std::format!("{:016x}", hasher.finish())
+            }
+        }
+
+        #display
+        #extracts
+        #byte_fields
+        #len
+        #shrinkable
+        #generator
+        #mutators
+    })
+}
+
+/// The arguments of `#[util_input(..)]`.
+struct UtilInputArgs {
+    /// the path of the crate containing the `generic` module, set with `crate = path`
+    krate: Path,
+    /// all long options of the utility, set with `long_options(..)`
+    long_options: Option<Vec<LitStr>>,
+}
+
+fn parse_util_input(input: &DeriveInput) -> syn::Result<UtilInputArgs> {
+    let mut args = UtilInputArgs {
+        krate: syn::parse_quote!(crate),
+        long_options: None,
+    };
+    for attr in &input.attrs {
+        if attr.path().is_ident("util_input") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("crate") {
+                    args.krate = meta.value()?.parse()?;
+                    Ok(())
+                } else if meta.path.is_ident("long_options") {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    let long_options =
+                        content.parse_terminated(<LitStr as Parse>::parse, Token![,])?;
+                    args.long_options = Some(long_options.into_iter().collect());
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `crate` or `long_options`"))
+                }
+            })?;
+        }
+    }
+    Ok(args)
+}
+
+fn parse_fields(input: &DeriveInput, krate: &Path) -> syn::Result<Vec<InputField>> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new(
+            input.ident.span(),
+            "UtilInput can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new(
+            input.ident.span(),
+            "UtilInput can only be derived for structs with named fields",
+        ));
+    };
+    fields
+        .named
+        .iter()
+        .map(|field| {
+            let ident = field
+                .ident
+                .clone()
+                .expect("named fields have an identifier");
+            let mut kind = FieldKind::Other;
+            for attr in &field.attrs {
+                let parsed = if attr.path().is_ident("stdin") {
+                    attr.meta.require_path_only()?;
+                    FieldKind::Stdin
+                } else if attr.path().is_ident("flag") {
+                    FieldKind::Flag(attr.parse_args()?)
+                } else if attr.path().is_ident("spelling") {
+                    attr.meta.require_path_only()?;
+                    FieldKind::Spelling
+                } else if attr.path().is_ident("opt") {
+                    let args: OptArgs = attr.parse_args()?;
+                    let value_type = option_inner(&field.ty).ok_or_else(|| {
+                        Error::new(
+                            ident.span(),
+                            "fields marked with #[opt] must be an `Option`",
+                        )
+                    })?;
+                    let range = args.range.unwrap_or_else(
+                        || syn::parse_quote!(<#value_type as #krate::generic::options::OptionValue>::full_range()),
+                    );
+                    FieldKind::Opt {
+                        spellings: args.spellings,
+                        value_type: Box::new(value_type),
+                        range: Box::new(range),
+                    }
+                } else {
+                    continue;
+                };
+                if !matches!(kind, FieldKind::Other) {
+                    return Err(Error::new(
+                        ident.span(),
+                        "a field can only have one of #[stdin], #[flag], #[opt] and #[spelling]",
+                    ));
+                }
+                kind = parsed;
+            }
+            Ok(InputField { ident, kind })
+        })
+        .collect()
+}
+
+/// The `T` in `Option<T>`
+fn option_inner(ty: &Type) -> Option<Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(inner) => Some(inner.clone()),
+        _ => None,
+    }
+}
+
+fn expand_display(
+    krate: &Path,
+    name: &Ident,
+    fields: &[InputField],
+    stdin: Option<&Ident>,
+) -> TokenStream2 {
+    let stdin = stdin.map(|stdin| {
+        let label = stdin.to_string();
+        quote! {
+            ::core::write!(
+                f,
+                "{}: '{}'",
+                #label,
+                #krate::generic::stdio::vec_string_mapper(&Some(self.#stdin.clone()))
+            )?;
+            separator = ", ";
+        }
+    });
+    let options = fields.iter().filter_map(|field| {
+        let ident = &field.ident;
+        let label = ident.to_string();
+        match &field.kind {
+            FieldKind::Flag(_) => Some(quote! {
+                if self.#ident {
+                    ::core::write!(f, "{}{}", separator, #label)?;
+                    separator = ", ";
+                }
+            }),
+            FieldKind::Opt { .. } => Some(quote! {
+                if let Some(value) = &self.#ident {
+                    ::core::write!(f, "{}{}: {}", separator, #label, value)?;
+                    separator = ", ";
+                }
+            }),
+            FieldKind::Stdin | FieldKind::Spelling | FieldKind::Other => None,
+        }
+    });
+    quote! {
+        impl ::std::fmt::Display for #name {
+            #[allow(unused_assignments)]
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                let mut separator = "";
+                #stdin
+                #(#options)*
+                Ok(())
+            }
+        }
+    }
+}
+
+fn expand_extracts_to_command(
+    krate: &Path,
+    name: &Ident,
+    fields: &[InputField],
+    declared_long_options: &[LitStr],
+    stdin: Option<&Ident>,
+    spelling: Option<&Ident>,
+) -> TokenStream2 {
+    let get_stdin = match stdin {
+        Some(stdin) => quote! { &self.#stdin },
+        None => quote! {
+            static EMPTY: ::std::vec::Vec<u8> = ::std::vec::Vec::new();
+            &EMPTY
+        },
+    };
+    let long_options = fields
+        .iter()
+        .filter_map(|field| match &field.kind {
+            FieldKind::Flag(spellings) | FieldKind::Opt { spellings, .. } => {
+                spellings.long.as_ref()
+            }
+            _ => None,
+        })
+        .chain(declared_long_options)
+        .map(LitStr::value)
+        .collect::<BTreeSet<_>>();
+    let long_options = long_options.iter();
+    let options = fields.iter().filter_map(|field| {
+        let ident = &field.ident;
+        let option_arg = |spellings: &Spellings, value: TokenStream2| {
+            let short = option_tokens(&spellings.short);
+            let long = option_tokens(&spellings.long);
+            quote! {
+                #krate::generic::spelling::OptionArg {
+                    short: #short,
+                    long: #long,
+                    unique_prefix: #long.map_or(0, |long| {
+                        #krate::generic::spelling::unique_prefix_len(long, LONG_OPTIONS)
+                    }),
+                    value: #value,
+                }
+            }
+        };
+        match &field.kind {
+            FieldKind::Flag(spellings) => {
+                let option = option_arg(spellings, quote! { None });
+                Some(quote! {
+                    if self.#ident {
+                        options.push(#option);
+                    }
+                })
+            }
+            FieldKind::Opt { spellings, .. } => {
+                let option = option_arg(spellings, quote! { Some(value.as_slice()) });
+                Some(quote! {
+                    let value = self
+                        .#ident
+                        .as_ref()
+                        .map(#krate::generic::options::OptionValue::to_arg);
+                    if let Some(value) = &value {
+                        options.push(#option);
+                    }
+                })
+            }
+            FieldKind::Stdin | FieldKind::Spelling | FieldKind::Other => None,
+        }
+    });
+    let spelling_value = match spelling {
+        Some(spelling) => quote! { self.#spelling },
+        None => quote! { 0 },
+    };
+    let spelling_impl = match spelling {
+        Some(spelling) => quote! {
+            impl #krate::generic::spelling::HasArgSpelling for #name {
+                fn spelling_mut(&mut self) -> Option<&mut u64> {
+                    Some(&mut self.#spelling)
+                }
+            }
+        },
+        None => quote! {
+            impl #krate::generic::spelling::HasArgSpelling for #name {}
+        },
+    };
+    quote! {
+        impl #krate::generic::executor::ExtractsToCommand for #name {
+            fn get_stdin(&self) -> &::std::vec::Vec<u8> {
+                #get_stdin
+            }
+
+            fn get_args<'a>(&self) -> ::std::vec::Vec<::std::borrow::Cow<'a, ::std::ffi::OsStr>> {
+                use ::std::os::unix::ffi::OsStringExt;
+                const LONG_OPTIONS: &[&str] = &[#(#long_options),*];
+                let mut options = ::std::vec::Vec::new();
+                #(#options)*
+                #krate::generic::spelling::render_args(&options, &[], #spelling_value)
+                    .into_iter()
+                    .map(|a| ::std::borrow::Cow::Owned(::std::ffi::OsString::from_vec(a)))
+                    .collect()
+            }
+        }
+
+        #spelling_impl
+    }
+}
+
+fn option_tokens(spelling: &Option<LitStr>) -> TokenStream2 {
+    match spelling {
+        Some(spelling) => quote! { Some(#spelling) },
+        None => quote! { None::<&str> },
+    }
+}
+
+fn expand_byte_fields(krate: &Path, name: &Ident, stdin: &Ident) -> TokenStream2 {
+    let label = stdin.to_string();
+    quote! {
+        impl #krate::generic::mapping::HasByteFields for #name {
+            fn byte_fields(&self) -> ::std::vec::Vec<(::std::borrow::Cow<'static, str>, &::std::vec::Vec<u8>)> {
+                ::std::vec![(::std::borrow::Cow::Borrowed(#label), &self.#stdin)]
+            }
+
+            fn byte_fields_mut(&mut self) -> ::std::vec::Vec<(::std::borrow::Cow<'static, str>, &mut ::std::vec::Vec<u8>)> {
+                ::std::vec![(::std::borrow::Cow::Borrowed(#label), &mut self.#stdin)]
+            }
+        }
+    }
+}
+
+/// `HasLen` is the length of `stdin`, or that of the arguments if there is none.
+fn expand_len(krate: &Path, name: &Ident, stdin: Option<&Ident>) -> TokenStream2 {
+    let len = match stdin {
+        Some(stdin) => quote! { self.#stdin.len() },
+        None => quote! {
+            <Self as #krate::generic::executor::ExtractsToCommand>::get_args(self)
+                .iter()
+                .map(|arg| arg.len())
+                .sum()
+        },
+    };
+    quote! {
+        impl ::libafl_bolts::HasLen for #name {
+            fn len(&self) -> usize {
+                #len
+            }
+        }
+    }
+}
+
+fn expand_shrinkable(
+    krate: &Path,
+    name: &Ident,
+    fields: &[InputField],
+    stdin: Option<&Ident>,
+) -> TokenStream2 {
+    let without = fields.iter().filter_map(|field| {
+        let ident = &field.ident;
+        match &field.kind {
+            FieldKind::Flag(_) => Some(quote! {
+                if self.#ident {
+                    let mut candidate = self.clone();
+                    candidate.#ident = false;
+                    candidates.push(candidate);
+                }
+            }),
+            FieldKind::Opt { .. } => Some(quote! {
+                if self.#ident.is_some() {
+                    let mut candidate = self.clone();
+                    candidate.#ident = None;
+                    candidates.push(candidate);
+                }
+            }),
+            FieldKind::Stdin | FieldKind::Spelling | FieldKind::Other => None,
+        }
+    });
+    let stdin = stdin.map(|stdin| quote! { fields.push(&mut self.#stdin); });
+    let values = fields.iter().filter_map(|field| {
+        let ident = &field.ident;
+        matches!(field.kind, FieldKind::Opt { .. }).then(|| {
+            quote! {
+                if let Some(value) = &mut self.#ident {
+                    fields.extend(#krate::generic::options::OptionValue::bytes_mut(value));
+                }
+            }
+        })
+    });
+    quote! {
+        impl #krate::generic::minimise::Shrinkable for #name {
+            fn without_parts(&self) -> ::std::vec::Vec<Self> {
+                let mut candidates = ::std::vec::Vec::new();
+                #(#without)*
+                candidates
+            }
+
+            fn shrinkable_fields_mut(&mut self) -> ::std::vec::Vec<&mut ::std::vec::Vec<u8>> {
+                let mut fields = ::std::vec::Vec::new();
+                #stdin
+                #(#values)*
+                fields
+            }
+        }
+    }
+}
+
+fn expand_generator(
+    krate: &Path,
+    name: &Ident,
+    vis: &syn::Visibility,
+    prefix: &str,
+    fields: &[InputField],
+    stdin: Option<&Ident>,
+) -> TokenStream2 {
+    let generator = format_ident!("{}Generator", prefix);
+    let values = fields.iter().map(|field| {
+        let ident = &field.ident;
+        match &field.kind {
+            FieldKind::Stdin => quote! {
+                #ident: {
+                    let size = rand.between(self.min_size, self.max_size);
+                    #krate::generic::options::generate_bytes(rand, size, &self.generator_type)
+                }
+            },
+            FieldKind::Flag(_) => quote! { #ident: rand.coinflip(0.5) },
+            FieldKind::Spelling => quote! {
+                #ident: if rand.coinflip(0.5) { rand.next() } else { 0 }
+            },
+            FieldKind::Opt {
+                value_type, range, ..
+            } => quote! {
+                #ident: rand.coinflip(0.5).then(|| {
+                    <#value_type as #krate::generic::options::OptionValue>::generate(rand, #range)
+                })
+            },
+            FieldKind::Other => quote! { #ident: ::core::default::Default::default() },
+        }
+    });
+    let definition = if stdin.is_some() {
+        quote! {
+            #vis struct #generator {
+                min_size: usize,
+                max_size: usize,
+                generator_type: #krate::generic::options::GeneratorType,
+            }
+
+            impl #generator {
+                #vis fn new(
+                    min_size: usize,
+                    max_size: usize,
+                    generator_type: #krate::generic::options::GeneratorType,
+                ) -> Self {
+                    Self {
+                        min_size,
+                        max_size,
+                        generator_type,
+                    }
+                }
+            }
+        }
+    } else {
+        quote! {
+            #vis struct #generator;
+
+            impl #generator {
+                #vis fn new() -> Self {
+                    Self
+                }
+            }
+        }
+    };
+    quote! {
+        #definition
+
+        impl<S> ::libafl::generators::Generator<#name, S> for #generator
+        where
+            S: ::libafl::state::HasRand,
+        {
+            fn generate(&mut self, state: &mut S) -> ::core::result::Result<#name, ::libafl::Error> {
+                use ::libafl_bolts::prelude::Rand;
+                let rand = state.rand_mut();
+                Ok(#name {
+                    #(#values),*
+                })
+            }
+        }
+    }
+}
+
+fn expand_mutators(
+    krate: &Path,
+    name: &Ident,
+    vis: &syn::Visibility,
+    prefix: &str,
+    fields: &[InputField],
+    spelling: Option<&Ident>,
+) -> TokenStream2 {
+    let mut definitions = Vec::new();
+    let mut mutators = Vec::new();
+    for field in fields {
+        let ident = &field.ident;
+        let camel = to_camel_case(&ident.to_string());
+        match &field.kind {
+            FieldKind::Flag(_) => {
+                let mutator = format_ident!("{}Flip{}Mutator", prefix, camel);
+                definitions.push(mutator_definition(
+                    name,
+                    vis,
+                    &mutator,
+                    quote! {
+                        input.#ident = !input.#ident;
+                        Ok(::libafl::mutators::MutationResult::Mutated)
+                    },
+                ));
+                mutators.push(quote! { #mutator });
+            }
+            FieldKind::Opt {
+                value_type, range, ..
+            } => {
+                let flip = format_ident!("{}Flip{}Mutator", prefix, camel);
+                definitions.push(mutator_definition(
+                    name,
+                    vis,
+                    &flip,
+                    quote! {
+                        input.#ident = match input.#ident {
+                            None => Some(<#value_type as #krate::generic::options::OptionValue>::generate(
+                                state.rand_mut(),
+                                #range,
+                            )),
+                            Some(_) => None,
+                        };
+                        Ok(::libafl::mutators::MutationResult::Mutated)
+                    },
+                ));
+                let content = format_ident!("{}{}ContentMutator", prefix, camel);
+                definitions.push(mutator_definition(
+                    name,
+                    vis,
+                    &content,
+                    quote! {
+                        match &mut input.#ident {
+                            Some(value) => {
+                                #krate::generic::options::OptionValue::mutate(value, state.rand_mut(), #range);
+                                Ok(::libafl::mutators::MutationResult::Mutated)
+                            }
+                            None => Ok(::libafl::mutators::MutationResult::Skipped),
+                        }
+                    },
+                ));
+                mutators.push(quote! { #flip });
+                mutators.push(quote! { #content });
+            }
+            FieldKind::Stdin | FieldKind::Spelling | FieldKind::Other => {}
+        }
+    }
+    if spelling.is_some() {
+        mutators.push(quote! { #krate::generic::spelling::SpellingMutator });
+    }
+    let tuple_type = mutators
+        .iter()
+        .rev()
+        .fold(quote! { () }, |tail, m| quote! { (#m, #tail) });
+    let tuple = mutators
+        .iter()
+        .rev()
+        .fold(quote! { () }, |tail, m| quote! { (#m, #tail) });
+    let type_name = format_ident!("{}OptionMutatorsType", prefix);
+    let function = format_ident!("{}_option_mutators", to_snake_case(prefix));
+    quote! {
+        #(#definitions)*
+
+        #vis type #type_name = #tuple_type;
+
+        #vis fn #function() -> #type_name {
+            #tuple
+        }
+    }
+}
+
+fn mutator_definition(
+    name: &Ident,
+    vis: &syn::Visibility,
+    mutator: &Ident,
+    body: TokenStream2,
+) -> TokenStream2 {
+    let label = mutator.to_string();
+    quote! {
+        #vis struct #mutator;
+
+        impl<S> ::libafl::mutators::Mutator<#name, S> for #mutator
+        where
+            S: ::libafl::state::HasRand,
+        {
+            #[allow(unused_variables)]
+            fn mutate(
+                &mut self,
+                state: &mut S,
+                input: &mut #name,
+            ) -> ::core::result::Result<::libafl::mutators::MutationResult, ::libafl::Error> {
+                #body
+            }
+        }
+
+        impl ::libafl_bolts::Named for #mutator {
+            fn name(&self) -> &::std::borrow::Cow<'static, str> {
+                &::std::borrow::Cow::Borrowed(#label)
+            }
+        }
+    }
+}
+
+fn to_camel_case(snake: &str) -> String {
+    snake
+        .split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn to_snake_case(camel: &str) -> String {
+    let mut snake = String::with_capacity(camel.len() + 4);
+    for (i, c) in camel.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
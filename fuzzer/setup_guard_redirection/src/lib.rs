@@ -2,11 +2,13 @@
 
 use core::slice;
 use std::{
-    ffi::CStr,
+    ffi::{CStr, CString},
     fmt::Debug,
-    fs::OpenOptions,
-    io::Write,
-    mem::{size_of, transmute_copy},
+    fs::{File, OpenOptions},
+    io::{Read, Write},
+    iter::once,
+    mem::{size_of, transmute_copy, ManuallyDrop},
+    os::fd::FromRawFd,
     panic::{catch_unwind, AssertUnwindSafe},
     ptr::null,
     time::{SystemTime, UNIX_EPOCH},
@@ -17,7 +19,11 @@ use libafl_bolts::{
     AsSliceMut,
 };
 
-use libc::{c_void, dlerror, dlsym, RTLD_DEFAULT, RTLD_NEXT};
+use libc::{
+    __errno_location, _exit, c_uint, c_void, chdir, close, dlerror, dlsym, dup2, fork, getenv,
+    kill, open, pid_t, prctl, setpgid, unsetenv, waitpid, EINTR, O_CREAT, O_RDONLY, O_TRUNC,
+    O_WRONLY, PR_SET_PDEATHSIG, RTLD_DEFAULT, RTLD_NEXT, SIGKILL,
+};
 
 pub unsafe fn get_symbol<T>(name: &CStr, search_global: bool) -> T {
     assert_eq!(
@@ -53,6 +59,17 @@ pub type LibcStartMainFunc = fn(
     *mut c_void,
 ) -> i32;
 
+/// Set for binaries without coverage instrumentation, which are not passed a shared memory description.
+const NO_COVERAGE_ENV: &CStr = c"COREUTILS_DIFFERENTIAL_NO_COVERAGE";
+/// If set, the binary under test becomes a fork server, see `FORK_SERVER_ENV` in the fuzzer.
+const FORK_SERVER_ENV: &CStr = c"COREUTILS_DIFFERENTIAL_FORK_SERVER";
+/// The fork server reads test cases from this file descriptor
+const CONTROL_FD: i32 = 198;
+/// The fork server reports the pid and wait status of each child to this file descriptor
+const STATUS_FD: i32 = 199;
+/// Sent once the fork server is ready to receive test cases
+const FORK_SERVER_HELLO: i32 = i32::from_ne_bytes(*b"fsrv");
+
 static mut SHMEM_DESCRIPTION: Option<ShMemDescription> = None;
 static mut RTLD_FINI: Option<extern "C" fn()> = None;
 
@@ -105,7 +122,10 @@ fn log<T: Debug>(s: T) {
 #[no_mangle]
 unsafe fn write_guards() {
     let result = catch_unwind(AssertUnwindSafe(|| {
-        let shmem_description = SHMEM_DESCRIPTION.expect("No shared memory descriptor was saved");
+        // the binary is not instrumented
+        let Some(shmem_description) = SHMEM_DESCRIPTION else {
+            return;
+        };
         let mut shmem = MmapShMemProvider::default()
             .shmem_from_description(shmem_description)
             .expect("Could not acquire shared memory");
@@ -141,18 +161,157 @@ unsafe fn write_guards() {
     RTLD_FINI.expect("Did not previously store a reference to the original rtld_fini function")();
 }
 
+/// Waits for the child `pid` to terminate and returns its wait status, or exits if that fails.
+unsafe fn wait_for(pid: pid_t) -> i32 {
+    let mut status = 0;
+    while waitpid(pid, &mut status, 0) < 0 {
+        if *__errno_location() != EINTR {
+            _exit(125);
+        }
+    }
+    status
+}
+
+/// Reads a native endian `u32` from the control pipe, the number or length of the fields of a test case
+fn read_u32(control: &mut File) -> Option<u32> {
+    let mut bytes = [0; 4];
+    control.read_exact(&mut bytes).ok()?;
+    Some(u32::from_ne_bytes(bytes))
+}
+
+/// Reads a test case from the control pipe: the number of fields, followed by each field prefixed by its length.
+/// The fields are the working directory (empty to keep the fork server's), the files to use as `stdin`, `stdout` and
+/// `stderr`, and the arguments including the program name. `None` once the fuzzer closed the pipe.
+fn read_request(control: &mut File) -> Option<Vec<CString>> {
+    let count = read_u32(control)?;
+    (0..count)
+        .map(|_| {
+            let mut field = vec![0; read_u32(control)? as usize];
+            control.read_exact(&mut field).ok()?;
+            CString::new(field).ok()
+        })
+        .collect()
+}
+
+/// Zeroes the guards hit so far. Instrumented binaries initialise their guards in a constructor, which only runs in
+/// the child, so this only matters for binaries that already ran instrumented code before `main` was reached.
+unsafe fn reset_guards() {
+    // the binary is not instrumented
+    let Some(_) = SHMEM_DESCRIPTION else {
+        return;
+    };
+    let get_guard_count: fn() -> usize = get_symbol(c"get_guard_count", true);
+    let get_guard_values: fn() -> *mut u32 = get_symbol(c"get_guard_values", true);
+    let guards = get_guard_values();
+    if !guards.is_null() {
+        slice::from_raw_parts_mut(guards, get_guard_count()).fill(0);
+    }
+}
+
+/// Sets up the working directory and standard streams of a test case, see [`read_request`], and returns its
+/// arguments as `argc` and `argv`. Exits with 125 like the namespace runner if that fails.
+unsafe fn enter_test_case(request: Vec<CString>) -> (i32, *mut *const char) {
+    let [cwd, stdin, stdout, stderr, args @ ..] = request.as_slice() else {
+        log("Received a malformed test case from the fuzzer");
+        _exit(125);
+    };
+    if !cwd.is_empty() && chdir(cwd.as_ptr()) != 0 {
+        log(format!("Could not change to the directory {cwd:?}"));
+        _exit(125);
+    }
+    for (path, fd, flags) in [
+        (stdin, 0, O_RDONLY),
+        (stdout, 1, O_WRONLY | O_CREAT | O_TRUNC),
+        (stderr, 2, O_WRONLY | O_CREAT | O_TRUNC),
+    ] {
+        let opened = open(path.as_ptr(), flags, 0o600 as c_uint);
+        if opened < 0 || dup2(opened, fd) < 0 {
+            log(format!("Could not open {path:?} as file descriptor {fd}"));
+            _exit(125);
+        }
+        close(opened);
+    }
+
+    let argc = args.len().try_into().expect("Too many arguments");
+    let argv = args
+        .iter()
+        .map(|arg| arg.clone().into_raw() as *const char)
+        .chain(once(null()))
+        .collect::<Vec<_>>();
+    (argc, argv.leak().as_mut_ptr())
+}
+
+/// Turns the process into a fork server, which avoids running `exec` and the dynamic linker for every test case.
+/// Stopped before `main`, it forks a child per test case read from [`CONTROL_FD`], reports the child's pid to
+/// [`STATUS_FD`], and after waiting for it its wait status. Only returns in the children, with the `argc` and `argv`
+/// of their test case, and exits once the fuzzer closes the control pipe.
+unsafe fn serve_forks() -> (i32, *mut *const char) {
+    // do not outlive the fuzzer
+    prctl(PR_SET_PDEATHSIG, SIGKILL);
+
+    // closed explicitly in the children, dropping them there would close the file descriptors a second time
+    let mut control = ManuallyDrop::new(File::from_raw_fd(CONTROL_FD));
+    let mut status_pipe = ManuallyDrop::new(File::from_raw_fd(STATUS_FD));
+    if status_pipe
+        .write_all(&FORK_SERVER_HELLO.to_ne_bytes())
+        .is_err()
+    {
+        _exit(125);
+    }
+
+    loop {
+        let Some(request) = read_request(&mut control) else {
+            _exit(0);
+        };
+        let pid = fork();
+        if pid < 0 {
+            log("Could not fork a child of the fork server");
+            _exit(125);
+        }
+        if pid == 0 {
+            close(CONTROL_FD);
+            close(STATUS_FD);
+            // do not outlive the fork server if it is killed
+            prctl(PR_SET_PDEATHSIG, SIGKILL);
+            // so the fuzzer can kill the processes it spawns on timeout, which do not inherit the death signal
+            setpgid(0, 0);
+            reset_guards();
+            return enter_test_case(request);
+        }
+
+        if status_pipe.write_all(&pid.to_ne_bytes()).is_err() {
+            kill(-pid, SIGKILL);
+            _exit(0);
+        }
+        let status = wait_for(pid);
+        if status_pipe.write_all(&status.to_ne_bytes()).is_err() {
+            _exit(0);
+        }
+    }
+}
+
 #[no_mangle]
 #[allow(clippy::similar_names)]
 pub unsafe extern "C" fn __libc_start_main(
     main: unsafe extern "C" fn(i32, *const *const u8, *const *const u8) -> i32,
     mut argc: i32,
-    argv: *mut *const char,
+    mut argv: *mut *const char,
     init: extern "C" fn(i32, *const *const u8, *const *const u8) -> i32,
     fini: extern "C" fn(),
     rtld_fini: extern "C" fn(),
     stack_end: *mut c_void,
 ) -> i32 {
-    extract_shmem_description(&mut argc, argv);
+    if getenv(NO_COVERAGE_ENV.as_ptr()).is_null() {
+        extract_shmem_description(&mut argc, argv);
+    } else {
+        unsetenv(NO_COVERAGE_ENV.as_ptr());
+    }
+    // do not inject this library into children, e.g. the command run by wrappers like `env` or `timeout`
+    unsetenv(c"LD_PRELOAD".as_ptr());
+    if !getenv(FORK_SERVER_ENV.as_ptr()).is_null() {
+        unsetenv(FORK_SERVER_ENV.as_ptr());
+        (argc, argv) = serve_forks();
+    }
     RTLD_FINI = Some(rtld_fini);
     let orig_libc_start_main: LibcStartMainFunc = get_symbol(c"__libc_start_main", false);
     orig_libc_start_main(main, argc, argv, init, fini, write_guards, stack_end)
@@ -0,0 +1,163 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use libafl_bolts::cli::FuzzerOptions;
+
+use crate::{
+    fs_altering::FsAlteringUtil,
+    generic::{exit_status::ExitStatusEquivalence, stdio::StdoutPolicy},
+    wrapper::WrapperUtil,
+};
+
+/// Command line interface of the fuzzer. Extends LibAFL's [`FuzzerOptions`] with the options specific to coreutils.
+#[derive(Parser, Debug)]
+#[command(
+    name = "coreutils_differential",
+    about = "Differential fuzzer for GNU's and uutils' coreutils"
+)]
+pub struct Cli {
+    /// The utility to fuzz
+    #[arg(long, value_enum, default_value_t = Util::Base64)]
+    pub util: Util,
+
+    /// The implementations to run, one to fuzz it alone or two to compare them, see `--implementations`
+    #[arg(long = "impl", value_delimiter = ',', default_values = ["uutils", "gnu"])]
+    pub impls: Vec<String>,
+
+    /// The implementation whose behaviour the reproducers of divergences expect, the second one of `--impl` by
+    /// default
+    #[arg(long)]
+    pub reference: Option<String>,
+
+    /// The named implementations `--impl` chooses from, with their binaries, preloads, coverage and environment
+    #[arg(long, default_value = "implementations.json")]
+    pub implementations: PathBuf,
+
+    /// Fuzz the utility described by a spec file instead of `--util`, see `specs/`
+    #[arg(long)]
+    pub spec: Option<PathBuf>,
+
+    /// Which exit codes of the implementations are considered equal: `exact`, `nonzero` (all non-zero codes are
+    /// equal) or groups of equal codes like `1:2,125:126:127`
+    #[arg(long, default_value = "exact")]
+    pub exit_status: ExitStatusEquivalence,
+
+    /// Rules to normalise and classify error messages before comparing them, see `stderr_rules.json`
+    #[arg(long, default_value = "stderr_rules.json")]
+    pub stderr_rules: PathBuf,
+
+    /// How stdout is compared if an implementation writes to stderr, defaults to the utility's policy
+    #[arg(long, value_enum)]
+    pub stdout_policy: Option<StdoutPolicy>,
+
+    /// Known divergences which are not saved again, see `suppressions.example.json`
+    #[arg(long)]
+    pub suppressions: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub options: FuzzerOptions,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// What to do instead of fuzzing
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Run saved inputs against both implementations and print how they behave
+    Replay {
+        /// An input file, or a directory searched for input files, like a bucket or the corpus
+        path: PathBuf,
+    },
+    /// Re-execute the representatives of all buckets and report which divergences are fixed
+    Recheck {
+        /// The directory holding the buckets, the output directory by default
+        dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Util {
+    Base64,
+    Ls,
+    Stat,
+    Du,
+    Tsort,
+    Ptx,
+    Env,
+    Nice,
+    Nohup,
+    Timeout,
+    Stdbuf,
+    Cp,
+    Mv,
+    Rm,
+    Ln,
+    Mkdir,
+    Chmod,
+    Touch,
+    Truncate,
+}
+
+impl Util {
+    /// The name of the binary implementing this utility
+    pub fn binary_name(&self) -> &'static str {
+        match self {
+            Util::Base64 => "base64",
+            Util::Ls => "ls",
+            Util::Stat => "stat",
+            Util::Du => "du",
+            Util::Tsort => "tsort",
+            Util::Ptx => "ptx",
+            Util::Env => "env",
+            Util::Nice => "nice",
+            Util::Nohup => "nohup",
+            Util::Timeout => "timeout",
+            Util::Stdbuf => "stdbuf",
+            Util::Cp => "cp",
+            Util::Mv => "mv",
+            Util::Rm => "rm",
+            Util::Ln => "ln",
+            Util::Mkdir => "mkdir",
+            Util::Chmod => "chmod",
+            Util::Touch => "touch",
+            Util::Truncate => "truncate",
+        }
+    }
+
+    /// How stdout is compared by default
+    pub fn stdout_policy(&self) -> StdoutPolicy {
+        match self {
+            // GNU writes what it decoded before reporting invalid input
+            Util::Base64 => StdoutPolicy::PrefixTolerant,
+            _ => StdoutPolicy::IgnoreOnError,
+        }
+    }
+
+    /// The utility as an [`FsAlteringUtil`], if it alters the tree it is run in
+    pub fn fs_altering(&self) -> Option<FsAlteringUtil> {
+        match self {
+            Util::Cp => Some(FsAlteringUtil::Cp),
+            Util::Mv => Some(FsAlteringUtil::Mv),
+            Util::Rm => Some(FsAlteringUtil::Rm),
+            Util::Ln => Some(FsAlteringUtil::Ln),
+            Util::Mkdir => Some(FsAlteringUtil::Mkdir),
+            Util::Chmod => Some(FsAlteringUtil::Chmod),
+            Util::Touch => Some(FsAlteringUtil::Touch),
+            Util::Truncate => Some(FsAlteringUtil::Truncate),
+            _ => None,
+        }
+    }
+
+    /// The utility as a [`WrapperUtil`], if it wraps another command
+    pub fn wrapper(&self) -> Option<WrapperUtil> {
+        match self {
+            Util::Env => Some(WrapperUtil::Env),
+            Util::Nice => Some(WrapperUtil::Nice),
+            Util::Nohup => Some(WrapperUtil::Nohup),
+            Util::Timeout => Some(WrapperUtil::Timeout),
+            Util::Stdbuf => Some(WrapperUtil::Stdbuf),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use core::fmt;
+use std::{
+    borrow::Cow,
+    ffi::{OsStr, OsString},
+    fmt::{Display, Formatter},
+    hash::{DefaultHasher, Hash, Hasher},
+    ops::RangeInclusive,
+    os::unix::ffi::{OsStrExt, OsStringExt},
+};
+
+use serde::{Deserialize, Serialize};
+
+use libafl::{
+    corpus::CorpusId,
+    generators::Generator,
+    inputs::Input,
+    mutators::{MutationResult, Mutator},
+    state::HasRand,
+    Error, SerdeAny,
+};
+
+use libafl_bolts::{
+    prelude::Rand,
+    tuples::{tuple_list_type, Append},
+    HasLen, Named,
+};
+
+use crate::generic::{
+    executor::ExtractsToCommand,
+    fixture::{
+        fixture_mutators, generate_fixture, generate_operand, Fixture, FixtureAddEntryMutator,
+        FixtureAttributeMutator, FixtureOperandMutator, FixtureRemoveEntryMutator, HasFixture,
+    },
+    minimise::{without_each, without_set_fields, Shrinkable},
+    numeric::NumericArg,
+    options::OptionValue,
+    spelling::HasArgSpelling,
+    stdio::args_string_mapper,
+};
+
+const BLOCK_SIZES: [&str; 6] = ["1", "512", "1K", "1M", "KB", "human-readable"];
+
+const MAX_DEPTHS: RangeInclusive<i128> = 0..=3;
+
+/// An [`Input`] implementation for coreutils' `du`
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, SerdeAny)]
+pub struct DuInput {
+    pub fixture: Fixture,
+    pub operands: Vec<Vec<u8>>,
+    pub all: bool,
+    pub apparent_size: bool,
+    pub bytes: bool,
+    pub summarize: bool,
+    pub human_readable: bool,
+    pub count_links: bool,
+    pub dereference: bool,
+    pub null: bool,
+    pub max_depth: Option<NumericArg>,
+    pub block_size: Option<String>,
+}
+
+impl Display for DuInput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "fixture: {}, args: {}",
+            self.fixture,
+            args_string_mapper(&self.get_args())
+        )
+    }
+}
+
+impl Input for DuInput {
+    fn generate_name(&self, _id: Option<CorpusId>) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+impl ExtractsToCommand for DuInput {
+    fn get_stdin(&self) -> &Vec<u8> {
+        static EMPTY: Vec<u8> = Vec::new();
+        &EMPTY
+    }
+
+    fn get_args<'a>(&self) -> Vec<Cow<'a, OsStr>> {
+        let mut args = Vec::with_capacity(11 + self.operands.len());
+        for (enabled, flag) in [
+            (self.all, "-a"),
+            (self.apparent_size, "--apparent-size"),
+            (self.bytes, "-b"),
+            (self.summarize, "-s"),
+            (self.human_readable, "-h"),
+            (self.count_links, "-l"),
+            (self.dereference, "-L"),
+            (self.null, "-0"),
+        ] {
+            if enabled {
+                args.push(Cow::Borrowed(OsStr::new(flag)))
+            }
+        }
+        if let Some(depth) = &self.max_depth {
+            let mut arg = b"--max-depth=".to_vec();
+            arg.extend(depth.to_arg());
+            args.push(Cow::Owned(OsString::from_vec(arg)))
+        }
+        if let Some(block_size) = &self.block_size {
+            args.push(Cow::Owned(OsString::from(format!(
+                "--block-size={block_size}"
+            ))))
+        }
+        // fixture names may start with a dash
+        args.push(Cow::Borrowed(OsStr::new("--")));
+        args.extend(
+            self.operands
+                .iter()
+                .map(|o| Cow::Owned(OsStr::from_bytes(o).to_os_string())),
+        );
+        args
+    }
+
+    fn get_fixture(&self) -> Option<&Fixture> {
+        Some(&self.fixture)
+    }
+}
+
+impl HasFixture for DuInput {
+    fn fixture(&self) -> &Fixture {
+        &self.fixture
+    }
+
+    fn fixture_mut(&mut self) -> &mut Fixture {
+        &mut self.fixture
+    }
+
+    fn operands_mut(&mut self) -> &mut Vec<Vec<u8>> {
+        &mut self.operands
+    }
+}
+
+impl HasArgSpelling for DuInput {}
+
+impl Shrinkable for DuInput {
+    fn without_parts(&self) -> Vec<Self> {
+        let mut candidates = without_set_fields!(
+            self,
+            flags: [
+                all,
+                apparent_size,
+                bytes,
+                summarize,
+                human_readable,
+                count_links,
+                dereference,
+                null
+            ],
+            options: [max_depth, block_size]
+        );
+        candidates.extend(without_each(&self.operands).map(|operands| Self {
+            operands,
+            ..self.clone()
+        }));
+        candidates
+    }
+
+    fn shrinkable_fields_mut(&mut self) -> Vec<&mut Vec<u8>> {
+        self.max_depth
+            .as_mut()
+            .map(|depth| &mut depth.0)
+            .into_iter()
+            .collect()
+    }
+}
+
+impl HasLen for DuInput {
+    fn len(&self) -> usize {
+        self.fixture.entries.len()
+    }
+}
+
+fn generate_block_size<R: Rand>(rand: &mut R) -> String {
+    BLOCK_SIZES[rand.below(BLOCK_SIZES.len())].to_string()
+}
+
+pub struct DuGenerator {
+    max_entries: usize,
+}
+
+impl DuGenerator {
+    pub fn new(max_entries: usize) -> Self {
+        Self { max_entries }
+    }
+}
+
+impl<S> Generator<DuInput, S> for DuGenerator
+where
+    S: HasRand,
+{
+    fn generate(&mut self, state: &mut S) -> Result<DuInput, Error> {
+        let rand = state.rand_mut();
+        let fixture = generate_fixture(rand, self.max_entries);
+        let operands = (0..rand.below(3))
+            .map(|_| generate_operand(rand, &fixture))
+            .collect();
+        Ok(DuInput {
+            fixture,
+            operands,
+            all: rand.coinflip(0.5),
+            apparent_size: rand.coinflip(0.3),
+            bytes: rand.coinflip(0.2),
+            summarize: rand.coinflip(0.2),
+            human_readable: rand.coinflip(0.2),
+            count_links: rand.coinflip(0.2),
+            dereference: rand.coinflip(0.2),
+            null: rand.coinflip(0.1),
+            max_depth: rand
+                .coinflip(0.3)
+                .then(|| NumericArg::generate(rand, MAX_DEPTHS)),
+            block_size: rand.coinflip(0.3).then(|| generate_block_size(rand)),
+        })
+    }
+}
+
+/// Flips or regenerates one randomly chosen option of a [`DuInput`]
+pub struct DuOptionMutator;
+impl<S> Mutator<DuInput, S> for DuOptionMutator
+where
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut DuInput) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+        match rand.below(10) {
+            0 => input.all = !input.all,
+            1 => input.apparent_size = !input.apparent_size,
+            2 => input.bytes = !input.bytes,
+            3 => input.summarize = !input.summarize,
+            4 => input.human_readable = !input.human_readable,
+            5 => input.count_links = !input.count_links,
+            6 => input.dereference = !input.dereference,
+            7 => input.null = !input.null,
+            8 => match &mut input.max_depth {
+                Some(depth) if rand.coinflip(0.5) => depth.mutate(rand, MAX_DEPTHS),
+                depth => {
+                    *depth = depth
+                        .is_none()
+                        .then(|| NumericArg::generate(rand, MAX_DEPTHS))
+                }
+            },
+            _ => {
+                input.block_size = input
+                    .block_size
+                    .is_none()
+                    .then(|| generate_block_size(rand))
+            }
+        }
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for DuOptionMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("DuOptionMutator")
+    }
+}
+
+pub fn du_mutators() -> tuple_list_type!(
+    FixtureAddEntryMutator,
+    FixtureRemoveEntryMutator,
+    FixtureAttributeMutator,
+    FixtureOperandMutator,
+    DuOptionMutator
+) {
+    fixture_mutators().append(DuOptionMutator)
+}
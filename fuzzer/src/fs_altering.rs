@@ -0,0 +1,407 @@
+use core::fmt;
+use std::{
+    borrow::Cow,
+    ffi::OsStr,
+    fmt::{Display, Formatter},
+    hash::{DefaultHasher, Hash, Hasher},
+    iter::once,
+    os::unix::ffi::OsStrExt,
+};
+
+use serde::{Deserialize, Serialize};
+
+use libafl::{
+    corpus::CorpusId,
+    generators::Generator,
+    inputs::Input,
+    mutators::{MutationResult, Mutator},
+    state::HasRand,
+    Error, SerdeAny,
+};
+
+use libafl_bolts::{
+    prelude::Rand,
+    tuples::{tuple_list_type, Append, Merge},
+    HasLen, Named,
+};
+
+use crate::generic::{
+    executor::ExtractsToCommand,
+    fixture::{
+        fixture_mutators, generate_fixture, generate_operand, Fixture, FixtureAddEntryMutator,
+        FixtureAttributeMutator, FixtureOperandMutator, FixtureRemoveEntryMutator, HasFixture,
+    },
+    mapping::{mapped_havoc_mutations, HasByteFields, MappedHavocMutationsType},
+    minimise::{without_each, Shrinkable},
+    spelling::HasArgSpelling,
+    stdio::args_string_mapper,
+};
+
+/// The utilities that alter the directory tree they are run in. They are run with the
+/// [`NamespacedCommandExecutor`](crate::generic::namespace::NamespacedCommandExecutor), and compared by the tree they
+/// leave behind.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum FsAlteringUtil {
+    Cp,
+    Mv,
+    Rm,
+    Ln,
+    Mkdir,
+    Chmod,
+    Touch,
+    Truncate,
+}
+
+/// The value an option of an [`FsAlteringUtil`] takes
+#[derive(Clone, Copy, Debug)]
+enum OptionValue {
+    None,
+    OneOf(&'static [&'static str]),
+    /// a path in the fixture, like the target directory of `cp -t`
+    Operand,
+}
+
+impl FsAlteringUtil {
+    /// The options along with the values they take, which are passed as a separate argument.
+    ///
+    /// Dates and sizes are always given explicitly, so the results do not depend on the time of execution.
+    fn options(&self) -> &'static [(&'static str, OptionValue)] {
+        match self {
+            Self::Cp => &[
+                ("-a", OptionValue::None),
+                ("-r", OptionValue::None),
+                ("-R", OptionValue::None),
+                ("-f", OptionValue::None),
+                ("-n", OptionValue::None),
+                ("-l", OptionValue::None),
+                ("-s", OptionValue::None),
+                ("-P", OptionValue::None),
+                ("-L", OptionValue::None),
+                ("-H", OptionValue::None),
+                ("-p", OptionValue::None),
+                ("-u", OptionValue::None),
+                ("-T", OptionValue::None),
+                ("-t", OptionValue::Operand),
+                ("--backup=numbered", OptionValue::None),
+                ("--backup=simple", OptionValue::None),
+                ("--suffix=.bak", OptionValue::None),
+                ("--remove-destination", OptionValue::None),
+                ("--preserve=mode", OptionValue::None),
+                ("--no-preserve=mode", OptionValue::None),
+                ("--parents", OptionValue::None),
+                ("--attributes-only", OptionValue::None),
+                ("--strip-trailing-slashes", OptionValue::None),
+                ("--sparse=always", OptionValue::None),
+            ],
+            Self::Mv => &[
+                ("-f", OptionValue::None),
+                ("-n", OptionValue::None),
+                ("-u", OptionValue::None),
+                ("-T", OptionValue::None),
+                ("-t", OptionValue::Operand),
+                ("-b", OptionValue::None),
+                ("--backup=numbered", OptionValue::None),
+                ("--suffix=.bak", OptionValue::None),
+                ("--strip-trailing-slashes", OptionValue::None),
+                ("--no-copy", OptionValue::None),
+            ],
+            Self::Rm => &[
+                ("-f", OptionValue::None),
+                ("-r", OptionValue::None),
+                ("-R", OptionValue::None),
+                ("-d", OptionValue::None),
+                ("--one-file-system", OptionValue::None),
+                ("--preserve-root=all", OptionValue::None),
+            ],
+            Self::Ln => &[
+                ("-s", OptionValue::None),
+                ("-f", OptionValue::None),
+                ("-n", OptionValue::None),
+                ("-T", OptionValue::None),
+                ("-t", OptionValue::Operand),
+                ("-r", OptionValue::None),
+                ("-L", OptionValue::None),
+                ("-P", OptionValue::None),
+                ("-b", OptionValue::None),
+                ("--backup=numbered", OptionValue::None),
+                ("--suffix=.bak", OptionValue::None),
+            ],
+            Self::Mkdir => &[
+                ("-p", OptionValue::None),
+                ("-m", OptionValue::OneOf(&["755", "700", "0", "u+t", "a-x"])),
+            ],
+            Self::Chmod => &[
+                ("-R", OptionValue::None),
+                ("-f", OptionValue::None),
+                ("--preserve-root", OptionValue::None),
+                ("--reference=a", OptionValue::None),
+            ],
+            Self::Touch => &[
+                ("-a", OptionValue::None),
+                ("-m", OptionValue::None),
+                ("-c", OptionValue::None),
+                ("-h", OptionValue::None),
+                ("--date=@1577836800", OptionValue::None),
+                ("--date=@0", OptionValue::None),
+                ("--date=2020-01-01 00:00", OptionValue::None),
+                (
+                    "-t",
+                    OptionValue::OneOf(&["202001010000", "197001010000.01"]),
+                ),
+                ("--reference=a", OptionValue::None),
+            ],
+            Self::Truncate => &[
+                ("-c", OptionValue::None),
+                ("-o", OptionValue::None),
+                ("--size=0", OptionValue::None),
+                ("--size=1K", OptionValue::None),
+                ("--size=+7", OptionValue::None),
+                ("--size=-3", OptionValue::None),
+                ("--size=%4", OptionValue::None),
+                ("--size=/3", OptionValue::None),
+                ("--size=<9", OptionValue::None),
+                ("--size=>9", OptionValue::None),
+                ("-s", OptionValue::OneOf(&["1M", "0", "+1", "<5"])),
+                ("--reference=a", OptionValue::None),
+            ],
+        }
+    }
+}
+
+const CHMOD_MODES: [&str; 10] = [
+    "644",
+    "0",
+    "4755",
+    "u+x",
+    "go-rwx",
+    "a=r",
+    "u=rwx,g=rx,o=",
+    "+t",
+    "=",
+    "g+s",
+];
+
+/// An option of an [`FsAlteringInput`], with its value if it takes one
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct FsAlteringOption {
+    pub flag: Vec<u8>,
+    pub value: Option<Vec<u8>>,
+}
+
+/// An [`Input`] implementation for the utilities in [`FsAlteringUtil`]
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, SerdeAny)]
+pub struct FsAlteringInput {
+    pub util: FsAlteringUtil,
+    pub fixture: Fixture,
+    pub flags: Vec<FsAlteringOption>,
+    /// the mode operand of `chmod`, which precedes the file operands
+    pub mode: Option<Vec<u8>>,
+    pub operands: Vec<Vec<u8>>,
+}
+
+impl Display for FsAlteringInput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "fixture: {}, args: {}",
+            self.fixture,
+            args_string_mapper(&self.get_args())
+        )
+    }
+}
+
+impl Input for FsAlteringInput {
+    fn generate_name(&self, _id: Option<CorpusId>) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+impl ExtractsToCommand for FsAlteringInput {
+    fn get_stdin(&self) -> &Vec<u8> {
+        static EMPTY: Vec<u8> = Vec::new();
+        &EMPTY
+    }
+
+    fn get_args<'a>(&self) -> Vec<Cow<'a, OsStr>> {
+        self.flags
+            .iter()
+            .flat_map(|option| once(&option.flag).chain(option.value.iter()))
+            .chain(self.mode.iter())
+            .map(Vec::as_slice)
+            // fixture names may start with a dash
+            .chain(once(b"--".as_slice()))
+            .chain(self.operands.iter().map(Vec::as_slice))
+            .map(|a| Cow::Owned(OsStr::from_bytes(a).to_os_string()))
+            .collect()
+    }
+
+    fn get_fixture(&self) -> Option<&Fixture> {
+        Some(&self.fixture)
+    }
+}
+
+impl HasFixture for FsAlteringInput {
+    fn fixture(&self) -> &Fixture {
+        &self.fixture
+    }
+
+    fn fixture_mut(&mut self) -> &mut Fixture {
+        &mut self.fixture
+    }
+
+    fn operands_mut(&mut self) -> &mut Vec<Vec<u8>> {
+        &mut self.operands
+    }
+}
+
+impl HasByteFields for FsAlteringInput {
+    fn byte_fields(&self) -> Vec<(Cow<'static, str>, &Vec<u8>)> {
+        self.fixture.file_fields().collect()
+    }
+
+    fn byte_fields_mut(&mut self) -> Vec<(Cow<'static, str>, &mut Vec<u8>)> {
+        self.fixture.file_fields_mut().collect()
+    }
+}
+
+impl HasArgSpelling for FsAlteringInput {}
+
+impl Shrinkable for FsAlteringInput {
+    fn without_parts(&self) -> Vec<Self> {
+        without_each(&self.flags)
+            .map(|flags| Self {
+                flags,
+                ..self.clone()
+            })
+            .chain(without_each(&self.operands).map(|operands| Self {
+                operands,
+                ..self.clone()
+            }))
+            .collect()
+    }
+
+    fn shrinkable_fields_mut(&mut self) -> Vec<&mut Vec<u8>> {
+        self.byte_fields_mut()
+            .into_iter()
+            .map(|(_, field)| field)
+            .collect()
+    }
+}
+
+impl HasLen for FsAlteringInput {
+    fn len(&self) -> usize {
+        self.fixture.entries.len()
+    }
+}
+
+fn generate_flag<R: Rand>(
+    rand: &mut R,
+    util: FsAlteringUtil,
+    fixture: &Fixture,
+) -> FsAlteringOption {
+    let options = util.options();
+    let (flag, value) = options[rand.below(options.len())];
+    FsAlteringOption {
+        flag: flag.as_bytes().to_vec(),
+        value: match value {
+            OptionValue::None => None,
+            OptionValue::OneOf(values) => {
+                Some(values[rand.below(values.len())].as_bytes().to_vec())
+            }
+            OptionValue::Operand => Some(generate_operand(rand, fixture)),
+        },
+    }
+}
+
+fn generate_mode<R: Rand>(rand: &mut R) -> Vec<u8> {
+    CHMOD_MODES[rand.below(CHMOD_MODES.len())]
+        .as_bytes()
+        .to_vec()
+}
+
+pub struct FsAlteringGenerator {
+    util: FsAlteringUtil,
+    max_entries: usize,
+}
+
+impl FsAlteringGenerator {
+    pub fn new(util: FsAlteringUtil, max_entries: usize) -> Self {
+        Self { util, max_entries }
+    }
+}
+
+impl<S> Generator<FsAlteringInput, S> for FsAlteringGenerator
+where
+    S: HasRand,
+{
+    fn generate(&mut self, state: &mut S) -> Result<FsAlteringInput, Error> {
+        let rand = state.rand_mut();
+        let fixture = generate_fixture(rand, self.max_entries);
+        let flags = (0..rand.below(3))
+            .map(|_| generate_flag(rand, self.util, &fixture))
+            .collect();
+        let operands = (0..rand.between(1, 3))
+            .map(|_| generate_operand(rand, &fixture))
+            .collect();
+        Ok(FsAlteringInput {
+            util: self.util,
+            fixture,
+            flags,
+            mode: (self.util == FsAlteringUtil::Chmod).then(|| generate_mode(rand)),
+            operands,
+        })
+    }
+}
+
+/// Adds, removes or replaces an option, or replaces the mode operand of `chmod`
+pub struct FsAlteringFlagMutator;
+impl<S> Mutator<FsAlteringInput, S> for FsAlteringFlagMutator
+where
+    S: HasRand,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut FsAlteringInput,
+    ) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+        if input.mode.is_some() && rand.coinflip(0.3) {
+            input.mode = Some(generate_mode(rand));
+            return Ok(MutationResult::Mutated);
+        }
+        let flag = generate_flag(rand, input.util, &input.fixture);
+        match rand.below(3) {
+            0 if !input.flags.is_empty() => {
+                input.flags.remove(rand.below(input.flags.len()));
+            }
+            1 if !input.flags.is_empty() => {
+                let index = rand.below(input.flags.len());
+                input.flags[index] = flag;
+            }
+            _ => input.flags.push(flag),
+        }
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for FsAlteringFlagMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("FsAlteringFlagMutator")
+    }
+}
+
+pub type FsAlteringMutatorsType = <tuple_list_type!(
+    FixtureAddEntryMutator,
+    FixtureRemoveEntryMutator,
+    FixtureAttributeMutator,
+    FixtureOperandMutator,
+    FsAlteringFlagMutator
+) as Merge<MappedHavocMutationsType>>::MergeResult;
+
+pub fn fs_altering_mutators() -> FsAlteringMutatorsType {
+    fixture_mutators()
+        .append(FsAlteringFlagMutator)
+        .merge(mapped_havoc_mutations())
+}
@@ -0,0 +1,168 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use libafl::{
+    corpus::Testcase, events::EventFirer, executors::ExitKind, feedbacks::Feedback, inputs::Input,
+    observers::ObserversTuple, state::State, Error, HasMetadata,
+};
+use libafl_bolts::{HasLen, Named};
+
+use super::{
+    divergence::{Classifier, DivergenceSignature},
+    executor::ExtractsToCommand,
+    minimise::PendingMinimisationMetadata,
+    reproducer::ReproducerExporter,
+};
+
+/// The file in a bucket holding the smallest input with the bucket's signature
+pub static REPRESENTATIVE_FILE: &str = "representative";
+/// The file in a bucket with one line per objective with the bucket's signature, holding the length of its input
+pub static HITS_FILE: &str = "hits";
+/// The file in a bucket holding its [`DivergenceSignature`]
+pub static SIGNATURE_FILE: &str = "signature.json";
+
+/// Groups objectives by their [`DivergenceSignature`] into one directory per bucket. Every objective is counted in
+/// its bucket, but only interesting if it is the first or smallest one with its signature, in which case it is
+/// stored as the bucket's representative, together with the metadata the other objective feedbacks attached to it and
+/// its reproducers.
+///
+/// Buckets are shared between clients, so the representative on disk is checked before replacing it. New
+/// representatives are queued for the [`MinimisationStage`](super::minimise::MinimisationStage).
+pub struct BucketFeedback<C> {
+    dir: PathBuf,
+    classifier: C,
+    exporter: ReproducerExporter,
+    /// the length of the representative of each bucket, as last seen by this client
+    representative_lens: HashMap<String, usize>,
+    /// the bucket of the last execution, if its input is the new representative
+    bucket: Option<(String, DivergenceSignature)>,
+}
+
+impl<C> BucketFeedback<C> {
+    pub fn new(dir: PathBuf, classifier: C, exporter: ReproducerExporter) -> Self {
+        Self {
+            dir,
+            classifier,
+            exporter,
+            representative_lens: HashMap::new(),
+            bucket: None,
+        }
+    }
+}
+
+/// The length of the representative stored in `bucket`, if there is one
+fn representative_len<I>(bucket: &Path) -> Option<usize>
+where
+    I: Input + HasLen,
+{
+    I::from_file(bucket.join(REPRESENTATIVE_FILE))
+        .ok()
+        .map(|input| input.len())
+}
+
+impl<C, S> Feedback<S> for BucketFeedback<C>
+where
+    C: Classifier,
+    S: State + HasMetadata,
+    S::Input: HasLen + ExtractsToCommand,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        input: &S::Input,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let signature = self.classifier.signature(state, observers, exit_kind)?;
+        let name = signature.bucket_name();
+        let bucket = self.dir.join(&name);
+        fs::create_dir_all(&bucket)
+            .map_err(|e| Error::os_error(e, format!("Could not create bucket {name}")))?;
+
+        // appending a single line is atomic, so concurrent clients do not lose hits
+        let len = input.len();
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(bucket.join(HITS_FILE))
+            .and_then(|mut hits| hits.write_all(format!("{len}\n").as_bytes()))
+            .map_err(|e| Error::os_error(e, format!("Could not count hit in bucket {name}")))?;
+
+        let smaller = match self.representative_lens.get(&name) {
+            Some(&known) if known <= len => false,
+            _ => match representative_len::<S::Input>(&bucket) {
+                Some(stored) => {
+                    self.representative_lens.insert(name.clone(), stored);
+                    len < stored
+                }
+                None => true,
+            },
+        };
+        self.bucket = smaller.then_some((name, signature));
+        Ok(smaller)
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        let Some((name, signature)) = self.bucket.take() else {
+            return Ok(());
+        };
+        let Some(input) = testcase.input() else {
+            return Ok(());
+        };
+        let bucket = self.dir.join(&name);
+        let write_error = |e| Error::os_error(e, format!("Could not write bucket {name}"));
+
+        serde_json::to_writer_pretty(
+            File::create(bucket.join(SIGNATURE_FILE)).map_err(write_error)?,
+            &signature,
+        )
+        .map_err(|e| Error::serialize(format!("Could not write signature of {name}: {e}")))?;
+        // the metadata of the other objectives, which append theirs before this one
+        serde_json::to_writer_pretty(
+            File::create(bucket.join(format!("{REPRESENTATIVE_FILE}.metadata")))
+                .map_err(write_error)?,
+            testcase.metadata_map(),
+        )
+        .map_err(|e| Error::serialize(format!("Could not write metadata of {name}: {e}")))?;
+        input.to_file(bucket.join(REPRESENTATIVE_FILE))?;
+        self.exporter.export(
+            &self.dir,
+            &name,
+            input,
+            &self.classifier.compared(observers)?,
+        )?;
+
+        self.representative_lens.insert(name.clone(), input.len());
+        state
+            .metadata_or_insert_with(PendingMinimisationMetadata::default)
+            .buckets
+            .push(name);
+        Ok(())
+    }
+}
+
+impl<C> Named for BucketFeedback<C> {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("BucketFeedback")
+    }
+}
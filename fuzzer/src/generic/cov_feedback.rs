@@ -1,36 +1,43 @@
-use std::{
-    borrow::Cow,
-    process::{Command, Stdio},
-};
+use std::borrow::Cow;
 
 use libafl::{
     corpus::Testcase, events::EventFirer, executors::ExitKind, feedbacks::Feedback,
     observers::ObserversTuple, state::State, Error,
 };
-use libafl_bolts::Named;
+use libafl_bolts::{shmem::ShMemDescription, Named};
 
-use super::executor::{pseudo_pipe, ExtractsToCommand};
+use super::{executor::CoverageConfigurator, implementation::Implementation};
 
-pub struct CovFeedback {
+/// Runs the `gcov` build on every new corpus entry, with the configurator `C` of the fuzzed binaries, so it runs in
+/// the same kind of namespace and fixture.
+pub struct CovFeedback<C> {
     is_interesting: bool,
-    gcov_path: String,
-    temp_file_stdin_path: String,
+    configurator: C,
 }
 
-impl CovFeedback {
-    pub fn new(is_interesting: bool, gcov_path: String, temp_file_stdin_path: String) -> Self {
+impl<C> CovFeedback<C> {
+    /// `gcov` is the implementation of the `gcov` build, see [`Implementation::gcov_build`]. The coverage map of
+    /// `shmem_coverage_description` is not written to.
+    pub fn new<I>(
+        is_interesting: bool,
+        gcov: &Implementation,
+        id: &str,
+        shmem_coverage_description: &ShMemDescription,
+    ) -> Self
+    where
+        C: CoverageConfigurator<I>,
+    {
         Self {
             is_interesting,
-            gcov_path,
-            temp_file_stdin_path: format!("/dev/shm/temp{}", temp_file_stdin_path),
+            configurator: C::configure(shmem_coverage_description, None, None, gcov, id),
         }
     }
 }
 
-impl<S> Feedback<S> for CovFeedback
+impl<S, C> Feedback<S> for CovFeedback<C>
 where
     S: State,
-    S::Input: ExtractsToCommand,
+    C: CoverageConfigurator<S::Input>,
 {
     fn is_interesting<EM, OT>(
         &mut self,
@@ -62,18 +69,13 @@ where
             .input()
             .as_ref()
             .ok_or(Error::illegal_state("Should have an input at this point"))?;
-        Command::new(&self.gcov_path)
-            .args(input.get_args())
-            .stdin(pseudo_pipe(input.get_stdin(), &self.temp_file_stdin_path)?)
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn()?
-            .wait()?;
+        // drains the outputs, so the child cannot block on a full pipe
+        self.configurator.spawn_child(input)?.wait_with_output()?;
         Ok(())
     }
 }
 
-impl Named for CovFeedback {
+impl<C> Named for CovFeedback<C> {
     fn name(&self) -> &Cow<'static, str> {
         &Cow::Borrowed("CovFeedback")
     }
@@ -0,0 +1,364 @@
+use std::{collections::BTreeSet, sync::Arc};
+
+use libafl::{
+    executors::ExitKind,
+    observers::{ObserversTuple, StdErrObserver, StdOutObserver},
+    state::State,
+    Error, HasMetadata,
+};
+use libafl_bolts::tuples::{Handle, Handled, MatchNameRef};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    equivalence::SpellingMismatchMetadata,
+    exit_status::{ExitStatus, ExitStatusEquivalence, ExitStatusObserver},
+    namespace::{TreeSnapshot, TreeSnapshotObserver},
+    panic::PanicReport,
+    sanitizer::SanitizerReport,
+    stderr::StderrNormaliser,
+    stdio::StdoutPolicy,
+};
+
+/// An aspect of an execution in which two implementations can differ.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Aspect {
+    /// both implementations crashed
+    Crash,
+    /// one implementation crashed or timed out, the other did not
+    ExitKind,
+    ExitStatus,
+    Stdout,
+    StderrClass,
+    Tree,
+    /// an implementation behaved differently for equivalent spellings of its options
+    Spelling,
+    /// the implementations did not panic, fail an assertion or abort at the same location
+    Panic,
+    /// the sanitizers of the implementations did not report the same error
+    Sanitizer,
+}
+
+/// What distinguishes a divergence, independent of the input triggering it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DivergenceSignature {
+    /// the aspects in which the implementations differ, empty if they do not diverge
+    pub differs: BTreeSet<Aspect>,
+    pub exit_statuses: [Option<ExitStatus>; 2],
+    pub stderr_classes: [Option<String>; 2],
+    /// the class of the first offset at which `stdout` differs, if it does: 0 for the first byte and `k` for
+    /// offsets in `2^(k-1)..2^k`
+    pub stdout_offset_class: Option<u32>,
+    /// the [`PanicReport::bucket_name`] of each implementation, if it panicked
+    #[serde(default)]
+    pub panics: [Option<String>; 2],
+    /// the [`SanitizerReport::bucket_name`] of each implementation, if its sanitizers reported an error
+    #[serde(default)]
+    pub sanitizer_reports: [Option<String>; 2],
+    /// the implementations a majority vote attributes the divergence to, see
+    /// [`MajorityClassifier`](super::majority::MajorityClassifier), empty when comparing two implementations
+    #[serde(default)]
+    pub outliers: Vec<String>,
+}
+
+/// What an implementation did in an execution
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Execution {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub exit_status: Option<ExitStatus>,
+}
+
+impl Aspect {
+    fn name(&self) -> &'static str {
+        match self {
+            Aspect::Crash => "crash",
+            Aspect::ExitKind => "exit_kind",
+            Aspect::ExitStatus => "exit_status",
+            Aspect::Stdout => "stdout",
+            Aspect::StderrClass => "stderr_class",
+            Aspect::Tree => "tree",
+            Aspect::Spelling => "spelling",
+            Aspect::Panic => "panic",
+            Aspect::Sanitizer => "sanitizer",
+        }
+    }
+}
+
+impl DivergenceSignature {
+    /// A readable name identifying the signature, usable as a directory name
+    pub fn bucket_name(&self) -> String {
+        let aspects = if self.differs.is_empty() {
+            "none".to_string()
+        } else {
+            self.differs
+                .iter()
+                .map(Aspect::name)
+                .collect::<Vec<_>>()
+                .join("+")
+        };
+        let status = |status: &Option<ExitStatus>| match status {
+            Some(ExitStatus::Code(code)) => format!("exit{code}"),
+            Some(ExitStatus::Signal(signal)) => format!("signal{signal}"),
+            None => "none".to_string(),
+        };
+        let class =
+            |class: &Option<String>| class.as_deref().map_or("none".to_string(), sanitised_name);
+        let mut name = String::new();
+        for outlier in &self.outliers {
+            name.push_str(&sanitised_name(outlier));
+            name.push('_');
+        }
+        name.push_str(&format!(
+            "{aspects}_{}_{}_{}_{}",
+            status(&self.exit_statuses[0]),
+            status(&self.exit_statuses[1]),
+            class(&self.stderr_classes[0]),
+            class(&self.stderr_classes[1]),
+        ));
+        if let Some(offset_class) = self.stdout_offset_class {
+            name.push_str(&format!("_at{offset_class}"));
+        }
+        for reports in [&self.panics, &self.sanitizer_reports] {
+            if reports.iter().any(Option::is_some) {
+                for report in reports {
+                    name.push('_');
+                    name.push_str(report.as_deref().unwrap_or("none"));
+                }
+            }
+        }
+        name
+    }
+}
+
+/// The class of the first offset at which the outputs differ, see [`DivergenceSignature::stdout_offset_class`]
+fn offset_class(output1: &[u8], output2: &[u8]) -> u32 {
+    let offset = output1
+        .iter()
+        .zip(output2)
+        .take_while(|(b1, b2)| b1 == b2)
+        .count();
+    usize::BITS - offset.leading_zeros()
+}
+
+/// The observer of `handle` among `observers`, or an error naming the `owner` looking for it
+pub fn observed<'a, T, OT>(
+    owner: &str,
+    handle: &Handle<T>,
+    observers: &'a OT,
+) -> Result<&'a T, Error>
+where
+    OT: MatchNameRef,
+{
+    observers.get(handle).ok_or_else(|| {
+        Error::illegal_argument(format!("{owner}: observer {} not found", handle.name()))
+    })
+}
+
+/// `name` with everything but ASCII letters and digits replaced by `-`, usable as part of a directory name
+pub fn sanitised_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// How the executions of two implementations are compared, using the same comparisons as the differential
+/// objectives.
+#[derive(Clone)]
+pub struct Comparison {
+    pub stdout_policy: StdoutPolicy,
+    pub exit_status_equivalence: ExitStatusEquivalence,
+    pub stderr_normaliser: Arc<StderrNormaliser>,
+}
+
+impl Comparison {
+    /// The signature of two executions and the trees they left behind, not considering their exit kinds
+    pub fn signature(
+        &self,
+        executions: [&Execution; 2],
+        trees: [Option<&TreeSnapshot>; 2],
+    ) -> DivergenceSignature {
+        let mut differs = BTreeSet::new();
+
+        let exit_statuses = executions.map(|e| e.exit_status);
+        if let [Some(status1), Some(status2)] = &exit_statuses {
+            if !self.exit_status_equivalence.equivalent(status1, status2) {
+                differs.insert(Aspect::ExitStatus);
+            }
+        }
+
+        let stderrs = executions.map(|e| Some(e.stderr.as_slice()).filter(|s| !s.is_empty()));
+        let stderr_classes = stderrs
+            .map(|stderr| stderr.and_then(|stderr| self.stderr_normaliser.normalise(stderr).class));
+        if let [Some(class1), Some(class2)] = &stderr_classes {
+            if class1 != class2 {
+                differs.insert(Aspect::StderrClass);
+            }
+        }
+
+        let [stdout1, stdout2] = executions.map(|e| e.stdout.as_slice());
+        if !self.stdout_policy.equivalent(
+            stdout1,
+            stderrs[0].is_some(),
+            stdout2,
+            stderrs[1].is_some(),
+        ) {
+            differs.insert(Aspect::Stdout);
+        }
+        let stdout_offset_class = differs
+            .contains(&Aspect::Stdout)
+            .then(|| offset_class(stdout1, stdout2));
+
+        let panics = executions.map(|e| {
+            PanicReport::detect(&e.stderr, e.exit_status).map(|report| report.bucket_name())
+        });
+        if panics[0] != panics[1] {
+            differs.insert(Aspect::Panic);
+        }
+        let sanitizer_reports = executions
+            .map(|e| SanitizerReport::parse(&e.stderr).map(|report| report.bucket_name()));
+        if sanitizer_reports[0] != sanitizer_reports[1] {
+            differs.insert(Aspect::Sanitizer);
+        }
+
+        match trees {
+            [Some(s1), Some(s2)] if s1.diff(s2).is_none() => (),
+            [None, None] => (),
+            _ => {
+                differs.insert(Aspect::Tree);
+            }
+        }
+
+        DivergenceSignature {
+            differs,
+            exit_statuses,
+            stderr_classes,
+            stdout_offset_class,
+            panics,
+            sanitizer_reports,
+            outliers: Vec::new(),
+        }
+    }
+}
+
+/// Classifies the divergence of the last execution for the objectives keyed on its [`DivergenceSignature`]: the
+/// [`SuppressionFeedback`](super::suppression::SuppressionFeedback), the
+/// [`BucketFeedback`](super::bucket::BucketFeedback) and the [`MinimisationStage`](super::minimise::MinimisationStage).
+pub trait Classifier: Clone {
+    /// The signature of the last execution
+    fn signature<S, OT>(
+        &self,
+        state: &S,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<DivergenceSignature, Error>
+    where
+        S: State + HasMetadata,
+        OT: ObserversTuple<S>;
+
+    /// The indices of the two implementations the signature compares, with what they did in the last execution
+    fn compared<OT: MatchNameRef>(&self, observers: &OT) -> Result<[(usize, Execution); 2], Error>;
+}
+
+/// Computes the [`DivergenceSignature`] of the last execution from the observers of both implementations.
+#[derive(Clone)]
+pub struct DivergenceClassifier {
+    stdout_observers: [Handle<StdOutObserver>; 2],
+    stderr_observers: [Handle<StdErrObserver>; 2],
+    exit_status_observers: [Handle<ExitStatusObserver>; 2],
+    tree_observers: [Handle<TreeSnapshotObserver>; 2],
+    comparison: Comparison,
+}
+
+impl DivergenceClassifier {
+    pub fn new(
+        stdout_observers: [&StdOutObserver; 2],
+        stderr_observers: [&StdErrObserver; 2],
+        exit_status_observers: [&ExitStatusObserver; 2],
+        tree_observers: [&TreeSnapshotObserver; 2],
+        stdout_policy: StdoutPolicy,
+        exit_status_equivalence: ExitStatusEquivalence,
+        stderr_normaliser: Arc<StderrNormaliser>,
+    ) -> Self {
+        Self {
+            stdout_observers: stdout_observers.map(Handled::handle),
+            stderr_observers: stderr_observers.map(Handled::handle),
+            exit_status_observers: exit_status_observers.map(Handled::handle),
+            tree_observers: tree_observers.map(Handled::handle),
+            comparison: Comparison {
+                stdout_policy,
+                exit_status_equivalence,
+                stderr_normaliser,
+            },
+        }
+    }
+
+    /// What both implementations did in the last execution
+    pub fn executions<OT: MatchNameRef>(&self, observers: &OT) -> Result<[Execution; 2], Error> {
+        let mut executions: [Execution; 2] = Default::default();
+        for (i, execution) in executions.iter_mut().enumerate() {
+            *execution = Execution {
+                stdout: observed("DivergenceClassifier", &self.stdout_observers[i], observers)?
+                    .stdout
+                    .clone()
+                    .unwrap_or_default(),
+                stderr: observed("DivergenceClassifier", &self.stderr_observers[i], observers)?
+                    .stderr
+                    .clone()
+                    .unwrap_or_default(),
+                exit_status: observed(
+                    "DivergenceClassifier",
+                    &self.exit_status_observers[i],
+                    observers,
+                )?
+                .status,
+            };
+        }
+        Ok(executions)
+    }
+}
+
+impl Classifier for DivergenceClassifier {
+    fn signature<S, OT>(
+        &self,
+        state: &S,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<DivergenceSignature, Error>
+    where
+        S: State + HasMetadata,
+        OT: ObserversTuple<S>,
+    {
+        let [execution1, execution2] = self.executions(observers)?;
+        let trees = [
+            observed("DivergenceClassifier", &self.tree_observers[0], observers)?,
+            observed("DivergenceClassifier", &self.tree_observers[1], observers)?,
+        ]
+        .map(|o| o.snapshot.as_ref());
+        let mut signature = self.comparison.signature([&execution1, &execution2], trees);
+
+        match exit_kind {
+            ExitKind::Crash => {
+                signature.differs.insert(Aspect::Crash);
+            }
+            ExitKind::Diff { .. } => {
+                signature.differs.insert(Aspect::ExitKind);
+            }
+            _ => (),
+        }
+
+        if state
+            .metadata::<SpellingMismatchMetadata>()
+            .is_ok_and(|m| !m.is_empty())
+        {
+            signature.differs.insert(Aspect::Spelling);
+        }
+
+        Ok(signature)
+    }
+
+    fn compared<OT: MatchNameRef>(&self, observers: &OT) -> Result<[(usize, Execution); 2], Error> {
+        let [execution1, execution2] = self.executions(observers)?;
+        Ok([(0, execution1), (1, execution2)])
+    }
+}
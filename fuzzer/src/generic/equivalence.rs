@@ -0,0 +1,250 @@
+use std::borrow::Cow;
+
+use libafl::{
+    events::EventFirer,
+    executors::{Executor, ExitKind, HasObservers},
+    feedbacks::Feedback,
+    inputs::UsesInput,
+    observers::{ObserversTuple, StdErrObserver, StdOutObserver, UsesObservers},
+    state::{State, UsesState},
+    Error, HasMetadata, SerdeAny,
+};
+use libafl_bolts::{
+    tuples::{Handle, MatchNameRef, RefIndexable},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    exit_status::{ExitStatus, ExitStatusEquivalence, ExitStatusObserver},
+    spelling::HasArgSpelling,
+    stdio::vec_string_mapper,
+};
+
+/// Runs an input a second time with its options spelled canonically and records in the state's
+/// [`SpellingMismatchMetadata`] if the two runs behave differently. Since all spellings are equivalent, any difference
+/// is a bug within a single implementation.
+#[derive(Debug)]
+pub struct SpellingEquivalenceExecutor<E> {
+    name: String,
+    inner: E,
+    stdout_observer: Handle<StdOutObserver>,
+    stderr_observer: Handle<StdErrObserver>,
+    exit_status_observer: Handle<ExitStatusObserver>,
+    exit_status_equivalence: ExitStatusEquivalence,
+}
+
+impl<E> SpellingEquivalenceExecutor<E> {
+    pub fn new(
+        name: &str,
+        inner: E,
+        stdout_observer: Handle<StdOutObserver>,
+        stderr_observer: Handle<StdErrObserver>,
+        exit_status_observer: Handle<ExitStatusObserver>,
+        exit_status_equivalence: ExitStatusEquivalence,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            inner,
+            stdout_observer,
+            stderr_observer,
+            exit_status_observer,
+            exit_status_equivalence,
+        }
+    }
+}
+
+/// What is compared between two spellings. `stderr` is only compared for presence, since messages may quote the
+/// option as it was spelled.
+struct Behavior {
+    exit_kind: ExitKind,
+    exit_status: Option<ExitStatus>,
+    stdout: Option<Vec<u8>>,
+    has_stderr: bool,
+}
+
+impl Behavior {
+    /// Exit statuses are compared under `equivalence`, everything else needs to match exactly.
+    fn equivalent(&self, other: &Self, equivalence: &ExitStatusEquivalence) -> bool {
+        let exit_status = match (&self.exit_status, &other.exit_status) {
+            (Some(status1), Some(status2)) => equivalence.equivalent(status1, status2),
+            (status1, status2) => status1 == status2,
+        };
+        self.exit_kind == other.exit_kind
+            && exit_status
+            && self.stdout == other.stdout
+            && self.has_stderr == other.has_stderr
+    }
+}
+
+impl<E> SpellingEquivalenceExecutor<E>
+where
+    E: HasObservers,
+{
+    fn behavior(&self, exit_kind: ExitKind) -> Behavior {
+        let observers = self.inner.observers();
+        Behavior {
+            exit_kind,
+            exit_status: observers
+                .get(&self.exit_status_observer)
+                .and_then(|o| o.status),
+            stdout: observers
+                .get(&self.stdout_observer)
+                .and_then(|o| o.stdout.clone()),
+            has_stderr: observers
+                .get(&self.stderr_observer)
+                .and_then(|o| o.stderr.as_ref())
+                .is_some_and(|s| !s.is_empty()),
+        }
+    }
+}
+
+impl<E, EM, Z> Executor<EM, Z> for SpellingEquivalenceExecutor<E>
+where
+    E: Executor<EM, Z> + HasObservers,
+    E::State: HasMetadata,
+    <E::State as UsesInput>::Input: HasArgSpelling,
+    EM: UsesState<State = E::State>,
+    Z: UsesState<State = E::State>,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut Self::State,
+        mgr: &mut EM,
+        input: &<Self::State as UsesInput>::Input,
+    ) -> Result<ExitKind, Error> {
+        let Some(canonical) = input.canonically_spelled() else {
+            record_mismatch(state, &self.name, None);
+            return self.inner.run_target(fuzzer, state, mgr, input);
+        };
+
+        let canonical_exit_kind = self.inner.run_target(fuzzer, state, mgr, &canonical)?;
+        let canonical_behavior = self.behavior(canonical_exit_kind);
+
+        // reset the observers, so they only contain the results of the actual input
+        self.inner.observers_mut().pre_exec_all(state, input)?;
+        let exit_kind = self.inner.run_target(fuzzer, state, mgr, input)?;
+        let behavior = self.behavior(exit_kind);
+
+        let timed_out = exit_kind == ExitKind::Timeout || canonical_exit_kind == ExitKind::Timeout;
+        let equivalent = behavior.equivalent(&canonical_behavior, &self.exit_status_equivalence);
+        let mismatch = (!timed_out && !equivalent).then(|| {
+            format!(
+                "canonical spelling: {:?} ({}), stdout '{}', stderr {}; this spelling: {:?} ({}), stdout '{}', stderr {}",
+                canonical_behavior.exit_kind,
+                describe(&canonical_behavior.exit_status),
+                vec_string_mapper(&canonical_behavior.stdout),
+                canonical_behavior.has_stderr,
+                behavior.exit_kind,
+                describe(&behavior.exit_status),
+                vec_string_mapper(&behavior.stdout),
+                behavior.has_stderr,
+            )
+        });
+        record_mismatch(state, &self.name, mismatch);
+        Ok(exit_kind)
+    }
+}
+
+fn describe(status: &Option<ExitStatus>) -> String {
+    status.map_or_else(|| "no exit status".to_string(), |s| s.to_string())
+}
+
+fn record_mismatch<S: HasMetadata>(state: &mut S, name: &str, mismatch: Option<String>) {
+    let metadata = state.metadata_or_insert_with(SpellingMismatchMetadata::default);
+    metadata.mismatches.retain(|(n, _)| n != name);
+    if let Some(mismatch) = mismatch {
+        metadata.mismatches.push((name.to_string(), mismatch));
+    }
+}
+
+impl<E> UsesState for SpellingEquivalenceExecutor<E>
+where
+    E: UsesState,
+{
+    type State = E::State;
+}
+
+impl<E> UsesObservers for SpellingEquivalenceExecutor<E>
+where
+    E: UsesObservers,
+{
+    type Observers = E::Observers;
+}
+
+impl<E> HasObservers for SpellingEquivalenceExecutor<E>
+where
+    E: HasObservers,
+{
+    fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
+        self.inner.observers()
+    }
+
+    fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers> {
+        self.inner.observers_mut()
+    }
+}
+
+/// The implementations whose last execution behaved differently depending on the spelling of the options
+#[derive(Debug, Default, Clone, SerdeAny, Serialize, Deserialize)]
+pub struct SpellingMismatchMetadata {
+    /// the name of the implementation and a description of the difference
+    mismatches: Vec<(String, String)>,
+}
+
+impl SpellingMismatchMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Interesting if a [`SpellingEquivalenceExecutor`] found a difference between spellings in the last execution.
+pub struct SpellingEquivalenceFeedback;
+
+impl<S> Feedback<S> for SpellingEquivalenceFeedback
+where
+    S: State + HasMetadata,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        Ok(state
+            .metadata::<SpellingMismatchMetadata>()
+            .is_ok_and(|m| !m.is_empty()))
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _observers: &OT,
+        testcase: &mut libafl::prelude::Testcase<<S>::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        if let Ok(metadata) = state.metadata::<SpellingMismatchMetadata>() {
+            if !metadata.is_empty() {
+                testcase.metadata_map_mut().insert(metadata.clone());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Named for SpellingEquivalenceFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("SpellingEquivalenceFeedback")
+    }
+}
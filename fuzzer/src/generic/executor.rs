@@ -1,69 +1,116 @@
 use std::{
     borrow::Cow,
+    collections::BTreeMap,
     ffi::OsStr,
     fs::File,
-    io::Write,
+    io::{Read, Write},
     marker::PhantomData,
+    path::PathBuf,
     process::{Child, Command, Stdio},
     time::Duration,
 };
 
 use libafl::{
-    executors::{command::CommandConfigurator, CommandExecutor},
-    observers::{StdErrObserver, StdOutObserver},
-    state::State,
+    executors::{command::CommandConfigurator, Executor, ExitKind, HasObservers},
+    observers::{ObserversTuple, StdErrObserver, StdOutObserver, UsesObservers},
+    state::{HasExecutions, State, UsesState},
     Error,
 };
 use libafl_bolts::{
     shmem::ShMemDescription,
-    tuples::{Handle, MatchName},
+    tuples::{Handle, MatchNameRef, RefIndexable},
 };
 use serde::Serialize;
+use wait_timeout::ChildExt;
+
+use super::{
+    exit_status::{ExitStatus, ExitStatusObserver},
+    fixture::{fixture_root, Fixture},
+    implementation::{CoverageMode, Implementation, NO_COVERAGE_ENV},
+};
 
 // Create the executor for an in-process function with just one observer
 #[derive(Debug)]
 pub struct CoverageCommandExecutor<I: ExtractsToCommand> {
     shmem_coverage_description: String,
     temp_file_stdin_path: String,
+    fixture_path: PathBuf,
+    preload_path: PathBuf,
+    args: Vec<String>,
+    env: BTreeMap<String, String>,
+    coverage: CoverageMode,
     stdout_observer: Option<Handle<StdOutObserver>>,
     stderr_observer: Option<Handle<StdErrObserver>>,
-    util: String,
+    util: PathBuf,
     phantom: PhantomData<I>,
 }
 
-impl<I: ExtractsToCommand> CoverageCommandExecutor<I> {
-    pub fn new<OT, S, ID>(
+/// A [`CommandConfigurator`] that runs the binary of an [`Implementation`], passing it the description of the shared
+/// coverage map if it is instrumented.
+pub trait CoverageConfigurator<I>: CommandConfigurator<I> + Sized {
+    /// Whether implementations with `fork_server` can be run by a
+    /// [`ForkServerExecutor`](super::forkserver::ForkServerExecutor) instead
+    const SUPPORTS_FORK_SERVER: bool = true;
+
+    fn configure(
         shmem_coverage_description: &ShMemDescription,
         stdout_observer: Option<Handle<StdOutObserver>>,
         stderr_observer: Option<Handle<StdErrObserver>>,
+        implementation: &Implementation,
+        id: &str,
+    ) -> Self;
+
+    /// Creates an [`ExitStatusCommandExecutor`] with `observers`, among which is `exit_status_observer`
+    fn into_exit_status_executor<OT, S>(
+        self,
         observers: OT,
-        util: &str,
-        id: ID,
-    ) -> CommandExecutor<OT, S, CoverageCommandExecutor<I>>
-    where
-        S: State,
-        S::Input: ExtractsToCommand,
-        OT: MatchName,
-        ID: ToString,
-    {
+        exit_status_observer: Handle<ExitStatusObserver>,
+    ) -> ExitStatusCommandExecutor<OT, S, Self> {
+        ExitStatusCommandExecutor {
+            configurator: self,
+            observers,
+            exit_status_observer,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<I: ExtractsToCommand> CoverageConfigurator<I> for CoverageCommandExecutor<I> {
+    fn configure(
+        shmem_coverage_description: &ShMemDescription,
+        stdout_observer: Option<Handle<StdOutObserver>>,
+        stderr_observer: Option<Handle<StdErrObserver>>,
+        implementation: &Implementation,
+        id: &str,
+    ) -> Self {
         let serialized_description = serde_json::to_string(&shmem_coverage_description)
             .expect("Could not stringify shared memory description");
 
-        let configurator = Self {
+        Self {
             shmem_coverage_description: serialized_description,
-            temp_file_stdin_path: format!("/dev/shm/temp{}", id.to_string()),
+            temp_file_stdin_path: format!("/dev/shm/temp{id}"),
+            fixture_path: fixture_root(id),
+            // absolute paths, since inputs with a fixture run in the fixture's directory
+            preload_path: absolute_path(&implementation.preload),
+            args: implementation.args.clone(),
+            env: implementation.env.clone(),
+            coverage: implementation.coverage,
             stdout_observer,
             stderr_observer,
-            util: util.to_string(),
+            util: absolute_path(&implementation.binary),
             phantom: PhantomData,
-        };
-        configurator.into_executor(observers)
+        }
     }
 }
 
 pub trait ExtractsToCommand: Serialize {
     fn get_stdin(&self) -> &Vec<u8>;
     fn get_args<'a>(&self) -> Vec<Cow<'a, OsStr>>;
+
+    /// The directory tree the command is run in, if any. Paths in the arguments are relative to it.
+    fn get_fixture(&self) -> Option<&Fixture> {
+        None
+    }
 }
 
 impl<I> CommandConfigurator<I> for CoverageCommandExecutor<I>
@@ -73,13 +120,21 @@ where
     fn spawn_child(&mut self, input: &I) -> Result<Child, Error> {
         let mut command = Command::new(&self.util);
 
+        if let Some(fixture) = input.get_fixture() {
+            fixture.build(&self.fixture_path)?;
+            command.current_dir(&self.fixture_path);
+        }
+
+        command
+            .envs(&self.env)
+            .env("LD_PRELOAD", &self.preload_path)
+            .args(&self.args)
+            .args(input.get_args());
+        match self.coverage {
+            CoverageMode::Guard => command.arg(&self.shmem_coverage_description),
+            CoverageMode::None | CoverageMode::Novelty => command.env(NO_COVERAGE_ENV, "1"),
+        };
         command
-            .env(
-                "LD_PRELOAD",
-                "./target/release/libsetup_guard_redirection.so",
-            )
-            .args(input.get_args())
-            .arg(&self.shmem_coverage_description)
             .stderr(Stdio::piped())
             .stdout(Stdio::piped())
             .stdin(pseudo_pipe(input.get_stdin(), &self.temp_file_stdin_path)?);
@@ -101,6 +156,115 @@ where
     }
 }
 
+/// Runs the children spawned by a [`CommandConfigurator`] like LibAFL's `CommandExecutor`, and additionally records
+/// their [`ExitStatus`] in an [`ExitStatusObserver`]. The status is taken from reaping the child, so it is known for
+/// statically linked binaries as well and the process tree is left as the configurator spawned it.
+#[derive(Debug)]
+pub struct ExitStatusCommandExecutor<OT, S, T> {
+    configurator: T,
+    observers: OT,
+    exit_status_observer: Handle<ExitStatusObserver>,
+    phantom: PhantomData<S>,
+}
+
+fn read_pipe(pipe: Option<&mut impl Read>, name: &str) -> Result<Vec<u8>, Error> {
+    let mut output = Vec::new();
+    pipe.ok_or_else(|| Error::illegal_state(format!("The {name} of the child is not piped")))?
+        .read_to_end(&mut output)
+        .map_err(|e| Error::os_error(e, format!("Could not read the {name} of the child")))?;
+    Ok(output)
+}
+
+impl<OT, S, T, EM, Z> Executor<EM, Z> for ExitStatusCommandExecutor<OT, S, T>
+where
+    OT: ObserversTuple<S>,
+    S: State + HasExecutions,
+    T: CommandConfigurator<S::Input>,
+    EM: UsesState<State = S>,
+    Z: UsesState<State = S>,
+{
+    fn run_target(
+        &mut self,
+        _fuzzer: &mut Z,
+        state: &mut S,
+        _mgr: &mut EM,
+        input: &S::Input,
+    ) -> Result<ExitKind, Error> {
+        *state.executions_mut() += 1;
+        self.observers.pre_exec_child_all(state, input)?;
+
+        let mut child = self.configurator.spawn_child(input)?;
+        let status = child
+            .wait_timeout(self.configurator.exec_timeout())
+            .map_err(|e| Error::os_error(e, "Could not wait for the child"))?
+            .map(ExitStatus::from);
+        let exit_kind = match status {
+            Some(status) => status.exit_kind(),
+            None => {
+                let _ = child.kill();
+                let _ = child.wait();
+                ExitKind::Timeout
+            }
+        };
+        self.observers
+            .post_exec_child_all(state, input, &exit_kind)?;
+
+        let not_found = |name: &str| {
+            Error::illegal_argument(format!(
+                "ExitStatusCommandExecutor: observer {name} not found"
+            ))
+        };
+        self.observers
+            .get_mut(&self.exit_status_observer)
+            .ok_or_else(|| not_found(self.exit_status_observer.name()))?
+            .status = status;
+        if let Some(handle) = self.configurator.stdout_observer() {
+            let stdout = read_pipe(child.stdout.as_mut(), "stdout")?;
+            self.observers
+                .get_mut(&handle)
+                .ok_or_else(|| not_found(handle.name()))?
+                .stdout = Some(stdout);
+        }
+        if let Some(handle) = self.configurator.stderr_observer() {
+            let stderr = read_pipe(child.stderr.as_mut(), "stderr")?;
+            self.observers
+                .get_mut(&handle)
+                .ok_or_else(|| not_found(handle.name()))?
+                .stderr = Some(stderr);
+        }
+        Ok(exit_kind)
+    }
+}
+
+impl<OT, S, T> UsesState for ExitStatusCommandExecutor<OT, S, T>
+where
+    S: State,
+{
+    type State = S;
+}
+
+impl<OT, S, T> UsesObservers for ExitStatusCommandExecutor<OT, S, T>
+where
+    OT: ObserversTuple<S>,
+    S: State,
+{
+    type Observers = OT;
+}
+
+impl<OT, S, T> HasObservers for ExitStatusCommandExecutor<OT, S, T>
+where
+    OT: ObserversTuple<S>,
+    S: State,
+{
+    fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
+        RefIndexable::from(&self.observers)
+    }
+
+    fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers> {
+        RefIndexable::from(&mut self.observers)
+    }
+}
+
 /// Creates a [`File`] that can be used to write data to a [`Command`]'s `stdin`.
 ///
 /// The implementation relies on a temp file on disk. Consider using an in-memory file, e.g. by locating it in `/dev/shm/`.
@@ -115,3 +279,8 @@ pub fn pseudo_pipe(data: &[u8], path: &str) -> Result<File, Error> {
         .map_err(|e| Error::os_error(e, "Could not write data to temp file"))?;
     File::open(path).map_err(|e| Error::os_error(e, "Could not open temp file again"))
 }
+
+/// Resolves `path` relative to the fuzzer's working directory, falling back to the path as given if it does not exist.
+pub fn absolute_path(path: &str) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+}
@@ -0,0 +1,343 @@
+use std::{
+    borrow::Cow,
+    fmt::{self, Display, Formatter},
+    os::unix::process::ExitStatusExt,
+    process,
+    str::FromStr,
+};
+
+use libafl::{
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::UsesInput,
+    observers::{Observer, ObserversTuple},
+    state::State,
+    Error, HasMetadata, SerdeAny,
+};
+use libafl_bolts::{
+    tuples::{Handle, Handled, MatchNameRef},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+/// How the binary under test terminated, as reaped by the executor
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitStatus {
+    Code(i32),
+    Signal(i32),
+}
+
+impl ExitStatus {
+    /// Decodes a raw wait status as returned by `waitpid`.
+    pub fn from_wait_status(status: i32) -> Self {
+        process::ExitStatus::from_raw(status).into()
+    }
+
+    /// The [`ExitKind`] of an execution terminating like this, the same as LibAFL's `CommandExecutor` reports.
+    pub fn exit_kind(&self) -> ExitKind {
+        match self {
+            ExitStatus::Code(_) => ExitKind::Ok,
+            ExitStatus::Signal(libc::SIGKILL) => ExitKind::Oom,
+            ExitStatus::Signal(_) => ExitKind::Crash,
+        }
+    }
+}
+
+/// Parses `exit <code>` or `signal <number>`.
+impl FromStr for ExitStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |n: &str| {
+            n.parse()
+                .map_err(|_| Error::serialize(format!("Invalid exit status '{s}'")))
+        };
+        match s.trim().split_once(' ') {
+            Some(("exit", code)) => parse(code).map(ExitStatus::Code),
+            Some(("signal", signal)) => parse(signal).map(ExitStatus::Signal),
+            _ => Err(Error::serialize(format!("Invalid exit status '{s}'"))),
+        }
+    }
+}
+
+impl From<process::ExitStatus> for ExitStatus {
+    fn from(status: process::ExitStatus) -> Self {
+        match (status.code(), status.signal()) {
+            (Some(code), _) => ExitStatus::Code(code),
+            (None, Some(signal)) => ExitStatus::Signal(signal),
+            // only reported if waited for with `WUNTRACED`, which the executors do not use
+            (None, None) => ExitStatus::Signal(status.stopped_signal().unwrap_or_default()),
+        }
+    }
+}
+
+/// Writes `exit <code>` or `signal <number>`, as parsed by [`FromStr`], so statuses can be copied into suppressions.
+impl Display for ExitStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitStatus::Code(code) => write!(f, "exit {code}"),
+            ExitStatus::Signal(signal) => write!(f, "signal {signal}"),
+        }
+    }
+}
+
+/// Which exit codes are considered equal when comparing implementations. Signals always need to match exactly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExitStatusEquivalence {
+    /// codes need to match exactly
+    Exact,
+    /// all non-zero codes are equal
+    NonZero,
+    /// codes within the same group are equal, all others need to match exactly
+    Groups(Vec<Vec<i32>>),
+}
+
+impl ExitStatusEquivalence {
+    pub fn equivalent(&self, status1: &ExitStatus, status2: &ExitStatus) -> bool {
+        match (status1, status2) {
+            (ExitStatus::Code(code1), ExitStatus::Code(code2)) => {
+                self.class(*code1) == self.class(*code2)
+            }
+            _ => status1 == status2,
+        }
+    }
+
+    fn class(&self, code: i32) -> i32 {
+        match self {
+            ExitStatusEquivalence::Exact => code,
+            ExitStatusEquivalence::NonZero => i32::from(code != 0),
+            ExitStatusEquivalence::Groups(groups) => groups
+                .iter()
+                .find(|group| group.contains(&code))
+                .map_or(code, |group| group[0]),
+        }
+    }
+}
+
+/// Parses `exact`, `nonzero` or groups of equal codes like `1:2,125:126:127`.
+impl FromStr for ExitStatusEquivalence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "exact" => Ok(ExitStatusEquivalence::Exact),
+            "nonzero" => Ok(ExitStatusEquivalence::NonZero),
+            _ => s
+                .split(',')
+                .map(|group| {
+                    group
+                        .split(':')
+                        .map(|code| {
+                            code.trim()
+                                .parse()
+                                .map_err(|_| format!("invalid exit code '{code}'"))
+                        })
+                        .collect()
+                })
+                .collect::<Result<_, _>>()
+                .map(ExitStatusEquivalence::Groups),
+        }
+    }
+}
+
+/// Holds the [`ExitStatus`] of the last execution, which the
+/// [`ExitStatusCommandExecutor`](super::executor::ExitStatusCommandExecutor) or the
+/// [`ForkServerExecutor`](super::forkserver::ForkServerExecutor) records when reaping the binary under test.
+///
+/// Does not observe anything if the binary under test was killed on timeout.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ExitStatusObserver {
+    name: Cow<'static, str>,
+    pub status: Option<ExitStatus>,
+}
+
+impl ExitStatusObserver {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
+            status: None,
+        }
+    }
+}
+
+impl<S> Observer<S> for ExitStatusObserver
+where
+    S: UsesInput,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.status = None;
+        Ok(())
+    }
+}
+
+impl Named for ExitStatusObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+/// Compares the exit statuses of two implementations under an [`ExitStatusEquivalence`]. Unlike
+/// [`DiffExitKindFeedback`](libafl::feedbacks::DiffExitKindFeedback), this distinguishes exit codes and signals.
+pub struct ExitStatusDiffFeedback {
+    observer1: Handle<ExitStatusObserver>,
+    observer2: Handle<ExitStatusObserver>,
+    equivalence: ExitStatusEquivalence,
+    diff: Option<(ExitStatus, ExitStatus)>,
+}
+
+impl ExitStatusDiffFeedback {
+    pub fn new(
+        observer1: &ExitStatusObserver,
+        observer2: &ExitStatusObserver,
+        equivalence: ExitStatusEquivalence,
+    ) -> Self {
+        Self {
+            observer1: observer1.handle(),
+            observer2: observer2.handle(),
+            equivalence,
+            diff: None,
+        }
+    }
+}
+
+impl<S> Feedback<S> for ExitStatusDiffFeedback
+where
+    S: State,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let get = |handle: &Handle<ExitStatusObserver>| {
+            observers.get(handle).ok_or_else(|| {
+                Error::illegal_argument(format!(
+                    "ExitStatusDiffFeedback: observer {} not found",
+                    handle.name()
+                ))
+            })
+        };
+        // a missing status means the execution was cut short, which is judged by the exit kind instead
+        self.diff = match (get(&self.observer1)?.status, get(&self.observer2)?.status) {
+            (Some(s1), Some(s2)) if !self.equivalence.equivalent(&s1, &s2) => Some((s1, s2)),
+            _ => None,
+        };
+        Ok(self.diff.is_some())
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _observers: &OT,
+        testcase: &mut libafl::prelude::Testcase<<S>::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        if let Some((status1, status2)) = self.diff.take() {
+            testcase
+                .metadata_map_mut()
+                .insert(ExitStatusDiffMetadata { status1, status2 });
+        }
+        Ok(())
+    }
+}
+
+impl Named for ExitStatusDiffFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("ExitStatusDiffFeedback")
+    }
+}
+
+#[derive(Debug, SerdeAny, Serialize, Deserialize)]
+struct ExitStatusDiffMetadata {
+    status1: ExitStatus,
+    status2: ExitStatus,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_equivalences() {
+        assert_eq!("exact".parse(), Ok(ExitStatusEquivalence::Exact));
+        assert_eq!("nonzero".parse(), Ok(ExitStatusEquivalence::NonZero));
+        assert_eq!(
+            "1:2, 125 :126:127".parse(),
+            Ok(ExitStatusEquivalence::Groups(vec![
+                vec![1, 2],
+                vec![125, 126, 127]
+            ]))
+        );
+        assert_eq!(
+            "3".parse(),
+            Ok(ExitStatusEquivalence::Groups(vec![vec![3]]))
+        );
+        for invalid in ["", "1:", "1,,2", "one:2", "Exact"] {
+            assert!(
+                invalid.parse::<ExitStatusEquivalence>().is_err(),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn compares_codes_by_group() {
+        let groups = "1:2,125:126:127".parse::<ExitStatusEquivalence>().unwrap();
+        let equivalent =
+            |code1, code2| groups.equivalent(&ExitStatus::Code(code1), &ExitStatus::Code(code2));
+        assert!(equivalent(1, 2));
+        assert!(equivalent(127, 125));
+        assert!(equivalent(3, 3));
+        assert!(!equivalent(2, 125));
+        assert!(!equivalent(0, 1));
+        assert!(
+            ExitStatusEquivalence::NonZero.equivalent(&ExitStatus::Code(1), &ExitStatus::Code(2))
+        );
+        assert!(
+            !ExitStatusEquivalence::NonZero.equivalent(&ExitStatus::Code(0), &ExitStatus::Code(2))
+        );
+        // signals are never equivalent to codes or other signals
+        assert!(!ExitStatusEquivalence::NonZero
+            .equivalent(&ExitStatus::Code(1), &ExitStatus::Signal(1)));
+        assert!(!ExitStatusEquivalence::NonZero
+            .equivalent(&ExitStatus::Signal(6), &ExitStatus::Signal(11)));
+    }
+
+    #[test]
+    fn decodes_wait_statuses() {
+        assert_eq!(ExitStatus::from_wait_status(3 << 8), ExitStatus::Code(3));
+        assert_eq!(
+            ExitStatus::from_wait_status(libc::SIGSEGV),
+            ExitStatus::Signal(libc::SIGSEGV)
+        );
+        assert_eq!(
+            "signal 9".parse::<ExitStatus>().unwrap().exit_kind(),
+            ExitKind::Oom
+        );
+        assert_eq!("exit 1".parse::<ExitStatus>().unwrap(), ExitStatus::Code(1));
+        assert!("exit".parse::<ExitStatus>().is_err());
+    }
+
+    #[test]
+    fn parses_displayed_statuses() {
+        for status in [
+            ExitStatus::Code(0),
+            ExitStatus::Code(125),
+            ExitStatus::Signal(libc::SIGSEGV),
+        ] {
+            assert_eq!(status.to_string().parse::<ExitStatus>().unwrap(), status);
+        }
+    }
+}
@@ -0,0 +1,507 @@
+use std::{
+    borrow::Cow,
+    ffi::{CString, OsStr},
+    fs::{self, File},
+    io::Write,
+    os::unix::{
+        ffi::OsStrExt,
+        fs::{symlink, PermissionsExt},
+    },
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use libafl::{
+    mutators::{MutationResult, Mutator},
+    state::HasRand,
+    Error,
+};
+
+use libafl_bolts::{prelude::Rand, tuples::tuple_list_type, tuple_list, Named};
+
+use super::stdio::vec_string_mapper;
+
+/// Fixed point in time all generated timestamps are relative to (2020-01-01T00:00:00Z).
+pub const FIXTURE_EPOCH: i64 = 1_577_836_800;
+
+/// A deterministic directory tree, built from scratch before every execution.
+///
+/// Paths are relative to the fixture root and stored as raw bytes, so they may contain newlines or invalid UTF-8.
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, Default, PartialEq, Eq)]
+pub struct Fixture {
+    pub entries: Vec<FixtureEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct FixtureEntry {
+    pub path: Vec<u8>,
+    pub kind: FixtureEntryKind,
+    pub mode: u32,
+    pub mtime: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum FixtureEntryKind {
+    /// A regular file, extended sparsely by `sparse` zero bytes after `content`. Kept apart from the content, so the
+    /// file keeps its content when byte mutators change the length of it.
+    File {
+        content: Vec<u8>,
+        #[serde(default)]
+        sparse: u64,
+    },
+    Directory,
+    Symlink { target: Vec<u8> },
+    Fifo,
+}
+
+impl FixtureEntry {
+    fn depth(&self) -> usize {
+        self.path.iter().filter(|&&b| b == b'/').count()
+    }
+}
+
+impl Fixture {
+    /// Recreates the fixture in `root`, removing anything that was left there by a previous execution.
+    ///
+    /// # Errors on
+    ///
+    /// This function will return an error if the underlying os functions error.
+    pub fn build(&self, root: &Path) -> Result<(), Error> {
+        clear_dir(root)?;
+        fs::create_dir_all(root)
+            .map_err(|e| Error::os_error(e, "Could not create fixture root"))?;
+
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|e| e.depth());
+
+        for entry in &entries {
+            let path = root.join(OsStr::from_bytes(&entry.path));
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| Error::os_error(e, "Could not create fixture parent"))?;
+            }
+            match &entry.kind {
+                FixtureEntryKind::File { content, sparse } => {
+                    let mut file = File::create(&path)
+                        .map_err(|e| Error::os_error(e, "Could not create fixture file"))?;
+                    file.write_all(content)
+                        .map_err(|e| Error::os_error(e, "Could not write fixture file"))?;
+                    if *sparse > 0 {
+                        file.set_len(content.len() as u64 + sparse)
+                            .map_err(|e| Error::os_error(e, "Could not extend fixture file"))?;
+                    }
+                }
+                FixtureEntryKind::Directory => fs::create_dir_all(&path)
+                    .map_err(|e| Error::os_error(e, "Could not create fixture directory"))?,
+                FixtureEntryKind::Symlink { target } => symlink(OsStr::from_bytes(target), &path)
+                    .map_err(|e| Error::os_error(e, "Could not create fixture symlink"))?,
+                FixtureEntryKind::Fifo => {
+                    let c_path = c_path(&path)?;
+                    if unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) } != 0 {
+                        return Err(Error::os_error(
+                            std::io::Error::last_os_error(),
+                            "Could not create fixture fifo",
+                        ));
+                    }
+                }
+            }
+        }
+
+        // deepest first, so restrictive directory modes do not prevent updating their children
+        for entry in entries.iter().rev() {
+            let path = root.join(OsStr::from_bytes(&entry.path));
+            set_mtime(&path, entry.mtime)?;
+            if !matches!(entry.kind, FixtureEntryKind::Symlink { .. }) {
+                fs::set_permissions(&path, fs::Permissions::from_mode(entry.mode))
+                    .map_err(|e| Error::os_error(e, "Could not set fixture permissions"))?;
+            }
+        }
+        set_mtime(root, FIXTURE_EPOCH)?;
+        // listed as `..`, and otherwise touched by whatever else is created next to the root between executions
+        match root.parent() {
+            Some(parent) => set_mtime(parent, FIXTURE_EPOCH),
+            None => Ok(()),
+        }
+    }
+
+    /// All paths in this fixture, usable as operands.
+    pub fn paths(&self) -> impl Iterator<Item = &Vec<u8>> {
+        self.entries.iter().map(|e| &e.path)
+    }
+
+    pub fn directories(&self) -> impl Iterator<Item = &Vec<u8>> {
+        self.entries
+            .iter()
+            .filter(|e| e.kind == FixtureEntryKind::Directory)
+            .map(|e| &e.path)
+    }
+
+    /// The contents of all regular files, as byte fields named by [`file_field_name`].
+    pub fn file_fields(&self) -> impl Iterator<Item = (Cow<'static, str>, &Vec<u8>)> {
+        self.entries.iter().filter_map(|e| match &e.kind {
+            FixtureEntryKind::File { content, .. } => Some((file_field_name(&e.path), content)),
+            _ => None,
+        })
+    }
+
+    pub fn file_fields_mut(&mut self) -> impl Iterator<Item = (Cow<'static, str>, &mut Vec<u8>)> {
+        self.entries.iter_mut().filter_map(|e| match &mut e.kind {
+            FixtureEntryKind::File { content, .. } => Some((file_field_name(&e.path), content)),
+            _ => None,
+        })
+    }
+}
+
+/// Name of the byte field holding the contents of the file at `path`, see [`HasByteFields`](super::mapping::HasByteFields).
+pub fn file_field_name(path: &[u8]) -> Cow<'static, str> {
+    Cow::Owned(format!("file:{}", String::from_utf8_lossy(path)))
+}
+
+impl std::fmt::Display for Fixture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[")?;
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            let path = vec_string_mapper(&Some(entry.path.clone()));
+            match &entry.kind {
+                FixtureEntryKind::File { content, sparse } => write!(
+                    f,
+                    "file '{path}' ({} bytes, content '{}')",
+                    content.len() as u64 + sparse,
+                    vec_string_mapper(&Some(content.clone()))
+                )?,
+                FixtureEntryKind::Directory => write!(f, "dir '{path}'")?,
+                FixtureEntryKind::Symlink { target } => write!(
+                    f,
+                    "symlink '{path}' -> '{}'",
+                    vec_string_mapper(&Some(target.clone()))
+                )?,
+                FixtureEntryKind::Fifo => write!(f, "fifo '{path}'")?,
+            }
+            write!(f, " mode {:o} mtime {}", entry.mode, entry.mtime)?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// Removes `root` recursively, restoring permissions of directories the previous execution left inaccessible.
+fn clear_dir(root: &Path) -> Result<(), Error> {
+    if fs::symlink_metadata(root).is_err() {
+        return Ok(());
+    }
+    fn make_accessible(dir: &Path) {
+        let _ = fs::set_permissions(dir, fs::Permissions::from_mode(0o700));
+        if let Ok(read_dir) = fs::read_dir(dir) {
+            for entry in read_dir.flatten() {
+                if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                    make_accessible(&entry.path());
+                }
+            }
+        }
+    }
+    make_accessible(root);
+    // only remove the contents, the root itself may be a mount point
+    for entry in fs::read_dir(root)
+        .map_err(|e| Error::os_error(e, "Could not read fixture root"))?
+        .flatten()
+    {
+        let path = entry.path();
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        }
+        .map_err(|e| Error::os_error(e, "Could not clear fixture root"))?;
+    }
+    Ok(())
+}
+
+fn c_path(path: &Path) -> Result<CString, Error> {
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| Error::illegal_argument("Fixture path contains a NUL byte"))
+}
+
+fn set_mtime(path: &Path, mtime: i64) -> Result<(), Error> {
+    let c_path = c_path(path)?;
+    let time = libc::timespec {
+        tv_sec: mtime,
+        tv_nsec: 0,
+    };
+    let times = [time, time];
+    if unsafe {
+        libc::utimensat(
+            libc::AT_FDCWD,
+            c_path.as_ptr(),
+            times.as_ptr(),
+            libc::AT_SYMLINK_NOFOLLOW,
+        )
+    } != 0
+    {
+        return Err(Error::os_error(
+            std::io::Error::last_os_error(),
+            "Could not set fixture timestamps",
+        ));
+    }
+    Ok(())
+}
+
+/// The scratch area a fixture is built in. Located in `/dev/shm/` to stay in memory, nested in a directory of its own
+/// so that its parent, which [`Fixture::build`] pins the timestamps of, only changes along with the fixture.
+pub fn fixture_root<ID: ToString>(id: ID) -> PathBuf {
+    PathBuf::from(format!("/dev/shm/fixture{}/root", id.to_string()))
+}
+
+/// Interface for inputs that run against a [`Fixture`] and name parts of it as operands.
+pub trait HasFixture {
+    fn fixture(&self) -> &Fixture;
+    fn fixture_mut(&mut self) -> &mut Fixture;
+    fn operands_mut(&mut self) -> &mut Vec<Vec<u8>>;
+}
+
+const NAME_PARTS: [&[u8]; 12] = [
+    b"a",
+    b"b",
+    b"file",
+    b"dir",
+    b".hidden",
+    b"-dash",
+    b" space",
+    b"new\nline",
+    b"tab\t",
+    b"\xff\xfeinvalid",
+    b"\xc3\xa4",
+    b"x.tar.gz",
+];
+
+const MODES: [u32; 8] = [0o644, 0o755, 0o600, 0o000, 0o4755, 0o2755, 0o1777, 0o444];
+
+fn generate_name<R: Rand>(rand: &mut R) -> Vec<u8> {
+    let mut name = NAME_PARTS[rand.below(NAME_PARTS.len())].to_vec();
+    if rand.coinflip(0.5) {
+        name.extend(rand.between(0, 99).to_string().as_bytes());
+    }
+    name
+}
+
+fn generate_mtime<R: Rand>(rand: &mut R) -> i64 {
+    // mix of very old, recent (relative to the epoch) and future timestamps to hit the different ls formats
+    match rand.below(3) {
+        0 => FIXTURE_EPOCH - rand.between(0, 60 * 60 * 24 * 365 * 30) as i64,
+        1 => FIXTURE_EPOCH - rand.between(0, 60 * 60 * 24 * 30) as i64,
+        _ => FIXTURE_EPOCH + rand.between(0, 60 * 60 * 24 * 365 * 10) as i64,
+    }
+}
+
+fn generate_entry<R: Rand>(rand: &mut R, fixture: &Fixture) -> FixtureEntry {
+    let directories = fixture.directories().cloned().collect::<Vec<_>>();
+    let mut path = if !directories.is_empty() && rand.coinflip(0.5) {
+        let mut parent = directories[rand.below(directories.len())].clone();
+        parent.push(b'/');
+        parent
+    } else {
+        Vec::new()
+    };
+    path.extend(generate_name(rand));
+
+    let kind = match rand.below(6) {
+        0 | 1 => {
+            let content = (0..rand.between(0, 64))
+                .map(|_| rand.between(0x20, 0x7E) as u8)
+                .collect::<Vec<_>>();
+            let sparse = if rand.coinflip(0.2) {
+                rand.between(0, 1 << 20) as u64
+            } else {
+                0
+            };
+            FixtureEntryKind::File { content, sparse }
+        }
+        2 => FixtureEntryKind::Directory,
+        3 => {
+            let paths = fixture.paths().cloned().collect::<Vec<_>>();
+            let target = match rand.below(4) {
+                // dangling
+                0 => b"does-not-exist".to_vec(),
+                // looping on itself
+                1 => path.rsplit(|&b| b == b'/').next().unwrap_or(&path).to_vec(),
+                _ if !paths.is_empty() => paths[rand.below(paths.len())].clone(),
+                _ => b".".to_vec(),
+            };
+            FixtureEntryKind::Symlink { target }
+        }
+        4 => FixtureEntryKind::Fifo,
+        _ => FixtureEntryKind::Directory,
+    };
+
+    let mode = match kind {
+        FixtureEntryKind::Directory => {
+            if rand.coinflip(0.9) {
+                0o755
+            } else {
+                MODES[rand.below(MODES.len())]
+            }
+        }
+        _ => MODES[rand.below(MODES.len())],
+    };
+
+    FixtureEntry {
+        path,
+        kind,
+        mode,
+        mtime: generate_mtime(rand),
+    }
+}
+
+/// Generates a random fixture with up to `max_entries` entries.
+pub fn generate_fixture<R: Rand>(rand: &mut R, max_entries: usize) -> Fixture {
+    let mut fixture = Fixture::default();
+    for _ in 0..rand.between(1, max_entries.max(1)) {
+        add_entry(rand, &mut fixture);
+    }
+    fixture
+}
+
+fn add_entry<R: Rand>(rand: &mut R, fixture: &mut Fixture) -> bool {
+    let entry = generate_entry(rand, fixture);
+    if fixture.paths().any(|p| *p == entry.path) {
+        return false;
+    }
+    fixture.entries.push(entry);
+    true
+}
+
+/// Picks an operand for a fixture-based input: mostly existing paths, sometimes names that do not exist.
+pub fn generate_operand<R: Rand>(rand: &mut R, fixture: &Fixture) -> Vec<u8> {
+    let paths = fixture.paths().collect::<Vec<_>>();
+    match rand.below(8) {
+        0 => b".".to_vec(),
+        1 => b"does-not-exist".to_vec(),
+        _ if !paths.is_empty() => paths[rand.below(paths.len())].clone(),
+        _ => b".".to_vec(),
+    }
+}
+
+pub struct FixtureAddEntryMutator;
+impl<I, S> Mutator<I, S> for FixtureAddEntryMutator
+where
+    I: HasFixture,
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        if add_entry(state.rand_mut(), input.fixture_mut()) {
+            Ok(MutationResult::Mutated)
+        } else {
+            Ok(MutationResult::Skipped)
+        }
+    }
+}
+
+impl Named for FixtureAddEntryMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("FixtureAddEntryMutator")
+    }
+}
+
+pub struct FixtureRemoveEntryMutator;
+impl<I, S> Mutator<I, S> for FixtureRemoveEntryMutator
+where
+    I: HasFixture,
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let entries = &mut input.fixture_mut().entries;
+        if entries.is_empty() {
+            return Ok(MutationResult::Skipped);
+        }
+        let removed = entries.remove(state.rand_mut().below(entries.len()));
+        // drop children of removed directories
+        let mut prefix = removed.path;
+        prefix.push(b'/');
+        entries.retain(|e| !e.path.starts_with(&prefix));
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for FixtureRemoveEntryMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("FixtureRemoveEntryMutator")
+    }
+}
+
+pub struct FixtureAttributeMutator;
+impl<I, S> Mutator<I, S> for FixtureAttributeMutator
+where
+    I: HasFixture,
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+        let entries = &mut input.fixture_mut().entries;
+        if entries.is_empty() {
+            return Ok(MutationResult::Skipped);
+        }
+        let index = rand.below(entries.len());
+        let entry = &mut entries[index];
+        match rand.below(3) {
+            0 => entry.mode = MODES[rand.below(MODES.len())],
+            1 => entry.mtime = generate_mtime(rand),
+            _ => match &mut entry.kind {
+                FixtureEntryKind::File { sparse, .. } => *sparse = rand.between(0, 1 << 20) as u64,
+                _ => return Ok(MutationResult::Skipped),
+            },
+        }
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for FixtureAttributeMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("FixtureAttributeMutator")
+    }
+}
+
+pub struct FixtureOperandMutator;
+impl<I, S> Mutator<I, S> for FixtureOperandMutator
+where
+    I: HasFixture,
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+        let operand = generate_operand(rand, input.fixture());
+        let operands = input.operands_mut();
+        match rand.below(3) {
+            0 if !operands.is_empty() => {
+                operands.remove(rand.below(operands.len()));
+            }
+            1 if !operands.is_empty() => {
+                let index = rand.below(operands.len());
+                operands[index] = operand;
+            }
+            _ => operands.push(operand),
+        }
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for FixtureOperandMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("FixtureOperandMutator")
+    }
+}
+
+pub fn fixture_mutators() -> tuple_list_type!(
+    FixtureAddEntryMutator,
+    FixtureRemoveEntryMutator,
+    FixtureAttributeMutator,
+    FixtureOperandMutator
+) {
+    tuple_list!(
+        FixtureAddEntryMutator,
+        FixtureRemoveEntryMutator,
+        FixtureAttributeMutator,
+        FixtureOperandMutator
+    )
+}
@@ -0,0 +1,362 @@
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    fs::{self, File},
+    io::{self, Read, Write},
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::{ffi::OsStrExt, process::CommandExt},
+    },
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    time::Duration,
+};
+
+use libafl::{
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::UsesInput,
+    observers::{ObserversTuple, StdErrObserver, StdOutObserver, UsesObservers},
+    state::{HasExecutions, UsesState},
+    Error,
+};
+use libafl_bolts::{
+    shmem::ShMemDescription,
+    tuples::{Handle, MatchNameRef, RefIndexable},
+};
+
+use super::{
+    executor::{absolute_path, pseudo_pipe, ExtractsToCommand},
+    exit_status::{ExitStatus, ExitStatusObserver},
+    fixture::fixture_root,
+    implementation::{CoverageMode, Implementation, NO_COVERAGE_ENV},
+};
+
+/// If set, the preload turns the binary under test into a fork server, see `serve_forks` in the preload
+pub static FORK_SERVER_ENV: &str = "COREUTILS_DIFFERENTIAL_FORK_SERVER";
+/// The fork server reads test cases from this file descriptor, like AFL's
+const CONTROL_FD: i32 = 198;
+/// The fork server reports the pid and wait status of each child to this file descriptor
+const STATUS_FD: i32 = 199;
+/// Sent by the fork server once it is ready to receive test cases
+const FORK_SERVER_HELLO: i32 = i32::from_ne_bytes(*b"fsrv");
+/// How long the binary may take to reach `main` and start serving
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+/// The same as that of [`CoverageCommandExecutor`](super::executor::CoverageCommandExecutor)
+const EXEC_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A binary under test stopped before `main` by the preload, forking a child for each test case
+#[derive(Debug)]
+struct ForkServer {
+    process: Child,
+    control: File,
+    status: File,
+}
+
+fn pipe() -> Result<(OwnedFd, OwnedFd), Error> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return Err(Error::os_error(
+            io::Error::last_os_error(),
+            "Could not create a pipe to the fork server",
+        ));
+    }
+    Ok(unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) })
+}
+
+impl ForkServer {
+    /// Starts `command` as fork server and waits for it to be ready.
+    fn spawn(mut command: Command) -> Result<Self, Error> {
+        let (control_read, control_write) = pipe()?;
+        let (status_read, status_write) = pipe()?;
+        let (control_fd, status_fd) = (control_read.as_raw_fd(), status_write.as_raw_fd());
+        unsafe {
+            command.pre_exec(move || {
+                // duplicated file descriptors are inherited, unlike the pipes created with `O_CLOEXEC`
+                if libc::dup2(control_fd, CONTROL_FD) < 0 || libc::dup2(status_fd, STATUS_FD) < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let process = command
+            .env(FORK_SERVER_ENV, "1")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| Error::os_error(e, "Could not start the fork server"))?;
+
+        let mut server = Self {
+            process,
+            control: File::from(control_write),
+            status: File::from(status_read),
+        };
+        match server.read_i32(STARTUP_TIMEOUT) {
+            Ok(Some(FORK_SERVER_HELLO)) => Ok(server),
+            _ => Err(Error::illegal_state(
+                "The fork server did not start, the binary needs to be dynamically linked and preloaded with \
+                 setup_guard_redirection",
+            )),
+        }
+    }
+
+    /// Reads a native endian `i32` from the status pipe, `None` on timeout
+    fn read_i32(&mut self, timeout: Duration) -> Result<Option<i32>, Error> {
+        let mut poll_fd = libc::pollfd {
+            fd: self.status.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout = timeout.as_millis().try_into().unwrap_or(i32::MAX);
+        match unsafe { libc::poll(&mut poll_fd, 1, timeout) } {
+            0 => Ok(None),
+            ready if ready < 0 => Err(Error::os_error(
+                io::Error::last_os_error(),
+                "Could not wait for the fork server",
+            )),
+            _ => {
+                let mut bytes = [0; 4];
+                self.status
+                    .read_exact(&mut bytes)
+                    .map_err(|e| Error::os_error(e, "The fork server exited"))?;
+                Ok(Some(i32::from_ne_bytes(bytes)))
+            }
+        }
+    }
+
+    /// Runs a test case, see `read_request` in the preload for its `fields`, and returns how the child terminated, or
+    /// `None` if it was killed on timeout.
+    fn run(&mut self, fields: &[&[u8]], timeout: Duration) -> Result<Option<ExitStatus>, Error> {
+        let mut request = Vec::new();
+        request.extend_from_slice(&u32::try_from(fields.len()).unwrap().to_ne_bytes());
+        for field in fields {
+            request.extend_from_slice(&u32::try_from(field.len()).unwrap().to_ne_bytes());
+            request.extend_from_slice(field);
+        }
+        self.control
+            .write_all(&request)
+            .map_err(|e| Error::os_error(e, "Could not send the test case to the fork server"))?;
+
+        let pid = self
+            .read_i32(STARTUP_TIMEOUT)?
+            .ok_or_else(|| Error::illegal_state("The fork server did not fork"))?;
+        let status = match self.read_i32(timeout)? {
+            Some(status) => status,
+            None => {
+                // the child is the leader of its own process group, which also holds the processes it spawned
+                unsafe { libc::kill(-pid, libc::SIGKILL) };
+                self.read_i32(STARTUP_TIMEOUT)?.ok_or_else(|| {
+                    Error::illegal_state("The fork server did not reap a timed out child")
+                })?;
+                return Ok(None);
+            }
+        };
+        Ok(Some(ExitStatus::from_wait_status(status)))
+    }
+}
+
+impl Drop for ForkServer {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+/// Runs an implementation with `fork_server` set through a [`ForkServer`], instead of spawning its binary for every
+/// input like the inner executor does. The binary is started once and stopped by the preload before `main`, from
+/// where a child is forked for each input, saving the `exec` and dynamic linking of every run. The server is
+/// restarted on the next input if it fails.
+///
+/// `stdout` and `stderr` are written to files and passed to the observers of the inner executor, as it would, and the
+/// exit status the fork server reaped the child with is recorded in the [`ExitStatusObserver`]. Other
+/// implementations and configurators not supporting it, see
+/// [`CoverageConfigurator::SUPPORTS_FORK_SERVER`](super::executor::CoverageConfigurator::SUPPORTS_FORK_SERVER), run
+/// the inner executor unchanged.
+#[derive(Debug)]
+pub struct ForkServerExecutor<E> {
+    inner: E,
+    enabled: bool,
+    util: PathBuf,
+    preload_path: PathBuf,
+    args: Vec<String>,
+    env: BTreeMap<String, String>,
+    coverage: CoverageMode,
+    shmem_coverage_description: String,
+    fixture_path: PathBuf,
+    temp_file_stdin_path: String,
+    stdout_path: PathBuf,
+    stderr_path: PathBuf,
+    stdout_observer: Handle<StdOutObserver>,
+    stderr_observer: Handle<StdErrObserver>,
+    exit_status_observer: Handle<ExitStatusObserver>,
+    server: Option<ForkServer>,
+}
+
+impl<E> ForkServerExecutor<E> {
+    /// `id` is the one `inner` was configured with, the observers are among those of `inner`. `supported` is whether
+    /// the configurator of `inner` can be replaced by a fork server.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        inner: E,
+        shmem_coverage_description: &ShMemDescription,
+        implementation: &Implementation,
+        id: &str,
+        stdout_observer: Handle<StdOutObserver>,
+        stderr_observer: Handle<StdErrObserver>,
+        exit_status_observer: Handle<ExitStatusObserver>,
+        supported: bool,
+    ) -> Self {
+        Self {
+            inner,
+            enabled: implementation.fork_server && supported,
+            util: absolute_path(&implementation.binary),
+            preload_path: absolute_path(&implementation.preload),
+            args: implementation.args.clone(),
+            env: implementation.env.clone(),
+            coverage: implementation.coverage,
+            shmem_coverage_description: serde_json::to_string(&shmem_coverage_description)
+                .expect("Could not stringify shared memory description"),
+            fixture_path: fixture_root(id),
+            temp_file_stdin_path: format!("/dev/shm/temp{id}"),
+            stdout_path: PathBuf::from(format!("/dev/shm/stdout{id}")),
+            stderr_path: PathBuf::from(format!("/dev/shm/stderr{id}")),
+            stdout_observer,
+            stderr_observer,
+            exit_status_observer,
+            server: None,
+        }
+    }
+
+    fn spawn_server(&self) -> Result<ForkServer, Error> {
+        let mut command = Command::new(&self.util);
+        command
+            .envs(&self.env)
+            .env("LD_PRELOAD", &self.preload_path);
+        match self.coverage {
+            CoverageMode::Guard => command.arg(&self.shmem_coverage_description),
+            CoverageMode::None | CoverageMode::Novelty => command.env(NO_COVERAGE_ENV, "1"),
+        };
+        ForkServer::spawn(command)
+    }
+
+    fn run_in_server<I: ExtractsToCommand>(
+        &mut self,
+        input: &I,
+    ) -> Result<Option<ExitStatus>, Error> {
+        let cwd = match input.get_fixture() {
+            Some(fixture) => {
+                fixture.build(&self.fixture_path)?;
+                self.fixture_path.as_os_str()
+            }
+            None => OsStr::new(""),
+        };
+        pseudo_pipe(input.get_stdin(), &self.temp_file_stdin_path)?;
+        let input_args = input.get_args();
+        let fields = [
+            cwd,
+            OsStr::new(&self.temp_file_stdin_path),
+            self.stdout_path.as_os_str(),
+            self.stderr_path.as_os_str(),
+            self.util.as_os_str(),
+        ]
+        .into_iter()
+        .chain(self.args.iter().map(OsStr::new))
+        .chain(input_args.iter().map(AsRef::<OsStr>::as_ref))
+        .map(OsStrExt::as_bytes)
+        .collect::<Vec<_>>();
+
+        if self.server.is_none() {
+            self.server = Some(self.spawn_server()?);
+        }
+        let server = self.server.as_mut().expect("started above");
+        let result = server.run(&fields, EXEC_TIMEOUT);
+        if result.is_err() {
+            // restarted on the next input
+            self.server = None;
+        }
+        result
+    }
+}
+
+fn read_output(path: &Path) -> Result<Vec<u8>, Error> {
+    fs::read(path)
+        .map_err(|e| Error::os_error(e, "Could not read the output of the fork server's child"))
+}
+
+impl<E, EM, Z> Executor<EM, Z> for ForkServerExecutor<E>
+where
+    E: Executor<EM, Z> + HasObservers,
+    E::State: HasExecutions,
+    <E::State as UsesInput>::Input: ExtractsToCommand,
+    EM: UsesState<State = E::State>,
+    Z: UsesState<State = E::State>,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut Self::State,
+        mgr: &mut EM,
+        input: &<Self::State as UsesInput>::Input,
+    ) -> Result<ExitKind, Error> {
+        if !self.enabled {
+            return self.inner.run_target(fuzzer, state, mgr, input);
+        }
+
+        *state.executions_mut() += 1;
+        self.inner
+            .observers_mut()
+            .pre_exec_child_all(state, input)?;
+        let status = self.run_in_server(input)?;
+        let exit_kind = status.map_or(ExitKind::Timeout, |status| status.exit_kind());
+        self.inner
+            .observers_mut()
+            .post_exec_child_all(state, input, &exit_kind)?;
+
+        let stdout = read_output(&self.stdout_path)?;
+        let stderr = read_output(&self.stderr_path)?;
+        let mut observers = self.inner.observers_mut();
+        let not_found = |name: &str| {
+            Error::illegal_argument(format!("ForkServerExecutor: observer {name} not found"))
+        };
+        observers
+            .get_mut(&self.stdout_observer)
+            .ok_or_else(|| not_found(self.stdout_observer.name()))?
+            .stdout = Some(stdout);
+        observers
+            .get_mut(&self.stderr_observer)
+            .ok_or_else(|| not_found(self.stderr_observer.name()))?
+            .stderr = Some(stderr);
+        observers
+            .get_mut(&self.exit_status_observer)
+            .ok_or_else(|| not_found(self.exit_status_observer.name()))?
+            .status = status;
+        Ok(exit_kind)
+    }
+}
+
+impl<E> UsesState for ForkServerExecutor<E>
+where
+    E: UsesState,
+{
+    type State = E::State;
+}
+
+impl<E> UsesObservers for ForkServerExecutor<E>
+where
+    E: UsesObservers,
+{
+    type Observers = E::Observers;
+}
+
+impl<E> HasObservers for ForkServerExecutor<E>
+where
+    E: HasObservers,
+{
+    fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
+        self.inner.observers()
+    }
+
+    fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers> {
+        self.inner.observers_mut()
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use libafl::Error;
+use serde::Deserialize;
+
+use super::{novelty::NOVELTY_MAP_SIZE, sanitizer::SANITIZER_ENV, shmem::get_coverage_shmem_size};
+
+/// The preload recording exit statuses and redirecting coverage, unless an implementation names another one
+pub static DEFAULT_PRELOAD_PATH: &str = "./target/release/libsetup_guard_redirection.so";
+/// Set for binaries without coverage instrumentation, see `NO_COVERAGE_ENV` in the preload
+pub static NO_COVERAGE_ENV: &str = "COREUTILS_DIFFERENTIAL_NO_COVERAGE";
+/// Replaced by the name of the fuzzed utility in the binary paths and arguments of an [`ImplementationEntry`]
+const UTIL_PLACEHOLDER: &str = "{util}";
+
+/// How the coverage of an implementation is observed
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverageMode {
+    /// built with `-fsanitize-coverage=trace-pc-guard` and linked against `coverage.c`, the guards are copied to
+    /// the shared coverage map by the preload
+    #[default]
+    Guard,
+    /// not instrumented, the coverage map stays empty
+    None,
+    /// not instrumented, the coverage map is filled with features of the outputs by
+    /// [`OutputNoveltyExecutor`](super::novelty::OutputNoveltyExecutor)
+    Novelty,
+}
+
+/// An implementation as listed in a file like `implementations.json`
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct ImplementationEntry {
+    /// the path of the binary, `{util}` is replaced by the name of the utility
+    binary: String,
+    /// arguments passed before those of the input, e.g. the applet of a multi-call binary like busybox, `{util}` is
+    /// replaced as well
+    #[serde(default)]
+    args: Vec<String>,
+    /// the library preloaded into the binary
+    #[serde(default = "default_preload")]
+    preload: String,
+    #[serde(default)]
+    coverage: CoverageMode,
+    /// variables set in addition to the fuzzer's environment, overriding [`SANITIZER_ENV`]
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    /// the version of the build, e.g. a release tag, detected from the output of `--version` if not given
+    #[serde(default)]
+    version: Option<String>,
+    /// run through a fork server in the preload instead of spawning the binary for every input, see
+    /// [`ForkServerExecutor`](super::forkserver::ForkServerExecutor)
+    #[serde(default)]
+    fork_server: bool,
+    /// a build of the same sources with `--coverage`, run for the `gcov` feature, `{util}` is replaced as well
+    #[serde(default)]
+    gcov: Option<String>,
+}
+
+fn default_preload() -> String {
+    DEFAULT_PRELOAD_PATH.to_string()
+}
+
+/// An implementation of the fuzzed utility, resolved from an [`ImplementationEntry`]
+#[derive(Clone, Debug)]
+pub struct Implementation {
+    pub name: String,
+    pub binary: String,
+    pub args: Vec<String>,
+    pub preload: String,
+    pub coverage: CoverageMode,
+    pub env: BTreeMap<String, String>,
+    /// recorded in the metadata of objectives, to tell builds of the same implementation apart
+    pub version: Option<String>,
+    pub fork_server: bool,
+    #[cfg_attr(not(feature = "gcov"), allow(dead_code))]
+    pub gcov: Option<String>,
+    /// the size of the binary's coverage map, as returned by [`get_coverage_shmem_size`]
+    pub coverage_shmem_size: usize,
+}
+
+impl Implementation {
+    /// Loads the implementations called `names` from the file at `path` and resolves their binaries for `util`.
+    pub fn load(path: &Path, names: &[String], util: &str) -> Result<Vec<Self>, Error> {
+        let mut entries: BTreeMap<String, ImplementationEntry> = serde_json::from_reader(
+            File::open(path).map_err(|e| Error::os_error(e, "Could not open implementations"))?,
+        )
+        .map_err(|e| Error::serialize(format!("Could not parse implementations: {e}")))?;
+
+        let mut seen = BTreeSet::new();
+        names
+            .iter()
+            .map(|name| {
+                if !seen.insert(name) {
+                    return Err(Error::illegal_argument(format!(
+                        "Implementation '{name}' is selected twice"
+                    )));
+                }
+                let entry = entries.remove(name).ok_or_else(|| {
+                    Error::illegal_argument(format!(
+                        "Unknown implementation '{name}', see {}",
+                        path.display()
+                    ))
+                })?;
+                Self::resolve(name, entry, util)
+            })
+            .collect()
+    }
+
+    fn resolve(name: &str, entry: ImplementationEntry, util: &str) -> Result<Self, Error> {
+        let binary = entry.binary.replace(UTIL_PLACEHOLDER, util);
+        let coverage_shmem_size = match entry.coverage {
+            CoverageMode::Guard => get_coverage_shmem_size(binary.clone())?.0,
+            CoverageMode::None | CoverageMode::Novelty => {
+                if !Path::new(&binary).exists() {
+                    return Err(Error::illegal_argument(format!("Util {binary} not found")));
+                }
+                match entry.coverage {
+                    CoverageMode::Novelty => NOVELTY_MAP_SIZE,
+                    // the binary does not write to the map, but observers need one
+                    _ => 1,
+                }
+            }
+        };
+        let args = entry
+            .args
+            .iter()
+            .map(|arg| arg.replace(UTIL_PLACEHOLDER, util))
+            .collect::<Vec<_>>();
+        let version = entry.version.or_else(|| detect_version(&binary, &args));
+        Ok(Self {
+            name: name.to_string(),
+            binary,
+            args,
+            preload: entry.preload,
+            coverage: entry.coverage,
+            env: SANITIZER_ENV
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .chain(entry.env)
+                .collect(),
+            version,
+            fork_server: entry.fork_server,
+            gcov: entry.gcov.map(|gcov| gcov.replace(UTIL_PLACEHOLDER, util)),
+            coverage_shmem_size,
+        })
+    }
+
+    /// The `gcov` build as an implementation of its own, run the same way but without writing to a coverage map
+    #[cfg(feature = "gcov")]
+    pub fn gcov_build(&self) -> Option<Self> {
+        Some(Self {
+            name: format!("{}_gcov", self.name),
+            binary: self.gcov.clone()?,
+            coverage: CoverageMode::None,
+            fork_server: false,
+            gcov: None,
+            ..self.clone()
+        })
+    }
+}
+
+/// The first line `binary` prints for `--version`, like `base64 (GNU coreutils) 9.5`, if it succeeds
+fn detect_version(binary: &str, args: &[String]) -> Option<String> {
+    let output = Command::new(binary)
+        .args(args)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+}
@@ -0,0 +1,556 @@
+use std::{borrow::Cow, collections::BTreeSet, marker::PhantomData};
+
+use libafl::{
+    corpus::Testcase,
+    events::{Event, EventFirer},
+    executors::{Executor, ExitKind, HasObservers},
+    feedbacks::Feedback,
+    inputs::UsesInput,
+    monitors::{AggregatorOps, UserStats, UserStatsValue},
+    observers::{Observer, ObserversTuple, StdErrObserver, StdOutObserver, UsesObservers},
+    state::{State, UsesState},
+    Error, HasMetadata, SerdeAny,
+};
+use libafl_bolts::{
+    tuples::{Handle, Handled, MatchNameRef, RefIndexable},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    divergence::{observed, Aspect, Classifier, Comparison, DivergenceSignature, Execution},
+    equivalence::SpellingMismatchMetadata,
+    exit_status::ExitStatusObserver,
+    namespace::{TreeSnapshot, TreeSnapshotObserver},
+};
+
+/// What an implementation did in the last execution of an [`NWayExecutor`]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Outcome {
+    pub exit_kind: ExitKind,
+    pub execution: Execution,
+    pub tree: Option<TreeSnapshot>,
+}
+
+/// Holds the [`Outcome`] of every implementation run by an [`NWayExecutor`], in the order of its executors.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NWayObserver {
+    name: Cow<'static, str>,
+    pub names: Vec<String>,
+    pub outcomes: Vec<Outcome>,
+}
+
+impl NWayObserver {
+    /// `names` are the names of the implementations, in the order of the executors
+    pub fn new(name: &'static str, names: Vec<String>) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
+            names,
+            outcomes: Vec::new(),
+        }
+    }
+}
+
+impl<S> Observer<S> for NWayObserver
+where
+    S: UsesInput,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.outcomes.clear();
+        Ok(())
+    }
+}
+
+impl Named for NWayObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+/// Runs the same input on any number of implementations, like [`DiffExecutor`](libafl::executors::DiffExecutor)
+/// does for two, and records what each of them did in an [`NWayObserver`].
+///
+/// All executors are of the same type, and their observers are found by the same handles. The executor's own
+/// observers, e.g. a map combining the coverage of all implementations, are shared by all executions. The exit kind
+/// is the common one if all implementations agree, otherwise a [`ExitKind::Diff`] of the first and the first
+/// differing one.
+pub struct NWayExecutor<E, OT> {
+    executors: Vec<E>,
+    observers: OT,
+    outcome_observer: Handle<NWayObserver>,
+    stdout_observer: Handle<StdOutObserver>,
+    stderr_observer: Handle<StdErrObserver>,
+    exit_status_observer: Handle<ExitStatusObserver>,
+    tree_observer: Handle<TreeSnapshotObserver>,
+}
+
+impl<E, OT> NWayExecutor<E, OT> {
+    /// The handles point into the observers of each executor, except for `outcome_observer`, which is one of
+    /// `observers`.
+    pub fn new(
+        executors: Vec<E>,
+        observers: OT,
+        outcome_observer: Handle<NWayObserver>,
+        stdout_observer: Handle<StdOutObserver>,
+        stderr_observer: Handle<StdErrObserver>,
+        exit_status_observer: Handle<ExitStatusObserver>,
+        tree_observer: Handle<TreeSnapshotObserver>,
+    ) -> Self {
+        Self {
+            executors,
+            observers,
+            outcome_observer,
+            stdout_observer,
+            stderr_observer,
+            exit_status_observer,
+            tree_observer,
+        }
+    }
+}
+
+impl<E, OT, EM, Z> Executor<EM, Z> for NWayExecutor<E, OT>
+where
+    E: Executor<EM, Z> + HasObservers,
+    OT: ObserversTuple<E::State>,
+    EM: UsesState<State = E::State>,
+    Z: UsesState<State = E::State>,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut Self::State,
+        mgr: &mut EM,
+        input: &<Self::State as UsesInput>::Input,
+    ) -> Result<ExitKind, Error> {
+        let mut outcomes = Vec::with_capacity(self.executors.len());
+        for executor in &mut self.executors {
+            executor.observers_mut().pre_exec_all(state, input)?;
+            let exit_kind = executor.run_target(fuzzer, state, mgr, input)?;
+            executor
+                .observers_mut()
+                .post_exec_all(state, input, &exit_kind)?;
+
+            let observers = executor.observers();
+            outcomes.push(Outcome {
+                exit_kind,
+                execution: Execution {
+                    stdout: observed("NWayExecutor", &self.stdout_observer, &*observers)?
+                        .stdout
+                        .clone()
+                        .unwrap_or_default(),
+                    stderr: observed("NWayExecutor", &self.stderr_observer, &*observers)?
+                        .stderr
+                        .clone()
+                        .unwrap_or_default(),
+                    exit_status: observed("NWayExecutor", &self.exit_status_observer, &*observers)?
+                        .status,
+                },
+                tree: observed("NWayExecutor", &self.tree_observer, &*observers)?
+                    .snapshot
+                    .clone(),
+            });
+        }
+
+        let first = outcomes.first().map_or(ExitKind::Ok, |o| o.exit_kind);
+        let exit_kind = match outcomes.iter().find(|o| o.exit_kind != first) {
+            Some(differing) => ExitKind::Diff {
+                primary: first.into(),
+                secondary: differing.exit_kind.into(),
+            },
+            None => first,
+        };
+        self.observers
+            .get_mut(&self.outcome_observer)
+            .ok_or_else(|| {
+                Error::illegal_argument(format!(
+                    "NWayExecutor: observer {} not found",
+                    self.outcome_observer.name()
+                ))
+            })?
+            .outcomes = outcomes;
+        Ok(exit_kind)
+    }
+}
+
+impl<E, OT> UsesState for NWayExecutor<E, OT>
+where
+    E: UsesState,
+{
+    type State = E::State;
+}
+
+impl<E, OT> UsesObservers for NWayExecutor<E, OT>
+where
+    E: UsesState,
+    OT: ObserversTuple<E::State>,
+{
+    type Observers = OT;
+}
+
+impl<E, OT> HasObservers for NWayExecutor<E, OT>
+where
+    E: UsesState,
+    OT: ObserversTuple<E::State>,
+{
+    fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
+        RefIndexable::from(&self.observers)
+    }
+
+    fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers> {
+        RefIndexable::from(&mut self.observers)
+    }
+}
+
+/// An implementation disagreeing with the majority
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Outlier {
+    pub name: String,
+    /// how the outlier differs from the first implementation of the majority, which comes first in the signature
+    pub signature: DivergenceSignature,
+}
+
+/// The majority vote on an objective of a [`MajorityFeedback`]
+#[derive(Serialize, Deserialize, Clone, Debug, SerdeAny)]
+pub struct MajorityMetadata {
+    /// the implementations grouped by agreeing behavior, largest group first
+    pub groups: Vec<Vec<String>>,
+    /// the implementations in the group of more than half of them, empty if there is no such group
+    pub majority: Vec<String>,
+    /// the implementations the divergence is attributed to, all outside the majority if there is one
+    pub outliers: Vec<Outlier>,
+}
+
+/// Interesting if the implementations run by an [`NWayExecutor`] do not all behave the same. The implementations
+/// are grouped by their behavior, compared like by the differential objectives, and if a group holds more than half
+/// of them, its behavior is taken as the correct one and the divergence is attributed to all others, the outliers.
+///
+/// The vote is attached to the objective as [`MajorityMetadata`], and how often each implementation was an outlier is
+/// reported as user stats to the monitor. Executions in which an implementation timed out are not interesting.
+pub struct MajorityFeedback {
+    outcome_observer: Handle<NWayObserver>,
+    comparison: Comparison,
+    /// the vote on the last execution, if it diverged
+    vote: Option<MajorityMetadata>,
+    /// how often each implementation was an outlier, in the order of the executors
+    outlier_counts: Vec<u64>,
+}
+
+impl MajorityFeedback {
+    pub fn new(outcome_observer: &NWayObserver, comparison: Comparison) -> Self {
+        Self {
+            outcome_observer: outcome_observer.handle(),
+            comparison,
+            vote: None,
+            outlier_counts: vec![0; outcome_observer.names.len()],
+        }
+    }
+}
+
+/// How `outcome2` differs from `outcome1`, including their exit kinds
+fn outcome_signature(
+    comparison: &Comparison,
+    outcome1: &Outcome,
+    outcome2: &Outcome,
+) -> DivergenceSignature {
+    let mut signature = comparison.signature(
+        [&outcome1.execution, &outcome2.execution],
+        [outcome1.tree.as_ref(), outcome2.tree.as_ref()],
+    );
+    if outcome1.exit_kind != outcome2.exit_kind {
+        signature.differs.insert(Aspect::ExitKind);
+    }
+    signature
+}
+
+/// Groups the implementations by agreeing with the first of a group, since behaviors under a tolerant
+/// [`StdoutPolicy`](super::stdio::StdoutPolicy) do not need to agree transitively
+fn vote(comparison: &Comparison, names: &[String], outcomes: &[Outcome]) -> MajorityMetadata {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (i, outcome) in outcomes.iter().enumerate() {
+        match groups.iter_mut().find(|group| {
+            outcome_signature(comparison, &outcomes[group[0]], outcome)
+                .differs
+                .is_empty()
+        }) {
+            Some(group) => group.push(i),
+            None => groups.push(vec![i]),
+        }
+    }
+    // stable, so ties keep the order of the executors
+    groups.sort_by_key(|group| std::cmp::Reverse(group.len()));
+
+    let majority = groups
+        .first()
+        .filter(|group| group.len() * 2 > outcomes.len())
+        .cloned()
+        .unwrap_or_default();
+    let outliers = majority.first().map_or(Vec::new(), |&reference| {
+        (0..outcomes.len())
+            .filter(|i| !majority.contains(i))
+            .map(|i| Outlier {
+                name: names[i].clone(),
+                signature: outcome_signature(comparison, &outcomes[reference], &outcomes[i]),
+            })
+            .collect()
+    });
+
+    let named = |group: &[usize]| group.iter().map(|&i| names[i].clone()).collect();
+    MajorityMetadata {
+        groups: groups.iter().map(|group| named(group)).collect(),
+        majority: named(&majority),
+        outliers,
+    }
+}
+
+impl<S> Feedback<S> for MajorityFeedback
+where
+    S: State,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let observer = observed("MajorityFeedback", &self.outcome_observer, observers)?;
+        self.vote = None;
+        if observer
+            .outcomes
+            .iter()
+            .any(|o| o.exit_kind == ExitKind::Timeout)
+        {
+            return Ok(false);
+        }
+        let vote = vote(&self.comparison, &observer.names, &observer.outcomes);
+        if vote.groups.len() > 1 {
+            self.vote = Some(vote);
+        }
+        Ok(self.vote.is_some())
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        let Some(vote) = self.vote.take() else {
+            return Ok(());
+        };
+        let names = observers
+            .get(&self.outcome_observer)
+            .map(|o| o.names.clone())
+            .unwrap_or_default();
+        let outliers = vote
+            .outliers
+            .iter()
+            .map(|outlier| outlier.name.as_str())
+            .collect::<BTreeSet<_>>();
+        for (i, name) in names.iter().enumerate() {
+            if !outliers.contains(name.as_str()) {
+                continue;
+            }
+            self.outlier_counts[i] += 1;
+            manager.fire(
+                state,
+                Event::UpdateUserStats {
+                    name: Cow::Owned(format!("outlier {name}")),
+                    value: UserStats::new(
+                        UserStatsValue::Number(self.outlier_counts[i]),
+                        AggregatorOps::Sum,
+                    ),
+                    phantom: PhantomData,
+                },
+            )?;
+        }
+        testcase.metadata_map_mut().insert(vote);
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.vote = None;
+        Ok(())
+    }
+}
+
+impl Named for MajorityFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("MajorityFeedback")
+    }
+}
+
+/// Classifies the divergences of the implementations run by an [`NWayExecutor`] like a
+/// [`DivergenceClassifier`](super::divergence::DivergenceClassifier) does for two, so their objectives are
+/// suppressed, bucketed and minimised the same way. The signature is the one of the first outlier of the majority
+/// vote, see [`Outlier::signature`], together with the names of all outliers. Without a majority, it compares the
+/// first implementation with the first one disagreeing with it.
+#[derive(Clone)]
+pub struct MajorityClassifier {
+    outcome_observer: Handle<NWayObserver>,
+    comparison: Comparison,
+}
+
+impl MajorityClassifier {
+    pub fn new(outcome_observer: &NWayObserver, comparison: Comparison) -> Self {
+        Self {
+            outcome_observer: outcome_observer.handle(),
+            comparison,
+        }
+    }
+
+    /// The outcomes of the last execution, the vote on them and the indices of the implementations compared
+    fn classify<'a, OT: MatchNameRef>(
+        &self,
+        observers: &'a OT,
+    ) -> Result<(&'a NWayObserver, MajorityMetadata, [usize; 2]), Error> {
+        let observer = observed("MajorityClassifier", &self.outcome_observer, observers)?;
+        let outcomes = &observer.outcomes;
+        if outcomes.len() < 2 {
+            return Err(Error::illegal_state(
+                "MajorityClassifier: fewer than two outcomes observed",
+            ));
+        }
+        let vote = vote(&self.comparison, &observer.names, outcomes);
+        let index = |name: &str| {
+            observer
+                .names
+                .iter()
+                .position(|n| n == name)
+                .expect("the vote names the implementations of the observer")
+        };
+        let compared = match (vote.majority.first(), vote.outliers.first()) {
+            (Some(reference), Some(outlier)) => [index(reference), index(&outlier.name)],
+            _ => [
+                0,
+                (1..outcomes.len())
+                    .find(|&i| {
+                        !outcome_signature(&self.comparison, &outcomes[0], &outcomes[i])
+                            .differs
+                            .is_empty()
+                    })
+                    .unwrap_or(1),
+            ],
+        };
+        Ok((observer, vote, compared))
+    }
+}
+
+impl Classifier for MajorityClassifier {
+    fn signature<S, OT>(
+        &self,
+        state: &S,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<DivergenceSignature, Error>
+    where
+        S: State + HasMetadata,
+        OT: ObserversTuple<S>,
+    {
+        let (observer, vote, compared) = self.classify(observers)?;
+        let [outcome1, outcome2] = compared.map(|i| &observer.outcomes[i]);
+        let mut signature = outcome_signature(&self.comparison, outcome1, outcome2);
+        signature.outliers = vote.outliers.into_iter().map(|o| o.name).collect();
+
+        if outcome1.exit_kind == ExitKind::Crash && outcome2.exit_kind == ExitKind::Crash {
+            signature.differs.insert(Aspect::Crash);
+        }
+        if state
+            .metadata::<SpellingMismatchMetadata>()
+            .is_ok_and(|m| !m.is_empty())
+        {
+            signature.differs.insert(Aspect::Spelling);
+        }
+        Ok(signature)
+    }
+
+    fn compared<OT: MatchNameRef>(&self, observers: &OT) -> Result<[(usize, Execution); 2], Error> {
+        let (observer, _, compared) = self.classify(observers)?;
+        Ok(compared.map(|i| (i, observer.outcomes[i].execution.clone())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, sync::Arc};
+
+    use super::*;
+    use crate::generic::{
+        exit_status::{ExitStatus, ExitStatusEquivalence},
+        stderr::StderrNormaliser,
+        stdio::StdoutPolicy,
+    };
+
+    fn comparison() -> Comparison {
+        let rules = Path::new(env!("CARGO_MANIFEST_DIR")).join("stderr_rules.json");
+        Comparison {
+            stdout_policy: StdoutPolicy::Strict,
+            exit_status_equivalence: ExitStatusEquivalence::Exact,
+            stderr_normaliser: Arc::new(StderrNormaliser::load(&rules, "base64").unwrap()),
+        }
+    }
+
+    fn outcome(exit_kind: ExitKind, stdout: &[u8], code: i32) -> Outcome {
+        Outcome {
+            exit_kind,
+            execution: Execution {
+                stdout: stdout.to_vec(),
+                stderr: Vec::new(),
+                exit_status: Some(ExitStatus::Code(code)),
+            },
+            tree: None,
+        }
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn attributes_divergences_to_outliers() {
+        let outcomes = [
+            outcome(ExitKind::Ok, b"x", 0),
+            outcome(ExitKind::Ok, b"y", 0),
+            outcome(ExitKind::Ok, b"x", 0),
+            outcome(ExitKind::Timeout, b"x", 0),
+            outcome(ExitKind::Ok, b"x", 0),
+        ];
+        let vote = vote(&comparison(), &names(&["a", "b", "c", "d", "e"]), &outcomes);
+        assert_eq!(
+            vote.groups,
+            [names(&["a", "c", "e"]), names(&["b"]), names(&["d"])]
+        );
+        assert_eq!(vote.majority, names(&["a", "c", "e"]));
+        let outliers = vote.outliers.iter().map(|o| &o.name).collect::<Vec<_>>();
+        assert_eq!(outliers, ["b", "d"]);
+        assert_eq!(vote.outliers[0].signature.differs, [Aspect::Stdout].into());
+        assert_eq!(
+            vote.outliers[1].signature.differs,
+            [Aspect::ExitKind].into()
+        );
+    }
+
+    #[test]
+    fn needs_more_than_half() {
+        let outcomes = [
+            outcome(ExitKind::Ok, b"x", 0),
+            outcome(ExitKind::Ok, b"x", 0),
+            outcome(ExitKind::Ok, b"x", 1),
+            outcome(ExitKind::Ok, b"y", 0),
+        ];
+        let vote = vote(&comparison(), &names(&["a", "b", "c", "d"]), &outcomes);
+        assert_eq!(vote.groups.len(), 3);
+        assert!(vote.majority.is_empty());
+        assert!(vote.outliers.is_empty());
+    }
+}
@@ -0,0 +1,303 @@
+use std::{borrow::Cow, ops::RangeBounds};
+
+use libafl::{
+    corpus::Corpus,
+    inputs::HasMutatorBytes,
+    mutators::{
+        BitFlipMutator, ByteAddMutator, ByteDecMutator, ByteFlipMutator, ByteIncMutator,
+        ByteInterestingMutator, ByteNegMutator, ByteRandMutator, BytesCopyMutator,
+        BytesDeleteMutator, BytesExpandMutator, BytesInsertCopyMutator, BytesInsertMutator,
+        BytesRandInsertMutator, BytesRandSetMutator, BytesSetMutator, BytesSwapMutator,
+        DwordAddMutator, DwordInterestingMutator, MutationResult, Mutator, QwordAddMutator,
+        WordAddMutator, WordInterestingMutator,
+    },
+    random_corpus_id,
+    state::{HasCorpus, HasMaxSize, HasRand},
+    Error,
+};
+use libafl_bolts::{prelude::Rand, tuple_list, tuples::tuple_list_type, HasLen, Named};
+
+/// Structured inputs with multiple byte buffers, e.g. `stdin`, a format string or the files of a fixture.
+///
+/// The number of fields may differ between inputs of the same type, e.g. if a field is optional.
+pub trait HasByteFields {
+    /// All byte fields of the input with their names, in a stable order.
+    fn byte_fields(&self) -> Vec<(Cow<'static, str>, &Vec<u8>)>;
+    fn byte_fields_mut(&mut self) -> Vec<(Cow<'static, str>, &mut Vec<u8>)>;
+
+    /// Relative likelihood of the field called `name` to be picked for mutation.
+    fn byte_field_weight(&self, _name: &str) -> usize {
+        1
+    }
+}
+
+/// Picks a field according to the weights of the input, or `None` if it has no fields.
+pub fn choose_byte_field<I: HasByteFields, R: Rand>(input: &I, rand: &mut R) -> Option<usize> {
+    let weights = input
+        .byte_fields()
+        .iter()
+        .map(|(name, _)| input.byte_field_weight(name))
+        .collect::<Vec<_>>();
+    let total = weights.iter().sum::<usize>();
+    if total == 0 {
+        return None;
+    }
+    let mut target = rand.below(total);
+    for (index, weight) in weights.into_iter().enumerate() {
+        if target < weight {
+            return Some(index);
+        }
+        target -= weight;
+    }
+    None
+}
+
+fn find_byte_field<I: HasByteFields>(input: &I, name: &str) -> Option<usize> {
+    input.byte_fields().iter().position(|(n, _)| n == name)
+}
+
+fn byte_field_mut<I: HasByteFields>(input: &mut I, index: usize) -> &mut Vec<u8> {
+    input.byte_fields_mut().swap_remove(index).1
+}
+
+/// A single field of a structured input, exposed to the byte-level mutators.
+#[derive(Debug)]
+pub struct MutVecInput<'a>(&'a mut Vec<u8>);
+
+impl<'a> From<&'a mut Vec<u8>> for MutVecInput<'a> {
+    fn from(bytes: &'a mut Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl HasLen for MutVecInput<'_> {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl HasMutatorBytes for MutVecInput<'_> {
+    fn bytes(&self) -> &[u8] {
+        self.0
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        self.0
+    }
+
+    fn resize(&mut self, new_len: usize, value: u8) {
+        self.0.resize(new_len, value)
+    }
+
+    fn extend<'b, I: IntoIterator<Item = &'b u8>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+
+    fn splice<R, I>(
+        &mut self,
+        range: R,
+        replace_with: I,
+    ) -> libafl::prelude::alloc::vec::Splice<'_, I::IntoIter>
+    where
+        R: RangeBounds<usize>,
+        I: IntoIterator<Item = u8>,
+    {
+        self.0.splice(range, replace_with)
+    }
+
+    fn drain<R>(&mut self, range: R) -> libafl::prelude::alloc::vec::Drain<'_, u8>
+    where
+        R: RangeBounds<usize>,
+    {
+        self.0.drain(range)
+    }
+}
+
+/// Applies a byte-level mutator to one field of a [`HasByteFields`] input, chosen according to the input's weights
+/// on every mutation.
+pub struct FieldMappingMutator<M> {
+    inner: M,
+    name: Cow<'static, str>,
+}
+
+impl<M: Named> FieldMappingMutator<M> {
+    pub fn new(inner: M) -> Self {
+        let name = Cow::Owned(format!("FieldMappingMutator<{}>", inner.name()));
+        Self { inner, name }
+    }
+}
+
+impl<I, S, M> Mutator<I, S> for FieldMappingMutator<M>
+where
+    I: HasByteFields,
+    S: HasRand,
+    M: for<'a> Mutator<MutVecInput<'a>, S>,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        match choose_byte_field(input, state.rand_mut()) {
+            Some(index) => self
+                .inner
+                .mutate(state, &mut byte_field_mut(input, index).into()),
+            None => Ok(MutationResult::Skipped),
+        }
+    }
+}
+
+impl<M> Named for FieldMappingMutator<M> {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+/// Picks a random input from the corpus and one of its fields, preferring the field with the same name as `index`.
+fn crossover_source<I, S>(state: &mut S, input: &I, index: usize) -> Result<Option<Vec<u8>>, Error>
+where
+    I: HasByteFields,
+    S: HasRand + HasCorpus,
+    S::Corpus: Corpus<Input = I>,
+{
+    if state.corpus().count() == 0 {
+        return Ok(None);
+    }
+    let id = random_corpus_id!(state.corpus(), state.rand_mut());
+    let other = state.corpus().cloned_input_for_id(id)?;
+    let other_index = match find_byte_field(&other, &input.byte_fields()[index].0) {
+        Some(other_index) => Some(other_index),
+        None => choose_byte_field(&other, state.rand_mut()),
+    };
+    Ok(other_index
+        .map(|i| other.byte_fields()[i].1.clone())
+        .filter(|bytes| !bytes.is_empty()))
+}
+
+/// Inserts a part of a field of another corpus entry into a field of the input.
+pub struct FieldCrossoverInsertMutator;
+impl<I, S> Mutator<I, S> for FieldCrossoverInsertMutator
+where
+    I: HasByteFields,
+    S: HasRand + HasCorpus + HasMaxSize,
+    S::Corpus: Corpus<Input = I>,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let Some(index) = choose_byte_field(input, state.rand_mut()) else {
+            return Ok(MutationResult::Skipped);
+        };
+        let Some(other) = crossover_source(state, input, index)? else {
+            return Ok(MutationResult::Skipped);
+        };
+        let max_size = state.max_size();
+        let rand = state.rand_mut();
+        let field = byte_field_mut(input, index);
+        if field.len() >= max_size {
+            return Ok(MutationResult::Skipped);
+        }
+        let from = rand.below(other.len());
+        let len = rand.between(1, (other.len() - from).min(max_size - field.len()));
+        let to = rand.below(field.len() + 1);
+        field.splice(to..to, other[from..from + len].iter().copied());
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for FieldCrossoverInsertMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("FieldCrossoverInsertMutator")
+    }
+}
+
+/// Overwrites a part of a field of the input with a part of a field of another corpus entry.
+pub struct FieldCrossoverReplaceMutator;
+impl<I, S> Mutator<I, S> for FieldCrossoverReplaceMutator
+where
+    I: HasByteFields,
+    S: HasRand + HasCorpus,
+    S::Corpus: Corpus<Input = I>,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let Some(index) = choose_byte_field(input, state.rand_mut()) else {
+            return Ok(MutationResult::Skipped);
+        };
+        if input.byte_fields()[index].1.is_empty() {
+            return Ok(MutationResult::Skipped);
+        }
+        let Some(other) = crossover_source(state, input, index)? else {
+            return Ok(MutationResult::Skipped);
+        };
+        let rand = state.rand_mut();
+        let field = byte_field_mut(input, index);
+        let from = rand.below(other.len());
+        let to = rand.below(field.len());
+        let len = rand.between(1, (other.len() - from).min(field.len() - to));
+        field[to..to + len].copy_from_slice(&other[from..from + len]);
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for FieldCrossoverReplaceMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("FieldCrossoverReplaceMutator")
+    }
+}
+
+pub type MappedHavocMutationsType = tuple_list_type!(
+    FieldMappingMutator<BitFlipMutator>,
+    FieldMappingMutator<ByteFlipMutator>,
+    FieldMappingMutator<ByteIncMutator>,
+    FieldMappingMutator<ByteDecMutator>,
+    FieldMappingMutator<ByteNegMutator>,
+    FieldMappingMutator<ByteRandMutator>,
+    FieldMappingMutator<ByteAddMutator>,
+    FieldMappingMutator<WordAddMutator>,
+    FieldMappingMutator<DwordAddMutator>,
+    FieldMappingMutator<QwordAddMutator>,
+    FieldMappingMutator<ByteInterestingMutator>,
+    FieldMappingMutator<WordInterestingMutator>,
+    FieldMappingMutator<DwordInterestingMutator>,
+    FieldMappingMutator<BytesDeleteMutator>,
+    FieldMappingMutator<BytesDeleteMutator>,
+    FieldMappingMutator<BytesDeleteMutator>,
+    FieldMappingMutator<BytesDeleteMutator>,
+    FieldMappingMutator<BytesExpandMutator>,
+    FieldMappingMutator<BytesInsertMutator>,
+    FieldMappingMutator<BytesRandInsertMutator>,
+    FieldMappingMutator<BytesSetMutator>,
+    FieldMappingMutator<BytesRandSetMutator>,
+    FieldMappingMutator<BytesCopyMutator>,
+    FieldMappingMutator<BytesInsertCopyMutator>,
+    FieldMappingMutator<BytesSwapMutator>,
+    FieldCrossoverInsertMutator,
+    FieldCrossoverReplaceMutator
+);
+
+/// LibAFL's havoc mutations, applied to the fields of a [`HasByteFields`] input.
+pub fn mapped_havoc_mutations() -> MappedHavocMutationsType {
+    tuple_list!(
+        FieldMappingMutator::new(BitFlipMutator::new()),
+        FieldMappingMutator::new(ByteFlipMutator::new()),
+        FieldMappingMutator::new(ByteIncMutator::new()),
+        FieldMappingMutator::new(ByteDecMutator::new()),
+        FieldMappingMutator::new(ByteNegMutator::new()),
+        FieldMappingMutator::new(ByteRandMutator::new()),
+        FieldMappingMutator::new(ByteAddMutator::new()),
+        FieldMappingMutator::new(WordAddMutator::new()),
+        FieldMappingMutator::new(DwordAddMutator::new()),
+        FieldMappingMutator::new(QwordAddMutator::new()),
+        FieldMappingMutator::new(ByteInterestingMutator::new()),
+        FieldMappingMutator::new(WordInterestingMutator::new()),
+        FieldMappingMutator::new(DwordInterestingMutator::new()),
+        FieldMappingMutator::new(BytesDeleteMutator::new()),
+        FieldMappingMutator::new(BytesDeleteMutator::new()),
+        FieldMappingMutator::new(BytesDeleteMutator::new()),
+        FieldMappingMutator::new(BytesDeleteMutator::new()),
+        FieldMappingMutator::new(BytesExpandMutator::new()),
+        FieldMappingMutator::new(BytesInsertMutator::new()),
+        FieldMappingMutator::new(BytesRandInsertMutator::new()),
+        FieldMappingMutator::new(BytesSetMutator::new()),
+        FieldMappingMutator::new(BytesRandSetMutator::new()),
+        FieldMappingMutator::new(BytesCopyMutator::new()),
+        FieldMappingMutator::new(BytesInsertCopyMutator::new()),
+        FieldMappingMutator::new(BytesSwapMutator::new()),
+        FieldCrossoverInsertMutator,
+        FieldCrossoverReplaceMutator
+    )
+}
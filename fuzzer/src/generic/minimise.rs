@@ -0,0 +1,349 @@
+use std::{fmt::Display, fs, marker::PhantomData, path::PathBuf};
+
+use libafl::{
+    executors::HasObservers,
+    fuzzer::ExecutesInput,
+    inputs::Input,
+    stages::Stage,
+    state::{State, UsesState},
+    Error, HasMetadata, SerdeAny,
+};
+use libafl_bolts::HasLen;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    bucket::REPRESENTATIVE_FILE, divergence::Classifier, executor::ExtractsToCommand,
+    reproducer::ReproducerExporter,
+};
+
+/// The file in a bucket holding its minimised representative, next to the representative itself
+pub static MINIMISED_FILE: &str = "representative.min";
+
+/// Interface for inputs the [`MinimisationStage`] can shrink without breaking their structure.
+pub trait Shrinkable: Clone {
+    /// Copies of the input with a single part dropped each, like a flag, an option with its value, an operand or a
+    /// token, in a stable order.
+    fn without_parts(&self) -> Vec<Self> {
+        Vec::new()
+    }
+
+    /// The byte buffers that may be shortened and canonicalised, like `stdin`, option values or file contents, in a
+    /// stable order.
+    fn shrinkable_fields_mut(&mut self) -> Vec<&mut Vec<u8>> {
+        Vec::new()
+    }
+}
+
+/// Copies of `items` with a single item removed each
+pub fn without_each<T: Clone>(items: &[T]) -> impl Iterator<Item = Vec<T>> + '_ {
+    (0..items.len()).map(|index| {
+        let mut items = items.to_vec();
+        items.remove(index);
+        items
+    })
+}
+
+/// Copies of `$input` with each of the given `bool` flags cleared and `Option` fields unset, if they are set
+macro_rules! without_set_fields {
+    ($input:expr, flags: [$($flag:ident),*], options: [$($option:ident),*]) => {{
+        let mut candidates = Vec::new();
+        $(
+            if $input.$flag {
+                let mut candidate = $input.clone();
+                candidate.$flag = false;
+                candidates.push(candidate);
+            }
+        )*
+        $(
+            if $input.$option.is_some() {
+                let mut candidate = $input.clone();
+                candidate.$option = None;
+                candidates.push(candidate);
+            }
+        )*
+        candidates
+    }};
+}
+pub(crate) use without_set_fields;
+
+fn field_mut<I: Shrinkable>(input: &mut I, index: usize) -> &mut Vec<u8> {
+    input.shrinkable_fields_mut().swap_remove(index)
+}
+
+/// Offsets of the starts of the lines in `bytes`, followed by its length
+fn line_bounds(bytes: &[u8]) -> Vec<usize> {
+    let mut bounds = vec![0];
+    bounds.extend(
+        bytes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| **b == b'\n')
+            .map(|(i, _)| i + 1),
+    );
+    if bounds.last() != Some(&bytes.len()) {
+        bounds.push(bytes.len());
+    }
+    bounds
+}
+
+/// Offsets of all bytes in `bytes`, followed by its length
+fn byte_bounds(bytes: &[u8]) -> Vec<usize> {
+    (0..=bytes.len()).collect()
+}
+
+/// Removes chunks of the units delimited by `bounds` from the field at `index`, starting with all of them and halving
+/// the chunk size down to a single unit, as long as `test` accepts the result.
+fn remove_chunks<I, T>(
+    current: &mut I,
+    index: usize,
+    bounds: fn(&[u8]) -> Vec<usize>,
+    test: &mut T,
+) -> Result<(), Error>
+where
+    I: Shrinkable,
+    T: FnMut(&I) -> Result<bool, Error>,
+{
+    let mut chunk = bounds(field_mut(current, index)).len() - 1;
+    while chunk > 0 {
+        let mut start = 0;
+        loop {
+            let unit_bounds = bounds(field_mut(current, index));
+            let units = unit_bounds.len() - 1;
+            if start >= units {
+                break;
+            }
+            let mut candidate = current.clone();
+            field_mut(&mut candidate, index)
+                .drain(unit_bounds[start]..unit_bounds[(start + chunk).min(units)]);
+            if test(&candidate)? {
+                *current = candidate;
+            } else {
+                start += chunk;
+            }
+        }
+        chunk /= 2;
+    }
+    Ok(())
+}
+
+fn is_printable(byte: u8) -> bool {
+    byte.is_ascii_graphic() || matches!(byte, b' ' | b'\n' | b'\t')
+}
+
+/// Replaces the non-printable bytes of the field at `index` by a letter, all at once if `test` accepts it and one by
+/// one otherwise.
+fn canonicalise<I, T>(current: &mut I, index: usize, test: &mut T) -> Result<(), Error>
+where
+    I: Shrinkable,
+    T: FnMut(&I) -> Result<bool, Error>,
+{
+    let field = field_mut(current, index);
+    if field.iter().all(|b| is_printable(*b)) {
+        return Ok(());
+    }
+    let mut candidate = current.clone();
+    for byte in field_mut(&mut candidate, index) {
+        if !is_printable(*byte) {
+            *byte = b'a';
+        }
+    }
+    if test(&candidate)? {
+        *current = candidate;
+        return Ok(());
+    }
+    for offset in 0..field_mut(current, index).len() {
+        if is_printable(field_mut(current, index)[offset]) {
+            continue;
+        }
+        let mut candidate = current.clone();
+        field_mut(&mut candidate, index)[offset] = b'a';
+        if test(&candidate)? {
+            *current = candidate;
+        }
+    }
+    Ok(())
+}
+
+/// Shrinks `input` as long as `test` accepts the result: drops parts one at a time, then removes lines and bytes of
+/// each field by delta debugging and finally replaces its non-printable bytes.
+pub fn minimise<I, T>(input: &I, test: &mut T) -> Result<I, Error>
+where
+    I: Shrinkable,
+    T: FnMut(&I) -> Result<bool, Error>,
+{
+    let mut current = input.clone();
+    'parts: loop {
+        for candidate in current.without_parts() {
+            if test(&candidate)? {
+                current = candidate;
+                continue 'parts;
+            }
+        }
+        break;
+    }
+    for index in 0..current.shrinkable_fields_mut().len() {
+        remove_chunks(&mut current, index, line_bounds, test)?;
+        remove_chunks(&mut current, index, byte_bounds, test)?;
+        canonicalise(&mut current, index, test)?;
+    }
+    Ok(current)
+}
+
+/// The buckets whose representative changed since the last [`MinimisationStage`]
+#[derive(Debug, Default, Clone, SerdeAny, Serialize, Deserialize)]
+pub struct PendingMinimisationMetadata {
+    pub buckets: Vec<String>,
+}
+
+/// Minimises the new representatives stored by [`BucketFeedback`](super::bucket::BucketFeedback) while their
+/// divergence signature stays the same, and stores the result as [`MINIMISED_FILE`] in the bucket. The reproducers of
+/// the bucket are replaced by those of the minimised input.
+///
+/// Representatives whose signature does not reproduce are skipped.
+pub struct MinimisationStage<C, S> {
+    dir: PathBuf,
+    classifier: C,
+    exporter: ReproducerExporter,
+    /// the maximal number of executions per representative
+    max_executions: usize,
+    phantom: PhantomData<S>,
+}
+
+impl<C, S> MinimisationStage<C, S> {
+    pub fn new(
+        dir: PathBuf,
+        classifier: C,
+        exporter: ReproducerExporter,
+        max_executions: usize,
+    ) -> Self {
+        Self {
+            dir,
+            classifier,
+            exporter,
+            max_executions,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<C, S> UsesState for MinimisationStage<C, S>
+where
+    S: State,
+{
+    type State = S;
+}
+
+impl<C, E, EM, Z, S> Stage<E, EM, Z> for MinimisationStage<C, S>
+where
+    C: Classifier,
+    S: State + HasMetadata,
+    S::Input: Shrinkable + HasLen + Display + ExtractsToCommand,
+    E: HasObservers + UsesState<State = S>,
+    EM: UsesState<State = S>,
+    Z: ExecutesInput<E, EM> + UsesState<State = S>,
+{
+    fn should_restart(&mut self, _state: &mut S) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn clear_progress(&mut self, _state: &mut S) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+    ) -> Result<(), Error> {
+        let buckets = match state
+            .metadata_map_mut()
+            .get_mut::<PendingMinimisationMetadata>()
+        {
+            Some(pending) => std::mem::take(&mut pending.buckets),
+            None => return Ok(()),
+        };
+        for name in buckets {
+            let bucket = self.dir.join(&name);
+            let Ok(representative) = S::Input::from_file(bucket.join(REPRESENTATIVE_FILE)) else {
+                continue;
+            };
+
+            let mut executions = 0;
+            let mut test = |candidate: &S::Input| {
+                if executions >= self.max_executions {
+                    return Ok(false);
+                }
+                executions += 1;
+                let exit_kind = fuzzer.execute_input(state, executor, manager, candidate)?;
+                let signature =
+                    self.classifier
+                        .signature(state, &*executor.observers(), &exit_kind)?;
+                Ok(signature.bucket_name() == name)
+            };
+            if !test(&representative)? {
+                continue;
+            }
+            let minimised = minimise(&representative, &mut test)?;
+
+            let stored = S::Input::from_file(bucket.join(MINIMISED_FILE)).ok();
+            if stored.is_some_and(|stored| stored.len() <= minimised.len()) {
+                continue;
+            }
+            minimised.to_file(bucket.join(MINIMISED_FILE))?;
+            fs::write(
+                bucket.join(format!("{MINIMISED_FILE}.txt")),
+                format!("{minimised}\n"),
+            )
+            .map_err(|e| Error::os_error(e, format!("Could not write bucket {name}")))?;
+
+            // the observers hold the last candidate tried, which may not be the minimised input
+            fuzzer.execute_input(state, executor, manager, &minimised)?;
+            let executions = self.classifier.compared(&*executor.observers())?;
+            self.exporter
+                .export(&self.dir, &name, &minimised, &executions)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Bytes(Vec<u8>);
+
+    impl Shrinkable for Bytes {
+        fn shrinkable_fields_mut(&mut self) -> Vec<&mut Vec<u8>> {
+            vec![&mut self.0]
+        }
+    }
+
+    #[test]
+    fn removes_single_unit_field() {
+        let minimised = minimise(&Bytes(b"x".to_vec()), &mut |_| Ok(true)).unwrap();
+        assert_eq!(minimised, Bytes(Vec::new()));
+    }
+
+    #[test]
+    fn removes_whole_field() {
+        let minimised = minimise(&Bytes(b"ab\ncd\n".to_vec()), &mut |_| Ok(true)).unwrap();
+        assert_eq!(minimised, Bytes(Vec::new()));
+    }
+
+    #[test]
+    fn keeps_required_bytes() {
+        let input = Bytes(b"ab\ncd\nef".to_vec());
+        let minimised = minimise(&input, &mut |c: &Bytes| Ok(c.0.contains(&b'd'))).unwrap();
+        assert_eq!(minimised, Bytes(b"d".to_vec()));
+    }
+
+    #[test]
+    fn canonicalises_non_printable_bytes() {
+        let input = Bytes(vec![0, b'x', 0xff]);
+        let minimised = minimise(&input, &mut |c: &Bytes| Ok(c.0.len() == 3)).unwrap();
+        assert_eq!(minimised, Bytes(b"axa".to_vec()));
+    }
+}
@@ -1,8 +1,30 @@
+pub mod bucket;
 #[cfg(feature = "gcov")]
 pub mod cov_feedback;
+pub mod divergence;
+pub mod equivalence;
 pub mod executor;
+pub mod exit_status;
+pub mod fixture;
+pub mod forkserver;
+pub mod implementation;
+pub mod majority;
+pub mod mapping;
+pub mod minimise;
+pub mod namespace;
 #[cfg(feature = "log_new_corpus_entries")]
 pub mod new_corpus_entry_log_feedback;
+pub mod novelty;
+pub mod numeric;
+pub mod options;
+pub mod panic;
+pub mod recheck;
+pub mod replay;
+pub mod reproducer;
+pub mod sanitizer;
 pub mod shmem;
+pub mod spelling;
+pub mod stderr;
 pub mod stdio;
+pub mod suppression;
 pub mod timeout;
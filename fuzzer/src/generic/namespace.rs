@@ -0,0 +1,628 @@
+use std::{
+    borrow::Cow,
+    collections::{hash_map::DefaultHasher, BTreeMap},
+    env,
+    ffi::{CString, OsString},
+    fmt::Write as _,
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    os::unix::{
+        ffi::OsStrExt,
+        fs::{FileTypeExt, MetadataExt},
+        process::{CommandExt, ExitStatusExt},
+    },
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use libafl::{
+    events::EventFirer,
+    executors::{command::CommandConfigurator, ExitKind},
+    feedbacks::Feedback,
+    inputs::UsesInput,
+    observers::{Observer, ObserversTuple, StdErrObserver, StdOutObserver},
+    state::State,
+    Error, HasMetadata, SerdeAny,
+};
+use libafl_bolts::{
+    shmem::ShMemDescription,
+    tuples::{Handle, Handled, MatchNameRef},
+    Named,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    executor::{absolute_path, pseudo_pipe, CoverageConfigurator, ExtractsToCommand},
+    fixture::{fixture_root, Fixture},
+    implementation::{CoverageMode, Implementation, NO_COVERAGE_ENV},
+    stdio::vec_string_mapper,
+};
+
+/// If set, the fuzzer binary acts as the [`run_namespace_runner`] trampoline instead of fuzzing.
+/// The value is the path to the serialized [`NamespaceRunnerConfig`].
+pub static NAMESPACE_RUNNER_ENV: &str = "COREUTILS_DIFFERENTIAL_NAMESPACE_RUNNER";
+
+/// Files larger than this are only recorded by their hash in a [`TreeSnapshot`].
+const SNAPSHOT_CONTENT_LIMIT: usize = 1024;
+
+/// Runs every child in a fresh user and mount namespace, with a tmpfs mounted over the fixture root.
+///
+/// The fuzzer binary itself is used as a trampoline (see [`run_namespace_runner`]): it sets up the namespaces, builds the
+/// fixture, runs the binary under test, and writes a [`TreeSnapshot`] of the resulting tree for the
+/// [`TreeSnapshotObserver`] before exiting with the same status as the binary under test.
+#[derive(Debug)]
+pub struct NamespacedCommandExecutor<I: ExtractsToCommand> {
+    shmem_coverage_description: String,
+    temp_file_stdin_path: String,
+    runner_config_path: PathBuf,
+    runner_config: NamespaceRunnerConfig,
+    coverage: CoverageMode,
+    stdout_observer: Option<Handle<StdOutObserver>>,
+    stderr_observer: Option<Handle<StdErrObserver>>,
+    phantom: PhantomData<I>,
+}
+
+impl<I: ExtractsToCommand> CoverageConfigurator<I> for NamespacedCommandExecutor<I> {
+    // children forked by a fork server would share its namespaces and fixture
+    const SUPPORTS_FORK_SERVER: bool = false;
+
+    fn configure(
+        shmem_coverage_description: &ShMemDescription,
+        stdout_observer: Option<Handle<StdOutObserver>>,
+        stderr_observer: Option<Handle<StdErrObserver>>,
+        implementation: &Implementation,
+        id: &str,
+    ) -> Self {
+        let serialized_description = serde_json::to_string(&shmem_coverage_description)
+            .expect("Could not stringify shared memory description");
+
+        Self {
+            shmem_coverage_description: serialized_description,
+            temp_file_stdin_path: format!("/dev/shm/temp{id}"),
+            runner_config_path: PathBuf::from(format!("/dev/shm/namespace{id}.json")),
+            runner_config: NamespaceRunnerConfig {
+                program: absolute_path(&implementation.binary),
+                args: implementation.args.clone(),
+                preload: absolute_path(&implementation.preload),
+                env: implementation.env.clone(),
+                root: fixture_root(id),
+                snapshot_path: snapshot_path(id),
+                fixture: Fixture::default(),
+            },
+            coverage: implementation.coverage,
+            stdout_observer,
+            stderr_observer,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<I> CommandConfigurator<I> for NamespacedCommandExecutor<I>
+where
+    I: ExtractsToCommand,
+{
+    fn spawn_child(&mut self, input: &I) -> Result<Child, Error> {
+        self.runner_config.fixture = input.get_fixture().cloned().unwrap_or_default();
+        serde_json::to_writer(
+            File::create(&self.runner_config_path)
+                .map_err(|e| Error::os_error(e, "Could not create namespace runner config"))?,
+            &self.runner_config,
+        )
+        .map_err(|e| Error::serialize(format!("Could not write namespace runner config: {e}")))?;
+
+        let runner = env::current_exe()
+            .map_err(|e| Error::os_error(e, "Could not find the fuzzer binary"))?;
+        let mut command = Command::new(runner);
+        command
+            .env(NAMESPACE_RUNNER_ENV, &self.runner_config_path)
+            .args(input.get_args());
+        match self.coverage {
+            CoverageMode::Guard => command.arg(&self.shmem_coverage_description),
+            CoverageMode::None | CoverageMode::Novelty => command.env(NO_COVERAGE_ENV, "1"),
+        };
+        let child = command
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stdin(pseudo_pipe(input.get_stdin(), &self.temp_file_stdin_path)?)
+            .spawn()
+            .expect("failed to start namespace runner");
+        Ok(child)
+    }
+
+    fn exec_timeout(&self) -> Duration {
+        Duration::from_secs(30)
+    }
+
+    fn stdout_observer(&self) -> Option<Handle<StdOutObserver>> {
+        self.stdout_observer.clone()
+    }
+
+    fn stderr_observer(&self) -> Option<Handle<StdErrObserver>> {
+        self.stderr_observer.clone()
+    }
+}
+
+/// Everything the trampoline needs to know, passed through a file since the fixture may be large.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamespaceRunnerConfig {
+    program: PathBuf,
+    /// passed before the arguments of the input
+    args: Vec<String>,
+    preload: PathBuf,
+    env: BTreeMap<String, String>,
+    root: PathBuf,
+    snapshot_path: PathBuf,
+    fixture: Fixture,
+}
+
+/// Where the [`TreeSnapshot`] of the execution with the given id is written to.
+pub fn snapshot_path(id: &str) -> PathBuf {
+    PathBuf::from(format!("/dev/shm/snapshot{id}.json"))
+}
+
+/// Where the namespace runner of the execution with the given id reports why it failed, next to its config
+fn runner_error_path(id: &str) -> PathBuf {
+    PathBuf::from(format!("/dev/shm/namespace{id}.error"))
+}
+
+/// Entry point of the trampoline, never returns. Arguments are passed on to the binary under test unchanged, after
+/// those of the implementation. Failures of the runner itself are not written to the `stderr` of the binary under
+/// test, but to the [`runner_error_path`] next to the config, where the [`TreeSnapshotObserver`] picks them up.
+pub fn run_namespace_runner(config_path: OsString) -> ! {
+    match namespace_runner(Path::new(&config_path)) {
+        Ok(status) => {
+            if let Some(signal) = status.signal() {
+                // die the same way as the binary under test
+                unsafe {
+                    libc::signal(signal, libc::SIG_DFL);
+                    libc::kill(libc::getpid(), signal);
+                }
+                std::process::exit(128 + signal)
+            }
+            std::process::exit(status.code().unwrap_or(1))
+        }
+        Err(e) => {
+            let _ = fs::write(
+                Path::new(&config_path).with_extension("error"),
+                format!("{e:?}"),
+            );
+            std::process::exit(125)
+        }
+    }
+}
+
+fn namespace_runner(config_path: &Path) -> Result<std::process::ExitStatus, Error> {
+    let config: NamespaceRunnerConfig = serde_json::from_reader(
+        File::open(config_path)
+            .map_err(|e| Error::os_error(e, "Could not open namespace runner config"))?,
+    )
+    .map_err(|e| Error::serialize(format!("Could not parse namespace runner config: {e}")))?;
+
+    fs::create_dir_all(&config.root)
+        .map_err(|e| Error::os_error(e, "Could not create fixture root"))?;
+    let _ = fs::remove_file(&config.snapshot_path);
+
+    enter_namespaces()?;
+    mount_tmpfs(&config.root)?;
+    config.fixture.build(&config.root)?;
+
+    let start = SystemTime::now();
+    let mut command = Command::new(&config.program);
+    command
+        .envs(&config.env)
+        .env("LD_PRELOAD", &config.preload)
+        .args(&config.args)
+        .args(env::args_os().skip(1))
+        .current_dir(&config.root);
+    unsafe {
+        // make sure the binary under test does not outlive the runner if it is killed on timeout
+        command.pre_exec(|| {
+            libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+            Ok(())
+        });
+    }
+    let status = command
+        .status()
+        .map_err(|e| Error::os_error(e, "Could not run binary under test"))?;
+    let end = SystemTime::now();
+
+    let snapshot = TreeSnapshot::capture(&config.root, start, end)?;
+    serde_json::to_writer(
+        File::create(&config.snapshot_path)
+            .map_err(|e| Error::os_error(e, "Could not create snapshot file"))?,
+        &snapshot,
+    )
+    .map_err(|e| Error::serialize(format!("Could not write snapshot: {e}")))?;
+
+    Ok(status)
+}
+
+/// Moves the current process into a new user and mount namespace, mapping the current user to itself.
+///
+/// The process keeps all capabilities in the new user namespace until it calls `exec`, which is enough to mount the
+/// tmpfs. The binary under test runs as the unprivileged user, so permission checks behave like outside.
+fn enter_namespaces() -> Result<(), Error> {
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    if unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNS) } != 0 {
+        return Err(Error::os_error(
+            std::io::Error::last_os_error(),
+            "Could not unshare user and mount namespace",
+        ));
+    }
+    fs::write("/proc/self/setgroups", "deny")
+        .map_err(|e| Error::os_error(e, "Could not write setgroups"))?;
+    fs::write("/proc/self/uid_map", format!("{uid} {uid} 1"))
+        .map_err(|e| Error::os_error(e, "Could not write uid_map"))?;
+    fs::write("/proc/self/gid_map", format!("{gid} {gid} 1"))
+        .map_err(|e| Error::os_error(e, "Could not write gid_map"))?;
+
+    // do not propagate any mounts back to the parent namespace
+    let root = CString::new("/").unwrap();
+    if unsafe {
+        libc::mount(
+            std::ptr::null(),
+            root.as_ptr(),
+            std::ptr::null(),
+            libc::MS_REC | libc::MS_PRIVATE,
+            std::ptr::null(),
+        )
+    } != 0
+    {
+        return Err(Error::os_error(
+            std::io::Error::last_os_error(),
+            "Could not make mounts private",
+        ));
+    }
+    Ok(())
+}
+
+fn mount_tmpfs(target: &Path) -> Result<(), Error> {
+    let source = CString::new("tmpfs").unwrap();
+    let target = CString::new(target.as_os_str().as_bytes())
+        .map_err(|_| Error::illegal_argument("Fixture root contains a NUL byte"))?;
+    let options = CString::new("mode=0755").unwrap();
+    if unsafe {
+        libc::mount(
+            source.as_ptr(),
+            target.as_ptr(),
+            source.as_ptr(),
+            libc::MS_NOSUID | libc::MS_NODEV,
+            options.as_ptr() as *const libc::c_void,
+        )
+    } != 0
+    {
+        return Err(Error::os_error(
+            std::io::Error::last_os_error(),
+            "Could not mount tmpfs over fixture root",
+        ));
+    }
+    Ok(())
+}
+
+/// The state of a directory tree after an execution.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+pub struct TreeSnapshot {
+    pub entries: Vec<SnapshotEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotEntry {
+    pub path: Vec<u8>,
+    pub kind: SnapshotEntryKind,
+    pub mode: u32,
+    pub mtime: SnapshotTime,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum SnapshotEntryKind {
+    File { size: u64, content: SnapshotContent },
+    Directory,
+    Symlink { target: Vec<u8> },
+    Fifo,
+    Other,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum SnapshotContent {
+    Bytes(Vec<u8>),
+    Hash(u64),
+}
+
+/// Timestamps set to the current time differ between executions, so they are only recorded if they are pinned,
+/// i.e. lie outside of the execution.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotTime {
+    Pinned(i64),
+    DuringExecution,
+}
+
+impl TreeSnapshot {
+    /// Walks the tree at `root` without following symlinks, in a deterministic order.
+    pub fn capture(root: &Path, start: SystemTime, end: SystemTime) -> Result<Self, Error> {
+        let to_secs = |t: SystemTime| {
+            t.duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs() as i64)
+        };
+        let (start, end) = (to_secs(start), to_secs(end) + 1);
+        let mut snapshot = Self::default();
+        snapshot.capture_dir(root, &mut Vec::new(), start, end)?;
+        Ok(snapshot)
+    }
+
+    fn capture_dir(
+        &mut self,
+        dir: &Path,
+        relative: &mut Vec<u8>,
+        start: i64,
+        end: i64,
+    ) -> Result<(), Error> {
+        let metadata = fs::symlink_metadata(dir)
+            .map_err(|e| Error::os_error(e, "Could not stat snapshot directory"))?;
+        self.entries.push(SnapshotEntry {
+            path: relative.clone(),
+            kind: SnapshotEntryKind::Directory,
+            mode: metadata.mode() & 0o7777,
+            mtime: snapshot_time(metadata.mtime(), start, end),
+        });
+
+        let mut children = fs::read_dir(dir)
+            .map_err(|e| Error::os_error(e, "Could not read snapshot directory"))?
+            .filter_map(Result::ok)
+            .map(|e| e.file_name())
+            .collect::<Vec<_>>();
+        children.sort();
+
+        for name in children {
+            let path = dir.join(&name);
+            let previous_len = relative.len();
+            if !relative.is_empty() {
+                relative.push(b'/');
+            }
+            relative.extend(name.as_bytes());
+
+            let metadata = fs::symlink_metadata(&path)
+                .map_err(|e| Error::os_error(e, "Could not stat snapshot entry"))?;
+            let file_type = metadata.file_type();
+            if file_type.is_dir() {
+                self.capture_dir(&path, relative, start, end)?;
+            } else {
+                let kind = if file_type.is_file() {
+                    let content = fs::read(&path).unwrap_or_default();
+                    let content = if content.len() <= SNAPSHOT_CONTENT_LIMIT {
+                        SnapshotContent::Bytes(content)
+                    } else {
+                        let mut hasher = DefaultHasher::new();
+                        content.hash(&mut hasher);
+                        SnapshotContent::Hash(hasher.finish())
+                    };
+                    SnapshotEntryKind::File {
+                        size: metadata.len(),
+                        content,
+                    }
+                } else if file_type.is_symlink() {
+                    SnapshotEntryKind::Symlink {
+                        target: fs::read_link(&path)
+                            .map(|t| t.as_os_str().as_bytes().to_vec())
+                            .unwrap_or_default(),
+                    }
+                } else if file_type.is_fifo() {
+                    SnapshotEntryKind::Fifo
+                } else {
+                    SnapshotEntryKind::Other
+                };
+                self.entries.push(SnapshotEntry {
+                    path: relative.clone(),
+                    kind,
+                    mode: metadata.mode() & 0o7777,
+                    mtime: snapshot_time(metadata.mtime(), start, end),
+                });
+            }
+            relative.truncate(previous_len);
+        }
+        Ok(())
+    }
+}
+
+fn snapshot_time(mtime: i64, start: i64, end: i64) -> SnapshotTime {
+    if (start..=end).contains(&mtime) {
+        SnapshotTime::DuringExecution
+    } else {
+        SnapshotTime::Pinned(mtime)
+    }
+}
+
+impl std::fmt::Display for SnapshotEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "'{}' ", vec_string_mapper(&Some(self.path.clone())))?;
+        match &self.kind {
+            SnapshotEntryKind::File { size, content } => match content {
+                SnapshotContent::Bytes(bytes) => write!(
+                    f,
+                    "file ({size} bytes, content '{}')",
+                    vec_string_mapper(&Some(bytes.clone()))
+                )?,
+                SnapshotContent::Hash(hash) => write!(f, "file ({size} bytes, hash {hash:016x})")?,
+            },
+            SnapshotEntryKind::Directory => write!(f, "dir")?,
+            SnapshotEntryKind::Symlink { target } => write!(
+                f,
+                "symlink -> '{}'",
+                vec_string_mapper(&Some(target.clone()))
+            )?,
+            SnapshotEntryKind::Fifo => write!(f, "fifo")?,
+            SnapshotEntryKind::Other => write!(f, "other")?,
+        }
+        write!(f, " mode {:o}", self.mode)?;
+        match self.mtime {
+            SnapshotTime::Pinned(mtime) => write!(f, " mtime {mtime}"),
+            SnapshotTime::DuringExecution => write!(f, " mtime during execution"),
+        }
+    }
+}
+
+impl TreeSnapshot {
+    /// Lists all entries that differ between the two snapshots, or `None` if they are equal.
+    pub fn diff(&self, other: &Self) -> Option<String> {
+        if self == other {
+            return None;
+        }
+        let mut diff = String::new();
+        for entry in &self.entries {
+            if !other.entries.contains(entry) {
+                writeln!(diff, "- {entry}").unwrap();
+            }
+        }
+        for entry in &other.entries {
+            if !self.entries.contains(entry) {
+                writeln!(diff, "+ {entry}").unwrap();
+            }
+        }
+        Some(diff)
+    }
+}
+
+/// Reads the [`TreeSnapshot`] written by the namespace runner after each execution. Fails the execution if the runner
+/// could not set up the namespaces, run the binary under test or capture the tree, so these are not mistaken for a
+/// divergence of the binary under test.
+///
+/// Does not observe anything when used with an executor that does not write snapshots.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TreeSnapshotObserver {
+    name: Cow<'static, str>,
+    snapshot_path: PathBuf,
+    runner_error_path: PathBuf,
+    pub snapshot: Option<TreeSnapshot>,
+}
+
+impl TreeSnapshotObserver {
+    /// `id` is the one the executor was configured with
+    pub fn new(name: &'static str, id: &str) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
+            snapshot_path: snapshot_path(id),
+            runner_error_path: runner_error_path(id),
+            snapshot: None,
+        }
+    }
+}
+
+impl<S> Observer<S> for TreeSnapshotObserver
+where
+    S: UsesInput,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.snapshot = None;
+        let _ = fs::remove_file(&self.snapshot_path);
+        let _ = fs::remove_file(&self.runner_error_path);
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &S::Input,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        if let Ok(error) = fs::read_to_string(&self.runner_error_path) {
+            return Err(Error::illegal_state(format!(
+                "Namespace runner failed: {error}"
+            )));
+        }
+        self.snapshot = File::open(&self.snapshot_path)
+            .ok()
+            .and_then(|f| serde_json::from_reader(f).ok());
+        Ok(())
+    }
+}
+
+impl Named for TreeSnapshotObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        &self.name
+    }
+}
+
+/// Compares the post-execution trees of two implementations and records the difference as metadata.
+pub struct TreeSnapshotDiffFeedback {
+    observer1: Handle<TreeSnapshotObserver>,
+    observer2: Handle<TreeSnapshotObserver>,
+    diff: Option<String>,
+}
+
+impl TreeSnapshotDiffFeedback {
+    pub fn new(observer1: &TreeSnapshotObserver, observer2: &TreeSnapshotObserver) -> Self {
+        Self {
+            observer1: observer1.handle(),
+            observer2: observer2.handle(),
+            diff: None,
+        }
+    }
+}
+
+impl<S> Feedback<S> for TreeSnapshotDiffFeedback
+where
+    S: State,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let get = |handle: &Handle<TreeSnapshotObserver>| {
+            observers.get(handle).ok_or_else(|| {
+                Error::illegal_argument(format!(
+                    "TreeSnapshotDiffFeedback: observer {} not found",
+                    handle.name()
+                ))
+            })
+        };
+        self.diff = match (
+            &get(&self.observer1)?.snapshot,
+            &get(&self.observer2)?.snapshot,
+        ) {
+            (Some(s1), Some(s2)) => s1.diff(s2),
+            (None, None) => None,
+            (Some(_), None) => Some("Only the first implementation produced a snapshot".into()),
+            (None, Some(_)) => Some("Only the second implementation produced a snapshot".into()),
+        };
+        Ok(self.diff.is_some())
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _observers: &OT,
+        testcase: &mut libafl::prelude::Testcase<<S>::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        if let Some(diff) = self.diff.take() {
+            testcase
+                .metadata_map_mut()
+                .insert(TreeSnapshotDiffMetadata { diff });
+        }
+        Ok(())
+    }
+}
+
+impl Named for TreeSnapshotDiffFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("TreeSnapshotDiffFeedback")
+    }
+}
+
+#[derive(Debug, SerdeAny, Serialize, Deserialize)]
+struct TreeSnapshotDiffMetadata {
+    diff: String,
+}
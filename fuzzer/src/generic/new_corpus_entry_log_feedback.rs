@@ -5,18 +5,18 @@ use libafl::{
     events::EventFirer,
     executors::ExitKind,
     feedbacks::Feedback,
-    inputs::{HasMutatorBytes, UsesInput},
+    inputs::UsesInput,
     observers::ObserversTuple,
     state::State,
     Error,
 };
-use libafl_bolts::Named;
+use libafl_bolts::{HasLen, Named};
 
 pub struct NewCorpusEntryLogFeedback;
 impl<S> Feedback<S> for NewCorpusEntryLogFeedback
 where
     S: State + UsesInput,
-    S::Input: HasMutatorBytes,
+    S::Input: HasLen,
 {
     fn is_interesting<EM, OT>(
         &mut self,
@@ -46,7 +46,7 @@ where
     {
         println!(
             "New corpus entry with len {}",
-            testcase.input().as_ref().unwrap().bytes().len()
+            testcase.input().as_ref().unwrap().len()
         );
         Ok(())
     }
@@ -0,0 +1,177 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use libafl::{
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::UsesInput,
+    observers::{MapObserver, StdErrObserver, StdOutObserver, UsesObservers},
+    state::UsesState,
+    Error,
+};
+use libafl_bolts::tuples::{Handle, MatchNameRef, RefIndexable};
+
+use super::{
+    exit_status::ExitStatusObserver,
+    implementation::{CoverageMode, Implementation},
+};
+
+/// The size of the map filled by [`OutputNoveltyExecutor`]
+pub const NOVELTY_MAP_SIZE: usize = 1 << 16;
+/// Only the beginning of an output is considered for its shape, long outputs mostly repeat their structure
+const SHAPE_PREFIX: usize = 1024;
+
+/// Fills the coverage map of an implementation with [`CoverageMode::Novelty`] from what it printed and how it exited,
+/// since the uninstrumented binary does not report any coverage itself. Each execution sets the entries of a few
+/// features: the exit status on its own, and combined with the shape of `stdout`, the shape of `stderr` and the
+/// length classes of both. A feedback on the map then keeps inputs producing outputs of a new kind, instead of inputs
+/// reaching new code.
+///
+/// For other implementations, the inner executor is run unchanged. If there is no exit status, e.g. on timeout, the
+/// exit kind is used instead.
+#[derive(Debug)]
+pub struct OutputNoveltyExecutor<E, M> {
+    inner: E,
+    enabled: bool,
+    map_observer: Handle<M>,
+    stdout_observer: Handle<StdOutObserver>,
+    stderr_observer: Handle<StdErrObserver>,
+    exit_status_observer: Handle<ExitStatusObserver>,
+}
+
+impl<E, M> OutputNoveltyExecutor<E, M> {
+    /// The observers are among those of `inner`.
+    pub fn new(
+        inner: E,
+        implementation: &Implementation,
+        map_observer: Handle<M>,
+        stdout_observer: Handle<StdOutObserver>,
+        stderr_observer: Handle<StdErrObserver>,
+        exit_status_observer: Handle<ExitStatusObserver>,
+    ) -> Self {
+        Self {
+            inner,
+            enabled: implementation.coverage == CoverageMode::Novelty,
+            map_observer,
+            stdout_observer,
+            stderr_observer,
+            exit_status_observer,
+        }
+    }
+}
+
+/// The shape of an output: runs of digits, letters, blanks and non-ASCII bytes collapse to a single representative
+/// each, while punctuation and line breaks are kept. The shape changes with the structure of an output rather than
+/// with the data it contains.
+fn shape(output: &[u8]) -> Vec<u8> {
+    let mut shape = Vec::new();
+    for &byte in output.iter().take(SHAPE_PREFIX) {
+        let (class, collapses) = match byte {
+            b'0'..=b'9' => (b'0', true),
+            b'a'..=b'z' | b'A'..=b'Z' => (b'a', true),
+            b' ' | b'\t' => (b' ', true),
+            0x80.. => (b'?', true),
+            _ => (byte, false),
+        };
+        if !collapses || shape.last() != Some(&class) {
+            shape.push(class);
+        }
+    }
+    shape
+}
+
+/// 0 for empty outputs and `k` for lengths in `2^(k-1)..2^k`
+fn length_class(output: &[u8]) -> u32 {
+    usize::BITS - output.len().leading_zeros()
+}
+
+fn feature_index(feature: impl Hash, map_size: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    feature.hash(&mut hasher);
+    (hasher.finish() % map_size as u64) as usize
+}
+
+impl<E, M, EM, Z> Executor<EM, Z> for OutputNoveltyExecutor<E, M>
+where
+    E: Executor<EM, Z> + HasObservers,
+    M: MapObserver<Entry = u8>,
+    EM: UsesState<State = E::State>,
+    Z: UsesState<State = E::State>,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut Self::State,
+        mgr: &mut EM,
+        input: &<Self::State as UsesInput>::Input,
+    ) -> Result<ExitKind, Error> {
+        let exit_kind = self.inner.run_target(fuzzer, state, mgr, input)?;
+        if !self.enabled {
+            return Ok(exit_kind);
+        }
+
+        let mut observers = self.inner.observers_mut();
+        let exit = observers
+            .get(&self.exit_status_observer)
+            .and_then(|o| o.status)
+            .map_or_else(|| format!("{exit_kind:?}"), |status| status.to_string());
+        let stdout = observers
+            .get(&self.stdout_observer)
+            .and_then(|o| o.stdout.clone())
+            .unwrap_or_default();
+        let stderr = observers
+            .get(&self.stderr_observer)
+            .and_then(|o| o.stderr.clone())
+            .unwrap_or_default();
+        let map = observers.get_mut(&self.map_observer).ok_or_else(|| {
+            Error::illegal_argument(format!(
+                "OutputNoveltyExecutor: observer {} not found",
+                self.map_observer.name()
+            ))
+        })?;
+
+        let map_size = map.usable_count();
+        for index in [
+            feature_index(("exit", &exit), map_size),
+            feature_index(("stdout", &exit, shape(&stdout)), map_size),
+            feature_index(("stderr", &exit, shape(&stderr)), map_size),
+            feature_index(
+                (
+                    "length",
+                    &exit,
+                    length_class(&stdout),
+                    length_class(&stderr),
+                ),
+                map_size,
+            ),
+        ] {
+            map.set(index, 1);
+        }
+        Ok(exit_kind)
+    }
+}
+
+impl<E, M> UsesState for OutputNoveltyExecutor<E, M>
+where
+    E: UsesState,
+{
+    type State = E::State;
+}
+
+impl<E, M> UsesObservers for OutputNoveltyExecutor<E, M>
+where
+    E: UsesObservers,
+{
+    type Observers = E::Observers;
+}
+
+impl<E, M> HasObservers for OutputNoveltyExecutor<E, M>
+where
+    E: HasObservers,
+{
+    fn observers(&self) -> RefIndexable<&Self::Observers, Self::Observers> {
+        self.inner.observers()
+    }
+
+    fn observers_mut(&mut self) -> RefIndexable<&mut Self::Observers, Self::Observers> {
+        self.inner.observers_mut()
+    }
+}
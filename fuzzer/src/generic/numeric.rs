@@ -0,0 +1,314 @@
+//! Numeric option values as raw strings. Besides plain decimal numbers, parsers are fed values like `0x10`, `+5`,
+//! `' 5'`, `5k`, the empty string or numbers overflowing any integer type, since this is where implementations using
+//! `xstrtol` and ones using `str::parse` tend to diverge.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::{Range, RangeInclusive},
+};
+
+use libafl_bolts::prelude::Rand;
+use serde::{Deserialize, Serialize};
+
+use super::options::OptionValue;
+
+/// Suffixes no utility accepts after a number, or only some of them
+const GARBAGE_SUFFIXES: [&str; 16] = [
+    "k", "K", "M", "G", "b", "B", "KB", "KiB", "e3", ".0", ".5", ",", "x", "_", " ", "abc",
+];
+
+const WHITESPACE: [&str; 4] = [" ", "\t", "\n", "\x0b"];
+
+/// Values at which integer parsing commonly changes behavior
+const BOUNDARIES: [i128; 16] = [
+    0,
+    1,
+    -1,
+    i8::MIN as i128,
+    i8::MAX as i128,
+    u8::MAX as i128,
+    i16::MIN as i128,
+    i16::MAX as i128,
+    u16::MAX as i128,
+    i32::MIN as i128,
+    i32::MAX as i128,
+    u32::MAX as i128,
+    i64::MIN as i128,
+    i64::MAX as i128,
+    u64::MAX as i128,
+    i128::MAX,
+];
+
+/// A decimal number from `range`, followed by one of `suffixes` if there are any, and sometimes altered by
+/// [`mutate_numeric`].
+pub fn generate_numeric<R: Rand>(
+    rand: &mut R,
+    range: &RangeInclusive<i128>,
+    suffixes: &[&str],
+) -> Vec<u8> {
+    let mut value = plain(rand, range, suffixes);
+    if rand.coinflip(0.3) {
+        mutate_numeric(rand, &mut value, range, suffixes);
+    }
+    value
+}
+
+/// Changes the number within `value` or the characters around it.
+pub fn mutate_numeric<R: Rand>(
+    rand: &mut R,
+    value: &mut Vec<u8>,
+    range: &RangeInclusive<i128>,
+    suffixes: &[&str],
+) {
+    let span = number_span(value);
+    let number = span.clone().and_then(|span| parse(&value[span]));
+    match rand.below(10) {
+        // a boundary value, keeping prefix and suffix
+        0 => {
+            let boundary = boundary(rand, range).to_string().into_bytes();
+            splice(value, span, boundary);
+        }
+        // a nearby or negated value
+        1 => {
+            let number = number.unwrap_or_else(|| value_in(rand, range));
+            let changed = match rand.below(3) {
+                0 => number.saturating_add(rand.between(1, 16) as i128),
+                1 => number.saturating_sub(rand.between(1, 16) as i128),
+                _ => number.saturating_neg(),
+            };
+            splice(value, span, changed.to_string().into_bytes());
+        }
+        // a radix prefix
+        2 => {
+            let number = number.unwrap_or_else(|| value_in(rand, range));
+            splice(value, span, with_radix(rand, number));
+        }
+        3 => {
+            let whitespace = WHITESPACE[rand.below(WHITESPACE.len())].bytes();
+            if rand.coinflip(0.7) {
+                value.splice(0..0, whitespace);
+            } else {
+                value.extend(whitespace);
+            }
+        }
+        // an additional or removed sign
+        4 => {
+            let start = span.map_or(0, |span| span.start);
+            match value.get(start) {
+                Some(b'+' | b'-') if rand.coinflip(0.5) => {
+                    value.remove(start);
+                }
+                _ => value.insert(start, if rand.coinflip(0.5) { b'+' } else { b'-' }),
+            }
+        }
+        // more digits than any integer type holds
+        5 => {
+            let digits = (0..rand.between(20, 48))
+                .map(|_| rand.between(b'0'.into(), b'9'.into()) as u8)
+                .collect::<Vec<_>>();
+            let end = span.map_or(value.len(), |span| span.end);
+            value.splice(end..end, digits);
+        }
+        // a different or additional suffix
+        6 => {
+            if let Some(span) = &span {
+                if rand.coinflip(0.5) {
+                    value.truncate(span.end);
+                }
+            }
+            let suffix = if !suffixes.is_empty() && rand.coinflip(0.5) {
+                suffixes[rand.below(suffixes.len())]
+            } else {
+                GARBAGE_SUFFIXES[rand.below(GARBAGE_SUFFIXES.len())]
+            };
+            value.extend(suffix.bytes());
+        }
+        7 => {
+            let start = span.map_or(0, |span| {
+                span.start + usize::from(matches!(value[span.start], b'+' | b'-'))
+            });
+            value.splice(start..start, vec![b'0'; rand.between(1, 4)]);
+        }
+        8 => {
+            if value.is_empty() || rand.coinflip(0.3) {
+                value.clear();
+            } else {
+                value.pop();
+            }
+        }
+        _ => *value = plain(rand, range, suffixes),
+    }
+}
+
+fn plain<R: Rand>(rand: &mut R, range: &RangeInclusive<i128>, suffixes: &[&str]) -> Vec<u8> {
+    let mut value = value_in(rand, range).to_string().into_bytes();
+    if !suffixes.is_empty() {
+        value.extend(suffixes[rand.below(suffixes.len())].bytes());
+    }
+    value
+}
+
+fn value_in<R: Rand>(rand: &mut R, range: &RangeInclusive<i128>) -> i128 {
+    let random = u128::from(rand.next()) << 64 | u128::from(rand.next());
+    match (range.end().wrapping_sub(*range.start()) as u128).checked_add(1) {
+        Some(width) => range.start().wrapping_add((random % width) as i128),
+        None => random as i128,
+    }
+}
+
+/// One of the ends of `range` or a common boundary, sometimes off by one.
+fn boundary<R: Rand>(rand: &mut R, range: &RangeInclusive<i128>) -> i128 {
+    let value = match rand.below(3) {
+        0 => *range.start(),
+        1 => *range.end(),
+        _ => BOUNDARIES[rand.below(BOUNDARIES.len())],
+    };
+    match rand.below(4) {
+        0 => value.saturating_sub(1),
+        1 => value.saturating_add(1),
+        _ => value,
+    }
+}
+
+fn with_radix<R: Rand>(rand: &mut R, number: i128) -> Vec<u8> {
+    let sign = if number < 0 { "-" } else { "" };
+    let magnitude = number.unsigned_abs();
+    match rand.below(4) {
+        0 => format!("{sign}0x{magnitude:x}"),
+        1 => format!("{sign}0X{magnitude:X}"),
+        2 => format!("{sign}0{magnitude:o}"),
+        _ => format!("{sign}0b{magnitude:b}"),
+    }
+    .into_bytes()
+}
+
+/// The first run of digits in `value`, including a sign directly in front of it
+fn number_span(value: &[u8]) -> Option<Range<usize>> {
+    let start = value.iter().position(u8::is_ascii_digit)?;
+    let end = value[start..]
+        .iter()
+        .position(|b| !b.is_ascii_digit())
+        .map_or(value.len(), |len| start + len);
+    let start = match start.checked_sub(1).map(|i| value[i]) {
+        Some(b'+' | b'-') => start - 1,
+        _ => start,
+    };
+    Some(start..end)
+}
+
+fn parse(number: &[u8]) -> Option<i128> {
+    std::str::from_utf8(number).ok()?.parse().ok()
+}
+
+/// Replaces `span` of `value` or, if there is no number, all of it
+fn splice(value: &mut Vec<u8>, span: Option<Range<usize>>, replacement: Vec<u8>) {
+    match span {
+        Some(span) => {
+            value.splice(span, replacement);
+        }
+        None => *value = replacement,
+    }
+}
+
+/// The value of a numeric option, passed to the utility exactly as stored. Use it in place of an integer type for
+/// `#[opt]` fields, the range then describes the values the utility accepts.
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct NumericArg(pub Vec<u8>);
+
+impl Display for NumericArg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", String::from_utf8_lossy(&self.0))
+    }
+}
+
+impl OptionValue for NumericArg {
+    type Bound = i128;
+
+    fn full_range() -> RangeInclusive<i128> {
+        i128::MIN..=i128::MAX
+    }
+
+    fn generate<R: Rand>(rand: &mut R, range: RangeInclusive<i128>) -> Self {
+        Self(generate_numeric(rand, &range, &[]))
+    }
+
+    fn mutate<R: Rand>(&mut self, rand: &mut R, range: RangeInclusive<i128>) {
+        mutate_numeric(rand, &mut self.0, &range, &[]);
+    }
+
+    fn to_arg(&self) -> Vec<u8> {
+        self.0.clone()
+    }
+
+    fn bytes_mut(&mut self) -> Option<&mut Vec<u8>> {
+        Some(&mut self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl_bolts::rands::StdRand;
+
+    use super::*;
+
+    #[test]
+    fn finds_the_number_with_its_sign() {
+        assert_eq!(number_span(b"  -12k"), Some(2..5));
+        assert_eq!(number_span(b"+0x10"), Some(0..2));
+        assert_eq!(number_span(b"k5"), Some(1..2));
+        assert_eq!(number_span(b"abc"), None);
+        assert_eq!(number_span(b""), None);
+    }
+
+    #[test]
+    fn plain_values_are_in_range() {
+        let mut rand = StdRand::with_seed(0x5eed);
+        for _ in 0..1000 {
+            let value = plain(&mut rand, &(-3..=40), &["K", "M"]);
+            let (number, suffix) = value.split_at(value.len() - 1);
+            assert!((-3..=40).contains(&parse(number).unwrap()));
+            assert!(suffix == b"K" || suffix == b"M");
+        }
+        for range in [i128::MIN..=i128::MAX, 7..=7] {
+            for _ in 0..100 {
+                assert!(range.contains(&parse(&plain(&mut rand, &range, &[])).unwrap()));
+            }
+        }
+    }
+
+    #[test]
+    fn mutations_reach_what_parsers_disagree_on() {
+        let mut rand = StdRand::with_seed(0x5eed);
+        let range = 0..=i64::MAX as i128;
+        let (mut radix, mut whitespace, mut overflow, mut garbage, mut empty) = (0, 0, 0, 0, 0);
+        for _ in 0..2000 {
+            let mut value = b"42".to_vec();
+            for _ in 0..rand.between(1, 4) {
+                mutate_numeric(&mut rand, &mut value, &range, &["k"]);
+            }
+            let value = String::from_utf8(value).expect("only ASCII is inserted");
+            radix += usize::from(["0x", "0X", "0b"].iter().any(|p| value.contains(p)));
+            whitespace += usize::from(value.contains(char::is_whitespace));
+            overflow += usize::from(value.chars().filter(char::is_ascii_digit).count() > 39);
+            garbage += usize::from(GARBAGE_SUFFIXES.iter().any(|s| value.ends_with(s)));
+            empty += usize::from(value.is_empty());
+        }
+        for count in [radix, whitespace, overflow, garbage, empty] {
+            assert!(
+                count > 10,
+                "{radix} {whitespace} {overflow} {garbage} {empty}"
+            );
+        }
+    }
+
+    #[test]
+    fn mutates_values_without_a_number() {
+        let mut rand = StdRand::with_seed(1);
+        for value in [&b""[..], b"+", b"-", b" ", b"abc", b"0x"] {
+            for _ in 0..200 {
+                let mut value = value.to_vec();
+                mutate_numeric(&mut rand, &mut value, &(i128::MIN..=i128::MAX), &[]);
+            }
+        }
+    }
+}
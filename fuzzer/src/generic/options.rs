@@ -0,0 +1,81 @@
+//! Runtime support for inputs created with `#[derive(UtilInput)]` from the `input_derive` crate.
+
+use std::ops::RangeInclusive;
+
+use libafl_bolts::prelude::Rand;
+
+/// How the bytes passed to `stdin` are generated
+#[allow(dead_code)]
+pub enum GeneratorType {
+    Random,
+    Printable,
+    A,
+}
+
+pub fn generate_bytes<R: Rand>(
+    rand: &mut R,
+    len: usize,
+    generator_type: &GeneratorType,
+) -> Vec<u8> {
+    match generator_type {
+        GeneratorType::Random => (0..len)
+            .map(|_e| rand.below(u8::MAX as usize + 1) as u8)
+            .collect::<Vec<_>>(),
+        GeneratorType::Printable => (0..len)
+            .map(|_e| rand.between(0x20, 0x7F) as u8)
+            .collect::<Vec<_>>(),
+        GeneratorType::A => vec![0x61; len],
+    }
+}
+
+/// The value of an option, generated from a non-empty range of `Bound`s.
+pub trait OptionValue: Sized {
+    type Bound;
+
+    /// The range used if none is given
+    // only called by the code derived for options without a range
+    #[allow(dead_code)]
+    fn full_range() -> RangeInclusive<Self::Bound>;
+
+    fn generate<R: Rand>(rand: &mut R, range: RangeInclusive<Self::Bound>) -> Self;
+
+    /// Changes the value, by default to a newly generated one
+    fn mutate<R: Rand>(&mut self, rand: &mut R, range: RangeInclusive<Self::Bound>) {
+        *self = Self::generate(rand, range);
+    }
+
+    /// The value as passed to the utility
+    fn to_arg(&self) -> Vec<u8>;
+
+    /// The raw bytes of the value, if it is kept as such and may be shortened
+    fn bytes_mut(&mut self) -> Option<&mut Vec<u8>> {
+        None
+    }
+}
+
+macro_rules! impl_option_value {
+    ($($t:ty),*) => {
+        $(
+            impl OptionValue for $t {
+                type Bound = Self;
+
+                fn full_range() -> RangeInclusive<Self> {
+                    Self::MIN..=Self::MAX
+                }
+
+                fn generate<R: Rand>(rand: &mut R, range: RangeInclusive<Self>) -> Self {
+                    let (start, end) = range.into_inner();
+                    let width = (end as i128 - start as i128) as u128 + 1;
+                    let offset = (u128::from(rand.next()) << 64 | u128::from(rand.next())) % width;
+                    (start as i128 + offset as i128) as Self
+                }
+
+                fn to_arg(&self) -> Vec<u8> {
+                    self.to_string().into_bytes()
+                }
+            }
+        )*
+    };
+}
+
+impl_option_value!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
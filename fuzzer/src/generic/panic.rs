@@ -0,0 +1,314 @@
+use std::{borrow::Cow, collections::BTreeMap, sync::OnceLock};
+
+use libafl::{
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    observers::{ObserversTuple, StdErrObserver},
+    state::State,
+    Error, HasMetadata, SerdeAny,
+};
+use libafl_bolts::{
+    tuples::{Handle, Handled},
+    Named,
+};
+use regex::bytes::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    divergence::{observed, sanitised_name},
+    exit_status::{ExitStatus, ExitStatusObserver},
+    majority::NWayObserver,
+};
+
+/// The exit code of a Rust program after a panic
+const PANIC_EXIT_CODE: i32 = 101;
+const SIGABRT: i32 = 6;
+
+/// How an implementation gave up
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PanicKind {
+    /// a Rust panic, printing `panicked at <file>:<line>:<column>`
+    Panic,
+    /// a failed C `assert`, printing `<file>:<line>: <function>: Assertion '<expression>' failed.` before aborting
+    Assertion,
+    /// any other `abort()`, e.g. by a Rust panic with `panic = "abort"` without a message
+    Abort,
+}
+
+/// A panic, failed assertion or abort detected in what an implementation printed to `stderr` and how it exited.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PanicReport {
+    pub kind: PanicKind,
+    /// `<file>:<line>` of the panic or assertion
+    pub location: Option<String>,
+    /// the line reporting the panic or assertion
+    pub message: Option<String>,
+}
+
+fn panic_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    // `panicked at <file>:<line>:<column>:` since Rust 1.73, `panicked at '<message>', <file>:<line>:<column>` before
+    REGEX.get_or_init(|| {
+        Regex::new(r"(?m)^.*panicked at (?:'.*', )?([^\s:']+:\d+):\d+").expect("valid regex")
+    })
+}
+
+fn assertion_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"(?m)^(?:.*: )?([^\s:]+:\d+): .*: Assertion .* failed\.$").expect("valid regex")
+    })
+}
+
+impl PanicReport {
+    /// Detects a panic from the output of an implementation. Panics need to exit with code 101 or abort, failed
+    /// assertions need to abort. If the exit status is unknown, e.g. on timeout, the messages suffice.
+    pub fn detect(stderr: &[u8], exit_status: Option<ExitStatus>) -> Option<Self> {
+        let aborted = exit_status == Some(ExitStatus::Signal(SIGABRT));
+        let report = |kind, captures: regex::bytes::Captures| Self {
+            kind,
+            location: captures
+                .get(1)
+                .map(|m| String::from_utf8_lossy(m.as_bytes()).into_owned()),
+            message: captures
+                .get(0)
+                .map(|m| String::from_utf8_lossy(m.as_bytes()).trim().to_string()),
+        };
+
+        let panicked = exit_status.is_none()
+            || aborted
+            || exit_status == Some(ExitStatus::Code(PANIC_EXIT_CODE));
+        if panicked {
+            if let Some(captures) = panic_regex().captures(stderr) {
+                return Some(report(PanicKind::Panic, captures));
+            }
+        }
+        if aborted || exit_status.is_none() {
+            if let Some(captures) = assertion_regex().captures(stderr) {
+                return Some(report(PanicKind::Assertion, captures));
+            }
+        }
+        aborted.then_some(Self {
+            kind: PanicKind::Abort,
+            location: None,
+            message: None,
+        })
+    }
+
+    /// A readable name identifying the panic by its kind and location, usable as part of a directory name
+    pub fn bucket_name(&self) -> String {
+        let kind = match self.kind {
+            PanicKind::Panic => "panic",
+            PanicKind::Assertion => "assertion",
+            PanicKind::Abort => "abort",
+        };
+        match &self.location {
+            Some(location) => format!("{kind}-{}", sanitised_name(location)),
+            None => kind.to_string(),
+        }
+    }
+}
+
+/// The panics found in an objective, see [`PanicFeedback`]
+#[derive(Serialize, Deserialize, Clone, Debug, SerdeAny)]
+pub struct PanicMetadata {
+    /// the name of the implementation and what it reported
+    pub panics: Vec<(String, PanicReport)>,
+}
+
+/// How often each panic bucket was hit by a [`PanicFeedback`] acting as crash objective, kept in the state
+#[derive(Serialize, Deserialize, Clone, Debug, Default, SerdeAny)]
+pub struct PanicBucketsMetadata {
+    /// the hits by implementation and [`PanicReport::bucket_name`]
+    pub hits: BTreeMap<String, u64>,
+}
+
+/// Interesting if an implementation panicked, failed an assertion or aborted, see [`PanicReport::detect`]. These exit
+/// like an error or with a signal, so they would otherwise only show as a difference in exit status or `stderr` if
+/// the other implementation does not fail the same way, and not at all for a single implementation.
+///
+/// As crash objective, created with [`PanicFeedback::crash_objective`], it replaces a
+/// [`CrashFeedback`](libafl::feedbacks::CrashFeedback): other crashes are all interesting, while only the first input of
+/// each panic bucket is, i.e. per implementation, kind and location. Further hits are counted in the
+/// [`PanicBucketsMetadata`] of the state. Without, divergences are bucketed by their signature instead.
+pub struct PanicFeedback {
+    implementations: Vec<(String, Handle<StdErrObserver>, Handle<ExitStatusObserver>)>,
+    /// the outcomes of the implementations run by an [`NWayExecutor`](super::majority::NWayExecutor), checked in
+    /// addition to `implementations`
+    outcome_observer: Option<Handle<NWayObserver>>,
+    crash_objective: bool,
+    panics: Vec<(String, PanicReport)>,
+}
+
+impl PanicFeedback {
+    /// `implementations` are the names of the implementations and their observers
+    pub fn new(implementations: &[(&str, &StdErrObserver, &ExitStatusObserver)]) -> Self {
+        Self {
+            implementations: implementations
+                .iter()
+                .map(|(name, stderr, exit_status)| {
+                    (name.to_string(), stderr.handle(), exit_status.handle())
+                })
+                .collect(),
+            outcome_observer: None,
+            crash_objective: false,
+            panics: Vec::new(),
+        }
+    }
+
+    /// Like [`PanicFeedback::new`], but for the implementations whose outcomes `outcome_observer` holds
+    pub fn n_way(outcome_observer: &NWayObserver) -> Self {
+        Self {
+            outcome_observer: Some(outcome_observer.handle()),
+            ..Self::new(&[])
+        }
+    }
+
+    /// Like [`PanicFeedback::new`], but reporting other crashes as well and only the first input of each panic bucket
+    pub fn crash_objective(
+        implementations: &[(&str, &StdErrObserver, &ExitStatusObserver)],
+    ) -> Self {
+        Self {
+            crash_objective: true,
+            ..Self::new(implementations)
+        }
+    }
+}
+
+impl<S> Feedback<S> for PanicFeedback
+where
+    S: State + HasMetadata,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        self.panics.clear();
+        if let Some(outcome_observer) = &self.outcome_observer {
+            let observer = observed("PanicFeedback", outcome_observer, observers)?;
+            for (name, outcome) in observer.names.iter().zip(&observer.outcomes) {
+                let execution = &outcome.execution;
+                if let Some(report) = PanicReport::detect(&execution.stderr, execution.exit_status)
+                {
+                    self.panics.push((name.clone(), report));
+                }
+            }
+        }
+        for (name, stderr_observer, exit_status_observer) in &self.implementations {
+            let stderr = observed("PanicFeedback", stderr_observer, observers)?
+                .stderr
+                .as_deref()
+                .unwrap_or_default();
+            let status = observed("PanicFeedback", exit_status_observer, observers)?.status;
+            if let Some(report) = PanicReport::detect(stderr, status) {
+                self.panics.push((name.clone(), report));
+            }
+        }
+        if !self.crash_objective {
+            return Ok(!self.panics.is_empty());
+        }
+        if self.panics.is_empty() {
+            return Ok(*exit_kind == ExitKind::Crash);
+        }
+
+        let buckets = state.metadata_or_insert_with(PanicBucketsMetadata::default);
+        let mut new_bucket = false;
+        for (name, report) in &self.panics {
+            let hits = buckets
+                .hits
+                .entry(format!("{name}_{}", report.bucket_name()))
+                .or_default();
+            new_bucket |= *hits == 0;
+            *hits += 1;
+        }
+        Ok(new_bucket)
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        if !self.panics.is_empty() {
+            testcase.metadata_map_mut().insert(PanicMetadata {
+                panics: std::mem::take(&mut self.panics),
+            });
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.panics.clear();
+        Ok(())
+    }
+}
+
+impl Named for PanicFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("PanicFeedback")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_panics() {
+        let stderr =
+            b"thread 'main' panicked at src/uu/ls/src/ls.rs:123:45:\nindex out of bounds\n";
+        let report = PanicReport::detect(stderr, Some(ExitStatus::Code(PANIC_EXIT_CODE))).unwrap();
+        assert_eq!(report.kind, PanicKind::Panic);
+        assert_eq!(report.location.as_deref(), Some("src/uu/ls/src/ls.rs:123"));
+        assert_eq!(
+            report.message.as_deref(),
+            Some("thread 'main' panicked at src/uu/ls/src/ls.rs:123:45")
+        );
+        assert_eq!(report.bucket_name(), "panic-src-uu-ls-src-ls-rs-123");
+
+        // before Rust 1.73
+        let stderr = b"thread 'main' panicked at 'oops: x', src/main.rs:7:5\n";
+        let report = PanicReport::detect(stderr, Some(ExitStatus::Signal(SIGABRT))).unwrap();
+        assert_eq!(report.location.as_deref(), Some("src/main.rs:7"));
+        // the messages suffice if the exit status is unknown
+        assert!(PanicReport::detect(stderr, None).is_some());
+        // a utility may print the message on purpose
+        assert_eq!(PanicReport::detect(stderr, Some(ExitStatus::Code(1))), None);
+    }
+
+    #[test]
+    fn detects_assertions_and_aborts() {
+        let stderr = b"ls: ls.c:4321: print_dir: Assertion `n < size' failed.\n";
+        let report = PanicReport::detect(stderr, Some(ExitStatus::Signal(SIGABRT))).unwrap();
+        assert_eq!(report.kind, PanicKind::Assertion);
+        assert_eq!(report.location.as_deref(), Some("ls.c:4321"));
+        assert_eq!(report.bucket_name(), "assertion-ls-c-4321");
+        assert_eq!(
+            PanicReport::detect(stderr, Some(ExitStatus::Code(PANIC_EXIT_CODE))),
+            None
+        );
+
+        let report = PanicReport::detect(b"", Some(ExitStatus::Signal(SIGABRT))).unwrap();
+        assert_eq!(report.kind, PanicKind::Abort);
+        assert_eq!(report.bucket_name(), "abort");
+        assert_eq!(PanicReport::detect(b"", None), None);
+        assert_eq!(PanicReport::detect(b"", Some(ExitStatus::Signal(11))), None);
+    }
+}
@@ -0,0 +1,132 @@
+use std::{collections::BTreeMap, fmt::Write, fs, path::Path};
+
+use libafl::{inputs::Input, Error};
+use serde::Serialize;
+
+use super::{
+    bucket::{REPRESENTATIVE_FILE, SIGNATURE_FILE},
+    divergence::DivergenceSignature,
+    executor::{CoverageConfigurator, ExtractsToCommand},
+    replay::{with_replayer, ReplaySetup},
+    spelling::HasArgSpelling,
+};
+
+/// The file in the buckets' directory holding the [`RecheckStatus`] of each bucket
+pub static STATUS_FILE: &str = "recheck.json";
+/// The file in the buckets' directory holding the summary table of the last recheck
+pub static SUMMARY_FILE: &str = "recheck.txt";
+
+/// What became of a bucket's divergence with the current binaries
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecheckStatus {
+    /// the representative still diverges with the bucket's signature
+    StillDiverging,
+    /// the implementations no longer diverge on the representative
+    Fixed,
+    /// the representative diverges with another signature
+    ChangedSignature,
+}
+
+impl RecheckStatus {
+    fn name(&self) -> &'static str {
+        match self {
+            RecheckStatus::StillDiverging => "still diverging",
+            RecheckStatus::Fixed => "fixed",
+            RecheckStatus::ChangedSignature => "changed signature",
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct RecheckResult {
+    status: RecheckStatus,
+    /// the signature the representative diverges with now
+    signature: DivergenceSignature,
+    /// the bucket the representative would now be put in, if it still diverges
+    bucket: Option<String>,
+}
+
+/// Re-executes the representative of every bucket in `dir` against both targets of `setup` and classifies the
+/// bucket by the [`RecheckStatus`] of its divergence. Writes the results to [`STATUS_FILE`] and a summary table
+/// to [`SUMMARY_FILE`], which is printed as well. Buckets whose representative is not an input of type `I` are
+/// skipped.
+pub fn recheck<I, C>(dir: &Path, setup: ReplaySetup) -> Result<(), Error>
+where
+    I: Input + ExtractsToCommand + HasArgSpelling,
+    C: CoverageConfigurator<I>,
+{
+    let read_error = |e| Error::os_error(e, format!("Could not read {}", dir.display()));
+    let mut buckets = fs::read_dir(dir)
+        .map_err(read_error)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(read_error)?;
+    buckets.retain(|bucket| bucket.join(SIGNATURE_FILE).is_file());
+    buckets.sort();
+
+    let results = with_replayer::<I, C, _>(setup, |run| {
+        let mut results = BTreeMap::new();
+        for bucket in buckets {
+            let name = bucket
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let Ok(representative) = I::from_file(bucket.join(REPRESENTATIVE_FILE)) else {
+                println!("Skipping bucket {name} without a readable representative");
+                continue;
+            };
+
+            let signature = run(&representative)?.signature;
+            let (status, new_bucket) = if signature.differs.is_empty() {
+                (RecheckStatus::Fixed, None)
+            } else if signature.bucket_name() == name {
+                (RecheckStatus::StillDiverging, Some(name.clone()))
+            } else {
+                (
+                    RecheckStatus::ChangedSignature,
+                    Some(signature.bucket_name()),
+                )
+            };
+            results.insert(
+                name,
+                RecheckResult {
+                    status,
+                    signature,
+                    bucket: new_bucket,
+                },
+            );
+        }
+        Ok(results)
+    })?;
+
+    let write_error = |e| Error::os_error(e, "Could not write recheck results");
+    serde_json::to_writer_pretty(
+        fs::File::create(dir.join(STATUS_FILE)).map_err(write_error)?,
+        &results,
+    )
+    .map_err(|e| Error::serialize(format!("Could not write recheck results: {e}")))?;
+
+    let width = results.keys().map(String::len).max().unwrap_or_default();
+    let mut summary = format!("{:<17}  {:<width$}  now\n", "status", "bucket");
+    for (name, result) in &results {
+        let now = match result.status {
+            RecheckStatus::ChangedSignature => result.bucket.as_deref().unwrap_or_default(),
+            _ => "",
+        };
+        let line = format!("{:<17}  {name:<width$}  {now}", result.status.name());
+        let _ = writeln!(summary, "{}", line.trim_end());
+    }
+    let count = |status| results.values().filter(|r| r.status == status).count();
+    let _ = writeln!(
+        summary,
+        "\n{} buckets: {} still diverging, {} fixed, {} changed signature",
+        results.len(),
+        count(RecheckStatus::StillDiverging),
+        count(RecheckStatus::Fixed),
+        count(RecheckStatus::ChangedSignature)
+    );
+    fs::write(dir.join(SUMMARY_FILE), &summary).map_err(write_error)?;
+    print!("{summary}");
+    Ok(())
+}
@@ -0,0 +1,417 @@
+use std::{
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use libafl::{
+    corpus::InMemoryCorpus,
+    events::NopEventManager,
+    executors::{DiffExecutor, Executor, ExitKind, HasObservers},
+    feedbacks::ConstFeedback,
+    fuzzer::NopFuzzer,
+    inputs::Input,
+    observers::{StdErrObserver, StdOutObserver},
+    state::StdState,
+    Error,
+};
+use libafl_bolts::{
+    rands::StdRand,
+    tuples::{tuple_list, Handled},
+};
+
+use super::{
+    bucket::{HITS_FILE, SIGNATURE_FILE},
+    divergence::{Classifier, DivergenceClassifier, DivergenceSignature, Execution},
+    equivalence::SpellingEquivalenceExecutor,
+    executor::{CoverageConfigurator, ExtractsToCommand},
+    exit_status::{ExitStatusEquivalence, ExitStatusObserver},
+    implementation::Implementation,
+    namespace::TreeSnapshotObserver,
+    shmem::get_shmem,
+    spelling::HasArgSpelling,
+    stderr::StderrNormaliser,
+    stdio::{args_string_mapper, StdoutPolicy},
+};
+
+type ReplayState<I> = StdState<I, InMemoryCorpus<I>, StdRand, InMemoryCorpus<I>>;
+
+/// The number of bytes per line of a hexdump
+const HEXDUMP_WIDTH: usize = 16;
+/// The maximal number of line pairs compared when diffing, larger outputs are shown as removed and added as a whole
+const MAX_DIFF_CELLS: usize = 1 << 24;
+
+/// The `hexdump -C` style dump of `bytes`
+pub fn hexdump(bytes: &[u8]) -> String {
+    let mut dump = String::new();
+    for (line, chunk) in bytes.chunks(HEXDUMP_WIDTH).enumerate() {
+        let hex = chunk
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let ascii = chunk
+            .iter()
+            .map(|b| {
+                if b.is_ascii_graphic() || *b == b' ' {
+                    *b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect::<String>();
+        let _ = writeln!(
+            dump,
+            "{:08x}  {hex:<width$}  |{ascii}|",
+            line * HEXDUMP_WIDTH,
+            width = HEXDUMP_WIDTH * 3 - 1
+        );
+    }
+    dump
+}
+
+/// A unified diff of the lines of `old` and `new` with the whole text as context, empty if they are equal
+pub fn unified_diff(old_name: &str, old: &str, new_name: &str, new: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+    let old = old.split_inclusive('\n').collect::<Vec<_>>();
+    let new = new.split_inclusive('\n').collect::<Vec<_>>();
+    let prefix = old.iter().zip(&new).take_while(|(o, n)| o == n).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(o, n)| o == n)
+        .count();
+    let (old_middle, new_middle) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let mut lines = old[..prefix]
+        .iter()
+        .map(|line| (' ', *line))
+        .collect::<Vec<_>>();
+    if old_middle.len() * new_middle.len() > MAX_DIFF_CELLS {
+        lines.extend(old_middle.iter().map(|line| ('-', *line)));
+        lines.extend(new_middle.iter().map(|line| ('+', *line)));
+    } else {
+        // lengths of the longest common subsequences of the suffixes of both middles
+        let mut lcs = vec![vec![0usize; new_middle.len() + 1]; old_middle.len() + 1];
+        for i in (0..old_middle.len()).rev() {
+            for j in (0..new_middle.len()).rev() {
+                lcs[i][j] = if old_middle[i] == new_middle[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < old_middle.len() || j < new_middle.len() {
+            if i < old_middle.len() && j < new_middle.len() && old_middle[i] == new_middle[j] {
+                lines.push((' ', old_middle[i]));
+                (i, j) = (i + 1, j + 1);
+            } else if j == new_middle.len()
+                || (i < old_middle.len() && lcs[i + 1][j] >= lcs[i][j + 1])
+            {
+                lines.push(('-', old_middle[i]));
+                i += 1;
+            } else {
+                lines.push(('+', new_middle[j]));
+                j += 1;
+            }
+        }
+    }
+    lines.extend(old[old.len() - suffix..].iter().map(|line| (' ', *line)));
+
+    let mut diff = format!(
+        "--- {old_name}\n+++ {new_name}\n@@ -1,{} +1,{} @@\n",
+        old.len(),
+        new.len()
+    );
+    for (marker, line) in lines {
+        diff.push(marker);
+        diff.push_str(line);
+        if !line.ends_with('\n') {
+            diff.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+    diff
+}
+
+/// A diff of two outputs: a [`unified_diff`] if both are UTF-8, else one of their [`hexdump`]s
+pub fn output_diff(old_name: &str, old: &[u8], new_name: &str, new: &[u8]) -> String {
+    match (std::str::from_utf8(old), std::str::from_utf8(new)) {
+        (Ok(old), Ok(new)) => unified_diff(old_name, old, new_name, new),
+        _ => unified_diff(old_name, &hexdump(old), new_name, &hexdump(new)),
+    }
+}
+
+/// The files below `path` which may hold inputs, skipping the other files the fuzzer writes next to them
+fn input_files(path: &Path) -> Result<Vec<PathBuf>, Error> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut entries = fs::read_dir(path)
+        .map_err(|e| Error::os_error(e, format!("Could not read {}", path.display())))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| Error::os_error(e, format!("Could not read {}", path.display())))?;
+    entries.sort();
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let name = entry
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if entry.is_dir() {
+            files.extend(input_files(&entry)?);
+        } else if !(name.starts_with('.')
+            || name == HITS_FILE
+            || name == SIGNATURE_FILE
+            || name.ends_with(".metadata")
+            || name.ends_with(".txt"))
+        {
+            files.push(entry);
+        }
+    }
+    Ok(files)
+}
+
+/// How inputs are replayed: the binaries under test and how their executions are compared
+pub struct ReplaySetup<'a> {
+    pub targets: [&'a Implementation; 2],
+    pub stdout_policy: StdoutPolicy,
+    pub exit_status_equivalence: ExitStatusEquivalence,
+    pub stderr_normaliser: Arc<StderrNormaliser>,
+}
+
+/// What both implementations did when replaying an input
+pub struct Replayed {
+    pub exit_kind: ExitKind,
+    pub signature: DivergenceSignature,
+    pub executions: [Execution; 2],
+}
+
+/// Builds the executors used for fuzzing for both targets of `setup`, but without observing coverage, and passes
+/// `body` a function replaying a single input with them.
+pub fn with_replayer<I, C, R>(
+    setup: ReplaySetup,
+    body: impl FnOnce(&mut dyn FnMut(&I) -> Result<Replayed, Error>) -> Result<R, Error>,
+) -> Result<R, Error>
+where
+    I: Input + ExtractsToCommand + HasArgSpelling,
+    C: CoverageConfigurator<I>,
+{
+    let [target1, target2] = setup.targets;
+    let id = |target: &Implementation| format!("{}-replay", target.name);
+    // coverage is not observed, but the binaries expect a map of their size
+    let (_coverage_shmem1, coverage_shmem_description1) = get_shmem(target1.coverage_shmem_size)?;
+    let (_coverage_shmem2, coverage_shmem_description2) = get_shmem(target2.coverage_shmem_size)?;
+
+    let stdout_observers = [
+        StdOutObserver::new("replay-stdout-observer-1"),
+        StdOutObserver::new("replay-stdout-observer-2"),
+    ];
+    let stderr_observers = [
+        StdErrObserver::new("replay-stderr-observer-1"),
+        StdErrObserver::new("replay-stderr-observer-2"),
+    ];
+    let tree_observers = [
+        TreeSnapshotObserver::new("replay-tree-observer-1", &id(target1)),
+        TreeSnapshotObserver::new("replay-tree-observer-2", &id(target2)),
+    ];
+    let exit_status_observers = [
+        ExitStatusObserver::new("replay-exit-status-observer-1"),
+        ExitStatusObserver::new("replay-exit-status-observer-2"),
+    ];
+    let classifier = DivergenceClassifier::new(
+        [&stdout_observers[0], &stdout_observers[1]],
+        [&stderr_observers[0], &stderr_observers[1]],
+        [&exit_status_observers[0], &exit_status_observers[1]],
+        [&tree_observers[0], &tree_observers[1]],
+        setup.stdout_policy,
+        setup.exit_status_equivalence.clone(),
+        setup.stderr_normaliser,
+    );
+    let stdout_handles = [stdout_observers[0].handle(), stdout_observers[1].handle()];
+    let stderr_handles = [stderr_observers[0].handle(), stderr_observers[1].handle()];
+    let exit_status_handles = [
+        exit_status_observers[0].handle(),
+        exit_status_observers[1].handle(),
+    ];
+
+    let [stdout_observer1, stdout_observer2] = stdout_observers;
+    let [stderr_observer1, stderr_observer2] = stderr_observers;
+    let [tree_observer1, tree_observer2] = tree_observers;
+    let [exit_status_observer1, exit_status_observer2] = exit_status_observers;
+    let executor1 = SpellingEquivalenceExecutor::new(
+        &target1.name,
+        C::configure(
+            &coverage_shmem_description1,
+            Some(stdout_handles[0].clone()),
+            Some(stderr_handles[0].clone()),
+            target1,
+            &id(target1),
+        )
+        .into_exit_status_executor::<_, ReplayState<I>>(
+            tuple_list!(
+                stdout_observer1,
+                stderr_observer1,
+                tree_observer1,
+                exit_status_observer1
+            ),
+            exit_status_handles[0].clone(),
+        ),
+        stdout_handles[0].clone(),
+        stderr_handles[0].clone(),
+        exit_status_handles[0].clone(),
+        setup.exit_status_equivalence.clone(),
+    );
+    let executor2 = SpellingEquivalenceExecutor::new(
+        &target2.name,
+        C::configure(
+            &coverage_shmem_description2,
+            Some(stdout_handles[1].clone()),
+            Some(stderr_handles[1].clone()),
+            target2,
+            &id(target2),
+        )
+        .into_exit_status_executor::<_, ReplayState<I>>(
+            tuple_list!(
+                stdout_observer2,
+                stderr_observer2,
+                tree_observer2,
+                exit_status_observer2
+            ),
+            exit_status_handles[1].clone(),
+        ),
+        stdout_handles[1].clone(),
+        stderr_handles[1].clone(),
+        exit_status_handles[1].clone(),
+        setup.exit_status_equivalence,
+    );
+    let mut executor = DiffExecutor::new(executor1, executor2, tuple_list!());
+
+    let mut state: ReplayState<I> = StdState::new(
+        StdRand::with_seed(0),
+        InMemoryCorpus::new(),
+        InMemoryCorpus::new(),
+        &mut ConstFeedback::new(false),
+        &mut ConstFeedback::new(false),
+    )?;
+    let mut fuzzer = NopFuzzer::new();
+    let mut mgr = NopEventManager::new();
+
+    body(&mut |input| {
+        let exit_kind = executor.run_target(&mut fuzzer, &mut state, &mut mgr, input)?;
+        let observers = executor.observers();
+        Ok(Replayed {
+            signature: classifier.signature(&state, &*observers, &exit_kind)?,
+            executions: classifier.executions(&*observers)?,
+            exit_kind,
+        })
+    })
+}
+
+/// Runs the inputs at `path`, a file or a directory searched recursively, against both targets of `setup` and
+/// prints the command line, `stdin`, the exit statuses, the [`DivergenceSignature`] and diffs of `stdout` and
+/// `stderr`. Files in a directory which do not hold an input of type `I` are skipped.
+pub fn replay<I, C>(path: &Path, setup: ReplaySetup) -> Result<(), Error>
+where
+    I: Input + ExtractsToCommand + HasArgSpelling,
+    C: CoverageConfigurator<I>,
+{
+    let names = setup.targets.map(|target| target.name.clone());
+    with_replayer::<I, C, _>(setup, |run| {
+        let searched = path.is_dir();
+        for file in input_files(path)? {
+            let input = match I::from_file(&file) {
+                Ok(input) => input,
+                Err(_) if searched => continue,
+                Err(e) => return Err(e),
+            };
+            let replayed = run(&input)?;
+
+            println!("==> {}", file.display());
+            println!("argv: {}", args_string_mapper(&input.get_args()));
+            println!("stdin: {} bytes", input.get_stdin().len());
+            print!("{}", hexdump(input.get_stdin()));
+            for (name, execution) in names.iter().zip(&replayed.executions) {
+                match execution.exit_status {
+                    Some(status) => println!("{name}: {status}"),
+                    None => println!("{name}: no exit status"),
+                }
+            }
+            if !matches!(replayed.exit_kind, ExitKind::Ok) {
+                println!("exit kind: {:?}", replayed.exit_kind);
+            }
+            if replayed.signature.differs.is_empty() {
+                println!("signature: no divergence");
+            } else {
+                println!("signature: {}", replayed.signature.bucket_name());
+            }
+
+            let [execution1, execution2] = &replayed.executions;
+            for (stream, output1, output2) in [
+                ("stdout", &execution1.stdout, &execution2.stdout),
+                ("stderr", &execution1.stderr, &execution2.stderr),
+            ] {
+                let diff = output_diff(
+                    &format!("{} {stream}", names[0]),
+                    output1,
+                    &format!("{} {stream}", names[1]),
+                    output2,
+                );
+                if diff.is_empty() {
+                    println!("{stream}: equal");
+                } else {
+                    print!("{diff}");
+                }
+            }
+            println!();
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_changed_lines() {
+        assert_eq!(unified_diff("a", "x\ny\n", "b", "x\ny\n"), "");
+        assert_eq!(
+            unified_diff("uutils", "a\nb\nc\nd\n", "gnu", "a\nB\nc\nd\ne\n"),
+            "--- uutils\n+++ gnu\n@@ -1,4 +1,5 @@\n a\n-b\n+B\n c\n d\n+e\n"
+        );
+        assert_eq!(
+            unified_diff("uutils", "a\nb\nc\n", "gnu", "b\nc\na\n"),
+            "--- uutils\n+++ gnu\n@@ -1,3 +1,3 @@\n-a\n b\n c\n+a\n"
+        );
+    }
+
+    #[test]
+    fn marks_missing_newlines() {
+        assert_eq!(
+            unified_diff("uutils", "a\nb", "gnu", "a\nb\n"),
+            "--- uutils\n+++ gnu\n@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n"
+        );
+        assert_eq!(
+            unified_diff("uutils", "", "gnu", "a"),
+            "--- uutils\n+++ gnu\n@@ -1,0 +1,1 @@\n+a\n\\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn diffs_binary_outputs_as_hexdumps() {
+        let diff = output_diff("uutils", b"\xff\x00", "gnu", b"\xff\x01");
+        assert!(diff.contains("-00000000  ff 00"), "{diff}");
+        assert!(diff.contains("+00000000  ff 01"), "{diff}");
+    }
+}
@@ -0,0 +1,567 @@
+use std::{
+    fmt::Write,
+    fs,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+use libafl::Error;
+
+use super::{
+    divergence::Execution,
+    executor::{absolute_path, ExtractsToCommand},
+    exit_status::ExitStatus,
+    fixture::{Fixture, FixtureEntryKind, FIXTURE_EPOCH},
+    implementation::Implementation,
+};
+
+/// The file in a bucket holding a shell script running both implementations on its representative
+pub static SCRIPT_FILE: &str = "reproduce.sh";
+/// The file in a bucket holding a test in the style of uutils' `tests/by-util/test_<util>.rs`
+pub static UUTILS_TEST_FILE: &str = "uutils_test.rs";
+/// The file in a bucket holding a test in the style of GNU's `tests/<util>/`
+pub static GNU_TEST_FILE: &str = "gnu_test.sh";
+
+fn is_printable(byte: u8) -> bool {
+    byte.is_ascii_graphic() || byte == b' '
+}
+
+/// `bytes` as the single quoted format of a POSIX `printf`, which prints them unchanged
+fn printf_format(bytes: &[u8]) -> String {
+    let mut format = String::from("'");
+    for &byte in bytes {
+        match byte {
+            b'\\' => format.push_str("\\\\"),
+            b'%' => format.push_str("%%"),
+            b'\'' => format.push_str("'\\''"),
+            b'\n' => format.push_str("\\n"),
+            b'\t' => format.push_str("\\t"),
+            _ if is_printable(byte) => format.push(byte as char),
+            _ => {
+                let _ = write!(format, "\\{byte:03o}");
+            }
+        }
+    }
+    format.push('\'');
+    format
+}
+
+/// `bytes` as a single shell word
+fn sh_word(bytes: &[u8]) -> String {
+    if bytes.iter().all(|&b| is_printable(b) || b == b'\n') {
+        return format!(
+            "'{}'",
+            String::from_utf8_lossy(bytes).replace('\'', "'\\''")
+        );
+    }
+    // a command substitution strips trailing newlines, so they are appended quoted
+    let content = bytes.len() - bytes.iter().rev().take_while(|&&b| b == b'\n').count();
+    let mut word = format!("\"$(printf {})\"", printf_format(&bytes[..content]));
+    if content < bytes.len() {
+        let _ = write!(word, "'{}'", "\n".repeat(bytes.len() - content));
+    }
+    word
+}
+
+/// `path` in the fixture as a shell word, never starting with a dash
+fn sh_path(path: &[u8]) -> String {
+    sh_word(&[b"./", path].concat())
+}
+
+/// The commands recreating `fixture` in the current directory, like [`Fixture::build`]
+fn sh_fixture(fixture: &Fixture) -> Vec<String> {
+    let mut entries = fixture.entries.iter().collect::<Vec<_>>();
+    entries.sort_by_key(|e| e.path.iter().filter(|&&b| b == b'/').count());
+
+    let mut commands = Vec::new();
+    for entry in &entries {
+        let path = sh_path(&entry.path);
+        if let Some(parent) = entry.path.iter().rposition(|&b| b == b'/') {
+            commands.push(format!("mkdir -p {}", sh_path(&entry.path[..parent])));
+        }
+        match &entry.kind {
+            FixtureEntryKind::File { content, sparse } => {
+                commands.push(format!("printf {} > {path}", printf_format(content)));
+                if *sparse > 0 {
+                    commands.push(format!(
+                        "truncate -s {} {path}",
+                        content.len() as u64 + sparse
+                    ));
+                }
+            }
+            FixtureEntryKind::Directory => commands.push(format!("mkdir -p {path}")),
+            FixtureEntryKind::Symlink { target } => {
+                // the target is kept verbatim, so it may start with a dash
+                commands.push(format!("ln -s -- {} {path}", sh_word(target)))
+            }
+            FixtureEntryKind::Fifo => commands.push(format!("mkfifo {path}")),
+        }
+    }
+    // deepest first, so restrictive directory modes do not prevent updating their children
+    for entry in entries.iter().rev() {
+        let path = sh_path(&entry.path);
+        commands.push(format!("touch -h -d @{} {path}", entry.mtime));
+        if !matches!(entry.kind, FixtureEntryKind::Symlink { .. }) {
+            commands.push(format!("chmod {:o} {path}", entry.mode & 0o7777));
+        }
+    }
+    commands.push(format!("touch -d @{FIXTURE_EPOCH} . .."));
+    commands
+}
+
+/// `bytes` as a Rust string literal if they are UTF-8, else as a byte string literal
+fn rust_literal(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => format!("{s:?}"),
+        Err(_) => format!(
+            "b\"{}\"",
+            bytes
+                .iter()
+                .flat_map(|&b| std::ascii::escape_default(b))
+                .map(char::from)
+                .collect::<String>()
+        ),
+    }
+}
+
+/// `bytes` as a Rust expression of a type implementing `AsRef<OsStr>`
+fn rust_os_str(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => format!("{s:?}"),
+        Err(_) => format!("OsStr::from_bytes({})", rust_literal(bytes)),
+    }
+}
+
+/// The statements recreating `fixture` in the test directory `at`, like [`Fixture::build`]
+fn rust_fixture(fixture: &Fixture) -> Vec<String> {
+    let mut entries = fixture.entries.iter().collect::<Vec<_>>();
+    entries.sort_by_key(|e| e.path.iter().filter(|&&b| b == b'/').count());
+
+    let mut statements = Vec::new();
+    for entry in &entries {
+        let path = format!("at.plus({})", rust_os_str(&entry.path));
+        if entry.path.contains(&b'/') {
+            statements.push(format!(
+                "fs::create_dir_all({path}.parent().unwrap()).unwrap();"
+            ));
+        }
+        match &entry.kind {
+            FixtureEntryKind::File { content, sparse } => {
+                statements.push(format!(
+                    "fs::write({path}, {}).unwrap();",
+                    rust_literal(content)
+                ));
+                if *sparse > 0 {
+                    statements.push(format!(
+                        "fs::File::options().write(true).open({path}).unwrap().set_len({}).unwrap();",
+                        content.len() as u64 + sparse
+                    ));
+                }
+            }
+            FixtureEntryKind::Directory => {
+                statements.push(format!("fs::create_dir_all({path}).unwrap();"))
+            }
+            FixtureEntryKind::Symlink { target } => statements.push(format!(
+                "std::os::unix::fs::symlink({}, {path}).unwrap();",
+                rust_os_str(target)
+            )),
+            FixtureEntryKind::Fifo => statements.push(format!(
+                "at.mkfifo({:?});",
+                String::from_utf8_lossy(&entry.path)
+            )),
+        }
+    }
+    for entry in entries.iter().rev() {
+        let path = format!("at.plus({})", rust_os_str(&entry.path));
+        statements.push(format!(
+            "filetime::set_symlink_file_times({path}, FileTime::from_unix_time({0}, 0), FileTime::from_unix_time({0}, 0)).unwrap();",
+            entry.mtime
+        ));
+        if !matches!(entry.kind, FixtureEntryKind::Symlink { .. }) {
+            statements.push(format!(
+                "fs::set_permissions({path}, fs::Permissions::from_mode(0o{:o})).unwrap();",
+                entry.mode & 0o7777
+            ));
+        }
+    }
+    statements
+}
+
+fn describe(status: &Option<ExitStatus>) -> String {
+    status.map_or("no exit status".to_string(), |status| status.to_string())
+}
+
+/// The name of the shell variable holding the binary of the implementation called `name`
+fn sh_variable(name: &str) -> String {
+    let variable = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if variable.starts_with(|c: char| !c.is_ascii_alphabetic()) {
+        format!("IMPL_{variable}")
+    } else {
+        variable
+    }
+}
+
+/// One of the two implementations compared in a divergence
+struct Compared<'a> {
+    name: &'a str,
+    binary: &'a Path,
+    execution: &'a Execution,
+}
+
+/// Writes reproducers of divergences for filing them upstream: a shell script running both implementations compared,
+/// a test in the style of uutils and a test fragment in the style of GNU, both expecting the behaviour of the
+/// reference implementation.
+#[derive(Clone, Debug)]
+pub struct ReproducerExporter {
+    util: String,
+    /// the names and binaries of all implementations
+    implementations: Vec<(String, PathBuf)>,
+    /// the index of the implementation whose behaviour the tests expect, if it is one of those compared
+    reference: Option<usize>,
+}
+
+impl ReproducerExporter {
+    /// `reference` is the index of the implementation in `implementations` whose behaviour the tests expect. If it
+    /// is not given or not compared in a divergence, the behaviour of the first implementation compared is expected,
+    /// like the majority's in a [`MajorityClassifier`](super::majority::MajorityClassifier).
+    pub fn new(util: &str, implementations: &[&Implementation], reference: Option<usize>) -> Self {
+        let util = Path::new(util)
+            .file_name()
+            .map_or(util.into(), |name| name.to_string_lossy())
+            .into_owned();
+        Self {
+            util,
+            implementations: implementations
+                .iter()
+                .map(|implementation| {
+                    (
+                        implementation.name.clone(),
+                        absolute_path(&implementation.binary),
+                    )
+                })
+                .collect(),
+            reference,
+        }
+    }
+
+    /// Writes the reproducers of `input` to the bucket `name` in `dir`, given the indices of the implementations
+    /// compared and what they did, see [`Classifier::compared`](super::divergence::Classifier::compared).
+    pub fn export<I: ExtractsToCommand>(
+        &self,
+        dir: &Path,
+        name: &str,
+        input: &I,
+        executions: &[(usize, Execution); 2],
+    ) -> Result<(), Error> {
+        let [(index1, execution1), (index2, execution2)] = executions;
+        let compared = [
+            self.compared(*index1, execution1)?,
+            self.compared(*index2, execution2)?,
+        ];
+        let reference = self
+            .reference
+            .and_then(|reference| [index1, index2].iter().position(|&&i| i == reference))
+            .unwrap_or(0);
+
+        let bucket = dir.join(name);
+        for (file, content) in [
+            (SCRIPT_FILE, self.script(name, input, &compared)),
+            (
+                UUTILS_TEST_FILE,
+                self.uutils_test(name, input, &compared, reference),
+            ),
+            (
+                GNU_TEST_FILE,
+                self.gnu_test(name, input, &compared, reference),
+            ),
+        ] {
+            fs::write(bucket.join(file), content)
+                .map_err(|e| Error::os_error(e, format!("Could not write reproducer of {name}")))?;
+        }
+        Ok(())
+    }
+
+    fn compared<'a>(
+        &'a self,
+        index: usize,
+        execution: &'a Execution,
+    ) -> Result<Compared<'a>, Error> {
+        let (name, binary) = self.implementations.get(index).ok_or_else(|| {
+            Error::illegal_argument(format!(
+                "ReproducerExporter: unknown implementation {index}"
+            ))
+        })?;
+        Ok(Compared {
+            name,
+            binary,
+            execution,
+        })
+    }
+
+    fn sh_command<I: ExtractsToCommand>(&self, binary: &str, input: &I) -> String {
+        let mut command = binary.to_string();
+        for arg in input.get_args() {
+            command.push(' ');
+            command.push_str(&sh_word(arg.as_bytes()));
+        }
+        command
+    }
+
+    fn script<I: ExtractsToCommand>(
+        &self,
+        name: &str,
+        input: &I,
+        compared: &[Compared; 2],
+    ) -> String {
+        let [implementation1, implementation2] = compared;
+        let (name1, name2) = (implementation1.name, implementation2.name);
+        let mut variables = [sh_variable(name1), sh_variable(name2)];
+        if variables[0] == variables[1] {
+            variables = variables.map(|variable| variable + "_");
+            variables[1].push('2');
+        }
+        let [variable1, variable2] = &variables;
+        let mut script = format!(
+            "#!/bin/sh\n\
+             # Runs {util} of {name1} and {name2} on an input diverging in {name}.\n\
+             # Set {variable1} and {variable2} to the binaries to compare.\n\
+             {variable1}=${{{variable1}:-{binary1}}}\n\
+             {variable2}=${{{variable2}:-{binary2}}}\n\n\
+             run() {{\n",
+            util = self.util,
+            binary1 = sh_word(implementation1.binary.as_os_str().as_bytes()),
+            binary2 = sh_word(implementation2.binary.as_os_str().as_bytes()),
+        );
+        let fixture = input.get_fixture();
+        if let Some(fixture) = fixture {
+            script.push_str(
+                "    dir=$(mktemp -d) && mkdir \"$dir/root\" && cd \"$dir/root\" || exit 1\n",
+            );
+            for command in sh_fixture(fixture) {
+                let _ = writeln!(script, "    {command}");
+            }
+        }
+        let command = self.sh_command("\"$1\"", input);
+        if input.get_stdin().is_empty() {
+            let _ = writeln!(script, "    {command} < /dev/null");
+        } else {
+            let _ = writeln!(
+                script,
+                "    printf {} | {command}",
+                printf_format(input.get_stdin())
+            );
+        }
+        script.push_str("    echo \"exit status: $?\"\n");
+        if fixture.is_some() {
+            script.push_str("    cd / && chmod -R u+rwx \"$dir\" && rm -rf \"$dir\"\n");
+        }
+        script.push_str("}\n");
+        for (implementation, variable) in compared.iter().zip(&variables) {
+            let _ = write!(
+                script,
+                "\necho {}\n(run \"${variable}\")",
+                sh_word(format!("== {}", implementation.name).as_bytes())
+            );
+        }
+        script.push('\n');
+        script
+    }
+
+    fn uutils_test<I: ExtractsToCommand>(
+        &self,
+        name: &str,
+        input: &I,
+        compared: &[Compared; 2],
+        reference: usize,
+    ) -> String {
+        let reference_name = compared[reference].name;
+        let reference = compared[reference].execution;
+        let test_name = name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>();
+        let mut test =
+            format!("// Diverges in {name}, expecting the behaviour of {reference_name}.\n");
+        for implementation in compared {
+            let _ = writeln!(
+                test,
+                "// {}: {}",
+                implementation.name,
+                describe(&implementation.execution.exit_status)
+            );
+            for line in String::from_utf8_lossy(&implementation.execution.stderr).lines() {
+                let _ = writeln!(test, "//   {line}");
+            }
+        }
+        let _ = writeln!(test, "#[test]\nfn test_{test_name}() {{");
+
+        let mut body = String::new();
+        let mut command = match input.get_fixture() {
+            Some(fixture) => {
+                test.push_str("    use filetime::FileTime;\n");
+                test.push_str("    use std::{fs, os::unix::fs::PermissionsExt};\n");
+                body.push_str("    let (at, mut ucmd) = at_and_ucmd!();\n");
+                for statement in rust_fixture(fixture) {
+                    let _ = writeln!(body, "    {statement}");
+                }
+                String::from("    ucmd")
+            }
+            None => String::from("    new_ucmd!()"),
+        };
+        for arg in &input.get_args() {
+            let _ = write!(command, "\n        .arg({})", rust_os_str(arg.as_bytes()));
+        }
+        if !input.get_stdin().is_empty() {
+            let _ = write!(
+                command,
+                "\n        .pipe_in({})",
+                rust_literal(input.get_stdin())
+            );
+        }
+        match reference.exit_status {
+            Some(ExitStatus::Code(0)) => command.push_str("\n        .succeeds()"),
+            Some(ExitStatus::Code(code)) => {
+                let _ = write!(command, "\n        .fails()\n        .code_is({code})");
+            }
+            _ => command.push_str("\n        .run()"),
+        }
+        match std::str::from_utf8(&reference.stdout) {
+            Ok(stdout) => {
+                let _ = write!(command, "\n        .stdout_is({stdout:?})");
+            }
+            Err(_) => {
+                let _ = write!(
+                    command,
+                    "\n        .stdout_is_bytes({})",
+                    rust_literal(&reference.stdout)
+                );
+            }
+        }
+        let _ = writeln!(body, "{command};\n}}");
+
+        // paths and arguments which are not UTF-8 are passed as `OsStr`s
+        if body.contains("OsStr::from_bytes") {
+            test.push_str("    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};\n");
+        }
+        if input.get_fixture().is_some() {
+            test.push('\n');
+        }
+        test.push_str(&body);
+        test
+    }
+
+    fn gnu_test<I: ExtractsToCommand>(
+        &self,
+        name: &str,
+        input: &I,
+        compared: &[Compared; 2],
+        reference: usize,
+    ) -> String {
+        let util = &self.util;
+        let reference_name = compared[reference].name;
+        let other_name = compared[1 - reference].name;
+        let reference = compared[reference].execution;
+        let mut test = format!(
+            "#!/bin/sh\n\
+             # Diverges between {reference_name} and {other_name} in {name}, expecting the behaviour of \
+             {reference_name}.\n\n\
+             . \"${{srcdir=.}}/tests/init.sh\"; path_prepend_ ./src\n\
+             print_ver_ {util}\n\n"
+        );
+        if let Some(fixture) = input.get_fixture() {
+            test.push_str("mkdir fixture && cd fixture || framework_failure_\n");
+            for command in sh_fixture(fixture) {
+                let _ = writeln!(test, "{command} || framework_failure_");
+            }
+        }
+        // the files of the test are next to the fixture, in which the command is run
+        let files = if input.get_fixture().is_some() {
+            "../"
+        } else {
+            ""
+        };
+        let _ = writeln!(
+            test,
+            "printf {} > {files}in || framework_failure_",
+            printf_format(input.get_stdin())
+        );
+        let _ = writeln!(
+            test,
+            "printf {} > {files}exp || framework_failure_\n",
+            printf_format(&reference.stdout)
+        );
+
+        let command = format!("{} < {files}in > {files}out", self.sh_command(util, input));
+        match reference.exit_status {
+            Some(ExitStatus::Code(0)) => {
+                let _ = writeln!(test, "{command} || fail=1");
+            }
+            Some(ExitStatus::Code(code)) => {
+                let _ = writeln!(test, "returns_ {code} {command} || fail=1");
+            }
+            status => {
+                let _ = writeln!(
+                    test,
+                    "# {reference_name} terminated with {}",
+                    describe(&status)
+                );
+                let _ = writeln!(test, "{command}");
+            }
+        }
+        let _ = writeln!(
+            test,
+            "compare {files}exp {files}out || fail=1\n\nExit $fail"
+        );
+        test
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+
+    const TRICKY: &[u8] = b"it's 100%\\n\n\t\x01\x1b\xff\"$HOME`x`";
+
+    fn sh(script: &str) -> Vec<u8> {
+        let output = Command::new("sh").arg("-c").arg(script).output().unwrap();
+        assert!(output.status.success(), "{script}");
+        output.stdout
+    }
+
+    #[test]
+    fn escapes_printf_formats() {
+        assert_eq!(printf_format(b"a b"), "'a b'");
+        assert_eq!(printf_format(b"%s\\"), "'%%s\\\\'");
+        assert_eq!(printf_format(b"it's"), "'it'\\''s'");
+        assert_eq!(printf_format(b"\n\t\x00\xff"), "'\\n\\t\\000\\377'");
+        assert_eq!(sh(&format!("printf {}", printf_format(TRICKY))), TRICKY);
+    }
+
+    #[test]
+    fn quotes_shell_words() {
+        assert_eq!(sh_word(b"a b\n"), "'a b\n'");
+        assert_eq!(sh_path(b"-rf"), "'./-rf'");
+        for word in [TRICKY, b"\xff\n\n", b"", b"\n"] {
+            let script = format!("printf %s {}", sh_word(word));
+            assert_eq!(sh(&script), word, "{script}");
+        }
+    }
+
+    #[test]
+    fn names_shell_variables() {
+        assert_eq!(sh_variable("uutils"), "UUTILS");
+        assert_eq!(sh_variable("gnu-9.4"), "GNU_9_4");
+        assert_eq!(sh_variable("9front"), "IMPL_9FRONT");
+    }
+}
@@ -0,0 +1,361 @@
+use std::{borrow::Cow, collections::BTreeMap, sync::OnceLock};
+
+use libafl::{
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    observers::{ObserversTuple, StdErrObserver},
+    state::State,
+    Error, HasMetadata, SerdeAny,
+};
+use libafl_bolts::{
+    tuples::{Handle, Handled},
+    Named,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    divergence::{observed, sanitised_name},
+    majority::NWayObserver,
+};
+
+/// Set for all implementations unless overridden in their `env`: reports are parsed from `stderr`, so the process
+/// exits with an error after an ASan report instead of aborting, and UBSan continues after the first error. Leaks are not
+/// reported, since coreutils rely on the process exit to free their memory.
+pub static SANITIZER_ENV: [(&str, &str); 2] = [
+    (
+        "ASAN_OPTIONS",
+        "detect_leaks=0:abort_on_error=0:symbolize=1:verify_asan_link_order=0",
+    ),
+    (
+        "UBSAN_OPTIONS",
+        "print_stacktrace=1:halt_on_error=0:symbolize=1",
+    ),
+];
+/// The number of frames of the first stack trace kept in a [`SanitizerReport`]
+const TOP_FRAMES: usize = 3;
+
+/// An error reported by AddressSanitizer, UndefinedBehaviorSanitizer or LeakSanitizer on `stderr`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SanitizerReport {
+    /// e.g. `AddressSanitizer`
+    pub sanitizer: String,
+    /// e.g. `heap-buffer-overflow`, or for UBSan the message with numbers replaced, like `signed integer overflow: N +
+    /// N cannot be represented in type 'int'`
+    pub error_type: String,
+    /// `<file>:<line>` the error was reported at, if known
+    pub location: Option<String>,
+    /// the top frames of the first stack trace, as `<function> <file>:<line>:<column>`
+    pub frames: Vec<String>,
+}
+
+fn asan_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"(?m)^==\d+==ERROR: (\w+Sanitizer): ([\w-]+)").expect("valid regex")
+    })
+}
+
+fn ubsan_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"(?m)^(\S+:\d+):\d+: runtime error: (.*)$").expect("valid regex")
+    })
+}
+
+fn frame_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"^\s*#\d+ 0x[0-9a-f]+ in (\S+)(?: (\S+))?").expect("valid regex")
+    })
+}
+
+fn numbers_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"0x[0-9a-f]+|\d+").expect("valid regex"))
+}
+
+/// Whether the frame is within the sanitizer runtime, like an interceptor of `memcpy`
+fn is_runtime_frame(frame: &str) -> bool {
+    frame.starts_with("__asan")
+        || frame.starts_with("__sanitizer")
+        || frame.contains("__interceptor_")
+}
+
+impl SanitizerReport {
+    /// Parses the first sanitizer report in `stderr`, if there is one.
+    pub fn parse(stderr: &[u8]) -> Option<Self> {
+        let stderr = String::from_utf8_lossy(stderr);
+        let (sanitizer, error_type, location, start) =
+            if let Some(captures) = asan_regex().captures(&stderr) {
+                (
+                    captures[1].to_string(),
+                    captures[2].to_string(),
+                    None,
+                    captures.get(0)?.end(),
+                )
+            } else {
+                let captures = ubsan_regex().captures(&stderr)?;
+                (
+                    "UndefinedBehaviorSanitizer".to_string(),
+                    numbers_regex().replace_all(&captures[2], "N").into_owned(),
+                    Some(captures[1].to_string()),
+                    captures.get(0)?.end(),
+                )
+            };
+
+        let frames = stderr[start..]
+            .lines()
+            .skip_while(|line| !frame_regex().is_match(line))
+            .map_while(|line| frame_regex().captures(line))
+            .take(TOP_FRAMES)
+            .map(|captures| match captures.get(2) {
+                Some(file) => format!("{} {}", &captures[1], file.as_str()),
+                None => captures[1].to_string(),
+            })
+            .collect::<Vec<_>>();
+        // ASan reports the location in the first frame outside of its runtime
+        let location = location.or_else(|| {
+            frames
+                .iter()
+                .find(|frame| !is_runtime_frame(frame))
+                .and_then(|frame| {
+                    let file = frame.split_once(' ')?.1;
+                    let (location, _column) = file.rsplit_once(':')?;
+                    Some(location.to_string())
+                })
+        });
+
+        Some(Self {
+            sanitizer,
+            error_type,
+            location,
+            frames,
+        })
+    }
+
+    /// A readable name identifying the report by its error type and location, usable as part of a directory name
+    pub fn bucket_name(&self) -> String {
+        let error_type = match self.sanitizer.as_str() {
+            // the messages of UBSan are long, its location identifies it well enough
+            "UndefinedBehaviorSanitizer" => "ubsan".to_string(),
+            _ => self.error_type.clone(),
+        };
+        sanitised_name(&format!(
+            "{error_type}-{}",
+            self.location.as_deref().unwrap_or("unknown")
+        ))
+    }
+}
+
+/// The sanitizer reports found in an objective, see [`SanitizerFeedback`]
+#[derive(Serialize, Deserialize, Clone, Debug, SerdeAny)]
+pub struct SanitizerMetadata {
+    /// the name of the implementation and what its sanitizers reported
+    pub reports: Vec<(String, SanitizerReport)>,
+}
+
+/// How often each sanitizer bucket was hit by a [`SanitizerFeedback`] acting as crash objective, kept in the state
+#[derive(Serialize, Deserialize, Clone, Debug, Default, SerdeAny)]
+pub struct SanitizerBucketsMetadata {
+    /// the hits by implementation and [`SanitizerReport::bucket_name`]
+    pub hits: BTreeMap<String, u64>,
+}
+
+/// Interesting if the sanitizers of an implementation reported an error, see [`SanitizerReport::parse`], regardless
+/// of how the implementation exited. With [`SANITIZER_ENV`], the process does not crash after a report, so it would
+/// otherwise go unnoticed unless the other implementation behaves differently.
+///
+/// As crash objective, created with [`SanitizerFeedback::crash_objective`], only the first input of each bucket is
+/// interesting, i.e. per implementation, error type and location. Further hits are counted in the
+/// [`SanitizerBucketsMetadata`] of the state. Without, divergences are bucketed by their signature instead.
+pub struct SanitizerFeedback {
+    implementations: Vec<(String, Handle<StdErrObserver>)>,
+    /// the outcomes of the implementations run by an [`NWayExecutor`](super::majority::NWayExecutor), checked in
+    /// addition to `implementations`
+    outcome_observer: Option<Handle<NWayObserver>>,
+    crash_objective: bool,
+    reports: Vec<(String, SanitizerReport)>,
+}
+
+impl SanitizerFeedback {
+    /// `implementations` are the names of the implementations and their `stderr` observers
+    pub fn new(implementations: &[(&str, &StdErrObserver)]) -> Self {
+        Self {
+            implementations: implementations
+                .iter()
+                .map(|(name, stderr)| (name.to_string(), stderr.handle()))
+                .collect(),
+            outcome_observer: None,
+            crash_objective: false,
+            reports: Vec::new(),
+        }
+    }
+
+    /// Like [`SanitizerFeedback::new`], but for the implementations whose outcomes `outcome_observer` holds
+    pub fn n_way(outcome_observer: &NWayObserver) -> Self {
+        Self {
+            outcome_observer: Some(outcome_observer.handle()),
+            ..Self::new(&[])
+        }
+    }
+
+    /// Like [`SanitizerFeedback::new`], but only reporting the first input of each bucket
+    pub fn crash_objective(implementations: &[(&str, &StdErrObserver)]) -> Self {
+        Self {
+            crash_objective: true,
+            ..Self::new(implementations)
+        }
+    }
+}
+
+impl<S> Feedback<S> for SanitizerFeedback
+where
+    S: State + HasMetadata,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        self.reports.clear();
+        if let Some(outcome_observer) = &self.outcome_observer {
+            let observer = observed("SanitizerFeedback", outcome_observer, observers)?;
+            for (name, outcome) in observer.names.iter().zip(&observer.outcomes) {
+                if let Some(report) = SanitizerReport::parse(&outcome.execution.stderr) {
+                    self.reports.push((name.clone(), report));
+                }
+            }
+        }
+        for (name, stderr_observer) in &self.implementations {
+            let stderr = observed("SanitizerFeedback", stderr_observer, observers)?
+                .stderr
+                .as_deref()
+                .unwrap_or_default();
+            if let Some(report) = SanitizerReport::parse(stderr) {
+                self.reports.push((name.clone(), report));
+            }
+        }
+        if !self.crash_objective || self.reports.is_empty() {
+            return Ok(!self.reports.is_empty());
+        }
+
+        let buckets = state.metadata_or_insert_with(SanitizerBucketsMetadata::default);
+        let mut new_bucket = false;
+        for (name, report) in &self.reports {
+            let hits = buckets
+                .hits
+                .entry(format!("{name}_{}", report.bucket_name()))
+                .or_default();
+            new_bucket |= *hits == 0;
+            *hits += 1;
+        }
+        Ok(new_bucket)
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _observers: &OT,
+        testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        if !self.reports.is_empty() {
+            testcase.metadata_map_mut().insert(SanitizerMetadata {
+                reports: std::mem::take(&mut self.reports),
+            });
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.reports.clear();
+        Ok(())
+    }
+}
+
+impl Named for SanitizerFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("SanitizerFeedback")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_asan_reports() {
+        let stderr = b"some output\n\
+            =================================================================\n\
+            ==4242==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000011 at pc 0x4c3a2b\n\
+            READ of size 1 at 0x602000000011 thread T0\n\
+            \x20   #0 0x4c3a2b in __interceptor_memcpy (/usr/bin/ls+0x4c3a2b)\n\
+            \x20   #1 0x55d1e0 in quote_name /src/coreutils/src/ls.c:4711:7\n\
+            \x20   #2 0x55d2f0 in print_name_with_quoting /src/coreutils/src/ls.c:4790:3\n\
+            \x20   #3 0x55d3a0 in main /src/coreutils/src/ls.c:1700:5\n\
+            \n\
+            \x20   #0 0x4a0000 in malloc\n";
+        let report = SanitizerReport::parse(stderr).unwrap();
+        assert_eq!(report.sanitizer, "AddressSanitizer");
+        assert_eq!(report.error_type, "heap-buffer-overflow");
+        assert_eq!(
+            report.location.as_deref(),
+            Some("/src/coreutils/src/ls.c:4711")
+        );
+        assert_eq!(
+            report.frames,
+            [
+                "__interceptor_memcpy (/usr/bin/ls+0x4c3a2b)",
+                "quote_name /src/coreutils/src/ls.c:4711:7",
+                "print_name_with_quoting /src/coreutils/src/ls.c:4790:3",
+            ]
+        );
+        assert_eq!(
+            report.bucket_name(),
+            "heap-buffer-overflow--src-coreutils-src-ls-c-4711"
+        );
+    }
+
+    #[test]
+    fn parses_ubsan_reports() {
+        let stderr = b"src/du.c:123:17: runtime error: signed integer overflow: 9223372036854775807 + 1 cannot be \
+            represented in type 'long int'\n\
+            \x20   #0 0x55d1e0 in main src/du.c:123:17\n";
+        let report = SanitizerReport::parse(stderr).unwrap();
+        assert_eq!(report.sanitizer, "UndefinedBehaviorSanitizer");
+        assert_eq!(
+            report.error_type,
+            "signed integer overflow: N + N cannot be represented in type 'long int'"
+        );
+        assert_eq!(report.location.as_deref(), Some("src/du.c:123"));
+        assert_eq!(report.frames, ["main src/du.c:123:17"]);
+        assert_eq!(report.bucket_name(), "ubsan-src-du-c-123");
+    }
+
+    #[test]
+    fn ignores_other_output() {
+        assert_eq!(SanitizerReport::parse(b""), None);
+        assert_eq!(
+            SanitizerReport::parse(b"ls: cannot access 'x': runtime error: no such file\n"),
+            None
+        );
+        let report =
+            SanitizerReport::parse(b"==1==ERROR: LeakSanitizer: detected memory leaks\n").unwrap();
+        assert_eq!(report.location, None);
+        assert_eq!(report.bucket_name(), "detected-unknown");
+    }
+}
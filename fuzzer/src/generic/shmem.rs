@@ -24,7 +24,9 @@ pub fn get_coverage_shmem_size(util: String) -> Result<(usize, String), Error> {
         .parse::<usize>()?;
 
     match guard_num {
-        0 => Err(Error::illegal_state("Binary reported a guard count of 0")),
+        0 => Err(Error::illegal_state(
+            "Binary reported a guard count of 0, set the coverage of uninstrumented implementations to \"novelty\"",
+        )),
         e => Ok((e, util)),
     }
 }
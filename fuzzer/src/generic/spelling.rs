@@ -0,0 +1,337 @@
+use std::borrow::Cow;
+
+use libafl::{
+    mutators::{MutationResult, Mutator},
+    state::HasRand,
+    Error,
+};
+use libafl_bolts::{
+    prelude::Rand,
+    rands::StdRand,
+    Named,
+};
+
+/// Long options every utility accepts, which need to be considered when abbreviating.
+pub const COMMON_LONG_OPTIONS: [&str; 2] = ["--help", "--version"];
+
+/// An option as passed to [`render_args`]
+pub struct OptionArg<'a> {
+    /// the short spelling, like `-w`
+    pub short: Option<&'a str>,
+    /// the long spelling, like `--wrap`
+    pub long: Option<&'a str>,
+    /// the length of the shortest unambiguous abbreviation of `long`, see [`unique_prefix_len`]
+    pub unique_prefix: usize,
+    pub value: Option<&'a [u8]>,
+}
+
+/// The length of the shortest prefix of `long` that no other option in `all` starts with.
+pub fn unique_prefix_len(long: &str, all: &[&str]) -> usize {
+    (3..long.len())
+        .find(|&len| {
+            !all
+                .iter()
+                .chain(COMMON_LONG_OPTIONS.iter())
+                .any(|other| *other != long && other.starts_with(&long[..len]))
+        })
+        .unwrap_or(long.len())
+}
+
+/// Renders options and operands as arguments. A `spelling` of `0` renders the canonical command line:
+/// - options in their short spelling if they have one, with the value as a separate argument, else in their long
+///   spelling with the value joined by `=`
+/// - operands after all options
+///
+/// Any other `spelling` seeds the choice between equivalent renderings: short or long spelling, abbreviated long
+/// options, clustered short options, values attached to short options or joined to long ones, operands between
+/// options or after `--`. The relative order of options and of operands is kept, since it may matter.
+pub fn render_args(options: &[OptionArg], operands: &[&[u8]], spelling: u64) -> Vec<Vec<u8>> {
+    if spelling == 0 {
+        let mut args = Vec::with_capacity(2 * options.len() + operands.len());
+        for option in options {
+            match (option.short, option.long, option.value) {
+                (Some(short), _, value) => {
+                    args.push(short.as_bytes().to_vec());
+                    args.extend(value.map(<[u8]>::to_vec));
+                }
+                (None, Some(long), Some(value)) => args.push(joined(long, value)),
+                (None, Some(long), None) => args.push(long.as_bytes().to_vec()),
+                (None, None, _) => {}
+            }
+        }
+        args.extend(operands.iter().map(|o| o.to_vec()));
+        return args;
+    }
+
+    let mut rand = StdRand::with_seed(spelling);
+    // the arguments of each option, where operands may be placed in between
+    let mut groups: Vec<Vec<Vec<u8>>> = Vec::with_capacity(options.len());
+    // whether the last group is a single argument of clustered short flags
+    let mut cluster_open = false;
+    for option in options {
+        let use_short = match (option.short, option.long) {
+            (Some(_), Some(_)) => rand.coinflip(0.5),
+            (Some(_), None) => true,
+            _ => false,
+        };
+        if let (true, Some(short)) = (use_short, option.short) {
+            let letter = single_letter(short);
+            let merge = letter.is_some() && cluster_open && rand.coinflip(0.5);
+            let mut first = if merge {
+                groups.pop().expect("an open cluster exists").remove(0)
+            } else {
+                b"-".to_vec()
+            };
+            match letter {
+                Some(letter) => first.push(letter),
+                None => first = short.as_bytes().to_vec(),
+            }
+            match option.value {
+                None => {
+                    groups.push(vec![first]);
+                    cluster_open = letter.is_some();
+                }
+                Some(value) if letter.is_some() && !value.is_empty() && rand.coinflip(0.5) => {
+                    first.extend(value);
+                    groups.push(vec![first]);
+                    cluster_open = false;
+                }
+                Some(value) => {
+                    groups.push(vec![first, value.to_vec()]);
+                    cluster_open = false;
+                }
+            }
+        } else if let Some(long) = option.long {
+            let len = rand.between(option.unique_prefix.min(long.len()), long.len());
+            let name = &long[..len];
+            groups.push(match option.value {
+                None => vec![name.as_bytes().to_vec()],
+                Some(value) if rand.coinflip(0.5) => vec![joined(name, value)],
+                Some(value) => vec![name.as_bytes().to_vec(), value.to_vec()],
+            });
+            cluster_open = false;
+        }
+    }
+
+    let mut args = Vec::with_capacity(2 * options.len() + operands.len() + 1);
+    // operands looking like options are parsed as such, so they stay at the end
+    if operands.iter().any(|o| o.starts_with(b"-")) {
+        args.extend(groups.into_iter().flatten());
+        args.extend(operands.iter().map(|o| o.to_vec()));
+    } else if rand.coinflip(0.3) {
+        args.extend(groups.into_iter().flatten());
+        args.push(b"--".to_vec());
+        args.extend(operands.iter().map(|o| o.to_vec()));
+    } else {
+        let mut positions = operands
+            .iter()
+            .map(|_| rand.below(groups.len() + 1))
+            .collect::<Vec<_>>();
+        positions.sort_unstable();
+        let mut operands = positions.into_iter().zip(operands).peekable();
+        for (index, group) in groups.into_iter().enumerate() {
+            while let Some((_, operand)) = operands.next_if(|(p, _)| *p == index) {
+                args.push(operand.to_vec());
+            }
+            args.extend(group);
+        }
+        args.extend(operands.map(|(_, o)| o.to_vec()));
+    }
+    args
+}
+
+fn joined(name: &str, value: &[u8]) -> Vec<u8> {
+    let mut arg = name.as_bytes().to_vec();
+    arg.push(b'=');
+    arg.extend(value);
+    arg
+}
+
+/// The letter of a short option like `-w`, which allows clustering and attaching values.
+fn single_letter(short: &str) -> Option<u8> {
+    match short.as_bytes() {
+        [b'-', letter] if *letter != b'-' => Some(*letter),
+        _ => None,
+    }
+}
+
+/// Interface for inputs whose options can be written in multiple equivalent ways, see [`render_args`].
+pub trait HasArgSpelling: Clone {
+    /// The seed of the rendering of the input's options, or `None` if it is always rendered canonically.
+    fn spelling_mut(&mut self) -> Option<&mut u64> {
+        None
+    }
+
+    /// The input with its options in their canonical spelling, if it is not spelled canonically already.
+    fn canonically_spelled(&self) -> Option<Self> {
+        let mut canonical = self.clone();
+        let spelling = canonical.spelling_mut()?;
+        if *spelling == 0 {
+            return None;
+        }
+        *spelling = 0;
+        Some(canonical)
+    }
+}
+
+/// Chooses a different way to write an input's options, or returns to the canonical one.
+pub struct SpellingMutator;
+impl<I, S> Mutator<I, S> for SpellingMutator
+where
+    I: HasArgSpelling,
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut I) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+        let Some(spelling) = input.spelling_mut() else {
+            return Ok(MutationResult::Skipped);
+        };
+        *spelling = if rand.coinflip(0.1) { 0 } else { rand.next() };
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for SpellingMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("SpellingMutator")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LONGS: [&str; 4] = ["--decode", "--debug", "--wrap", "--format"];
+
+    fn options() -> Vec<OptionArg<'static>> {
+        let option = |short, long, value| OptionArg {
+            short,
+            long,
+            unique_prefix: long.map_or(0, |long| unique_prefix_len(long, &LONGS)),
+            value,
+        };
+        vec![
+            option(Some("-d"), Some("--decode"), None),
+            option(None, Some("--debug"), None),
+            option(Some("-w"), Some("--wrap"), Some(b"76".as_slice())),
+            option(Some("-i"), None, None),
+            option(None, Some("--format"), Some(b"x".as_slice())),
+        ]
+    }
+
+    /// The indices of the options with their values, and the operands
+    type Parsed = (Vec<(usize, Option<Vec<u8>>)>, Vec<Vec<u8>>);
+
+    /// Parses `args` like `getopt_long`.
+    fn parse(options: &[OptionArg], args: &[Vec<u8>]) -> Parsed {
+        let mut parsed = Vec::new();
+        let mut operands = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == b"--" {
+                operands.extend(args.by_ref().cloned());
+            } else if let Some(long) = arg.strip_prefix(b"--") {
+                let (name, value) = match long.iter().position(|&b| b == b'=') {
+                    Some(equals) => (&long[..equals], Some(long[equals + 1..].to_vec())),
+                    None => (long, None),
+                };
+                let matching = options
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, o)| o.long.is_some_and(|l| l.as_bytes()[2..].starts_with(name)))
+                    .map(|(index, _)| index)
+                    .collect::<Vec<_>>();
+                let [index] = matching[..] else {
+                    panic!(
+                        "ambiguous or unknown option {}",
+                        String::from_utf8_lossy(arg)
+                    );
+                };
+                let value = match options[index].value {
+                    Some(_) => value.or_else(|| args.next().cloned()),
+                    None => value,
+                };
+                parsed.push((index, value));
+            } else if arg.len() > 1 && arg[0] == b'-' {
+                for (offset, &letter) in arg.iter().enumerate().skip(1) {
+                    let index = options
+                        .iter()
+                        .position(|o| o.short.and_then(single_letter) == Some(letter))
+                        .expect("a known short option");
+                    if options[index].value.is_some() {
+                        let rest = &arg[offset + 1..];
+                        let value = if rest.is_empty() {
+                            args.next().cloned()
+                        } else {
+                            Some(rest.to_vec())
+                        };
+                        parsed.push((index, value));
+                        break;
+                    }
+                    parsed.push((index, None));
+                }
+            } else {
+                operands.push(arg.clone());
+            }
+        }
+        (parsed, operands)
+    }
+
+    #[test]
+    fn unique_prefix_considers_common_options() {
+        assert_eq!(unique_prefix_len("--decode", &LONGS), 5);
+        assert_eq!(unique_prefix_len("--wrap", &LONGS), 3);
+        assert_eq!(unique_prefix_len("--verbose", &["--verbose"]), 6);
+    }
+
+    #[test]
+    fn renders_canonically() {
+        let args = render_args(&options(), &[b"file", b"-"], 0);
+        let expected: [&[u8]; 8] = [
+            b"-d",
+            b"--debug",
+            b"-w",
+            b"76",
+            b"-i",
+            b"--format=x",
+            b"file",
+            b"-",
+        ];
+        assert_eq!(args, expected);
+    }
+
+    #[test]
+    fn spellings_are_equivalent() {
+        let options = options();
+        let expected = (
+            vec![
+                (0, None),
+                (1, None),
+                (2, Some(b"76".to_vec())),
+                (3, None),
+                (4, Some(b"x".to_vec())),
+            ],
+            vec![b"file".to_vec(), b"other".to_vec()],
+        );
+        let canonical = render_args(&options, &[b"file", b"other"], 0);
+        assert_eq!(parse(&options, &canonical), expected);
+        let mut spellings = std::collections::HashSet::new();
+        for spelling in 1..500 {
+            let args = render_args(&options, &[b"file", b"other"], spelling);
+            assert_eq!(args, render_args(&options, &[b"file", b"other"], spelling));
+            assert_eq!(parse(&options, &args), expected, "{spelling}: {args:?}");
+            spellings.insert(args);
+        }
+        assert!(spellings.len() > 100);
+    }
+
+    #[test]
+    fn keeps_dash_operands_last() {
+        let options = options();
+        for spelling in 1..100 {
+            let args = render_args(&options, &[b"-x"], spelling);
+            assert_eq!(args.last().map(Vec::as_slice), Some(b"-x".as_slice()));
+            assert!(!args.contains(&b"--".to_vec()));
+        }
+    }
+}
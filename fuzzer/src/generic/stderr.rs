@@ -0,0 +1,217 @@
+use std::{borrow::Cow, collections::HashMap, fs::File, path::Path, sync::Arc};
+
+use libafl::{
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    observers::{ObserversTuple, StdErrObserver},
+    state::State,
+    Error, HasMetadata, SerdeAny,
+};
+use libafl_bolts::{
+    tuples::{Handle, Handled, MatchNameRef},
+    Named,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Rules to normalise `stderr` before comparing implementations, as loaded from a JSON file like
+/// `stderr_rules.json`. All patterns are regular expressions matched against single lines, after the program name
+/// has been stripped.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct StderrRules {
+    /// lines that are dropped, like usage tips
+    #[serde(default)]
+    drop_lines: Vec<String>,
+    /// checked in order, the first class with a pattern matching any line is the class of the output
+    #[serde(default)]
+    classes: Vec<ClassRule>,
+    /// rules for single utilities, applied before the common ones
+    #[serde(default)]
+    utils: HashMap<String, StderrRules>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct ClassRule {
+    class: String,
+    patterns: Vec<String>,
+}
+
+/// The [`StderrRules`] for one utility, ready to be applied.
+#[derive(Debug)]
+pub struct StderrNormaliser {
+    /// the program name, possibly with a path, at the start of a line
+    program_prefix: Regex,
+    drop_lines: Vec<Regex>,
+    classes: Vec<(String, Vec<Regex>)>,
+}
+
+/// `stderr` after normalisation
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NormalisedStderr {
+    pub lines: Vec<String>,
+    /// the error class, if any rule matched
+    pub class: Option<String>,
+}
+
+impl StderrNormaliser {
+    /// Loads the rules at `path` and selects the ones applying to `util`.
+    pub fn load(path: &Path, util: &str) -> Result<Self, Error> {
+        let mut rules: StderrRules = serde_json::from_reader(
+            File::open(path).map_err(|e| Error::os_error(e, "Could not open stderr rules"))?,
+        )
+        .map_err(|e| Error::serialize(format!("Could not parse stderr rules: {e}")))?;
+        let util_rules = rules.utils.remove(util).unwrap_or_default();
+
+        let compile = |pattern: &String| {
+            Regex::new(pattern).map_err(|e| {
+                Error::illegal_argument(format!("Invalid stderr pattern '{pattern}': {e}"))
+            })
+        };
+        let util_name = Path::new(util)
+            .file_name()
+            .map_or(util.into(), |name| name.to_string_lossy());
+        Ok(Self {
+            program_prefix: compile(&format!(r"^(?:\S*/)?{}: ", regex::escape(&util_name)))?,
+            drop_lines: util_rules
+                .drop_lines
+                .iter()
+                .chain(&rules.drop_lines)
+                .map(compile)
+                .collect::<Result<_, _>>()?,
+            classes: util_rules
+                .classes
+                .iter()
+                .chain(&rules.classes)
+                .map(|rule| {
+                    Ok((
+                        rule.class.clone(),
+                        rule.patterns
+                            .iter()
+                            .map(compile)
+                            .collect::<Result<_, _>>()?,
+                    ))
+                })
+                .collect::<Result<_, Error>>()?,
+        })
+    }
+
+    pub fn normalise(&self, stderr: &[u8]) -> NormalisedStderr {
+        let lines = String::from_utf8_lossy(stderr)
+            .lines()
+            .map(|line| self.program_prefix.replace(line, "").into_owned())
+            .filter(|line| !self.drop_lines.iter().any(|r| r.is_match(line)))
+            .collect::<Vec<_>>();
+        let class = self
+            .classes
+            .iter()
+            .find(|(_, patterns)| {
+                lines
+                    .iter()
+                    .any(|line| patterns.iter().any(|r| r.is_match(line)))
+            })
+            .map(|(class, _)| class.clone());
+        NormalisedStderr { lines, class }
+    }
+}
+
+/// Interesting if both implementations write to `stderr` and their outputs fall into different error classes.
+/// Outputs no rule classifies are never reported, since their messages are not comparable.
+pub struct StderrClassDiffFeedback {
+    observer1: Handle<StdErrObserver>,
+    observer2: Handle<StdErrObserver>,
+    normaliser: Arc<StderrNormaliser>,
+    diff: Option<(NormalisedStderr, NormalisedStderr)>,
+}
+
+impl StderrClassDiffFeedback {
+    pub fn new(
+        observer1: &StdErrObserver,
+        observer2: &StdErrObserver,
+        normaliser: Arc<StderrNormaliser>,
+    ) -> Self {
+        Self {
+            observer1: observer1.handle(),
+            observer2: observer2.handle(),
+            normaliser,
+            diff: None,
+        }
+    }
+}
+
+impl<S> Feedback<S> for StderrClassDiffFeedback
+where
+    S: State,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let get = |handle: &Handle<StdErrObserver>| {
+            observers
+                .get(handle)
+                .ok_or_else(|| {
+                    Error::illegal_argument(format!(
+                        "StderrClassDiffFeedback: observer {} not found",
+                        handle.name()
+                    ))
+                })
+                .map(|o| o.stderr.as_deref().filter(|s| !s.is_empty()))
+        };
+        self.diff = match (get(&self.observer1)?, get(&self.observer2)?) {
+            (Some(stderr1), Some(stderr2)) => {
+                let (stderr1, stderr2) = (
+                    self.normaliser.normalise(stderr1),
+                    self.normaliser.normalise(stderr2),
+                );
+                match (&stderr1.class, &stderr2.class) {
+                    (Some(class1), Some(class2)) if class1 != class2 => Some((stderr1, stderr2)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        Ok(self.diff.is_some())
+    }
+
+    fn append_metadata<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _observers: &OT,
+        testcase: &mut libafl::prelude::Testcase<<S>::Input>,
+    ) -> Result<(), Error>
+    where
+        OT: ObserversTuple<S>,
+        EM: EventFirer<State = S>,
+    {
+        if let Some((stderr1, stderr2)) = self.diff.take() {
+            testcase
+                .metadata_map_mut()
+                .insert(StderrClassDiffMetadata { stderr1, stderr2 });
+        }
+        Ok(())
+    }
+}
+
+impl Named for StderrClassDiffFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("StderrClassDiffFeedback")
+    }
+}
+
+#[derive(Debug, SerdeAny, Serialize, Deserialize)]
+struct StderrClassDiffMetadata {
+    stderr1: NormalisedStderr,
+    stderr2: NormalisedStderr,
+}
@@ -1,4 +1,4 @@
-use std::{borrow::Cow, fmt::Display};
+use std::{borrow::Cow, ffi::OsStr, fmt::Display, os::unix::ffi::OsStrExt};
 
 use std::fmt::Write;
 
@@ -11,19 +11,21 @@ use libafl::{
     Error, HasMetadata, SerdeAny,
 };
 use libafl_bolts::{
-    tuples::{Handle, MatchNameRef},
+    tuples::{Handle, Handled, MatchNameRef},
     Named,
 };
 
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
-#[cfg(feature = "differential")]
-use libafl_bolts::tuples::Handled;
+use super::implementation::Implementation;
 
 #[derive(Clone)]
 pub struct DiffStdIOMetadataPseudoFeedback {
     name1: Cow<'static, str>,
     name2: Cow<'static, str>,
+    version1: Option<String>,
+    version2: Option<String>,
     stderr_observer1: Handle<StdErrObserver>,
     stderr_observer2: Handle<StdErrObserver>,
     stdout_observer1: Handle<StdOutObserver>,
@@ -32,18 +34,19 @@ pub struct DiffStdIOMetadataPseudoFeedback {
 }
 
 impl DiffStdIOMetadataPseudoFeedback {
-    #[cfg(feature = "differential")]
     pub fn new(
-        name1: &str,
-        name2: &str,
+        implementation1: &Implementation,
+        implementation2: &Implementation,
         stderr_observer1: &StdErrObserver,
         stderr_observer2: &StdErrObserver,
         stdout_observer1: &StdOutObserver,
         stdout_observer2: &StdOutObserver,
     ) -> Self {
         Self {
-            name1: Cow::Owned(name1.to_string()),
-            name2: Cow::Owned(name2.to_string()),
+            name1: Cow::Owned(implementation1.binary.clone()),
+            name2: Cow::Owned(implementation2.binary.clone()),
+            version1: implementation1.version.clone(),
+            version2: implementation2.version.clone(),
             stderr_observer1: stderr_observer1.handle(),
             stderr_observer2: stderr_observer2.handle(),
             stdout_observer1: stdout_observer1.handle(),
@@ -110,6 +113,8 @@ where
                 input,
                 name1: self.name1.to_string(),
                 name2: self.name2.to_string(),
+                version1: self.version1.clone(),
+                version2: self.version2.clone(),
                 stderr_observer1: vec_string_mapper(&f(&self.stderr_observer1, observers)?.stderr),
                 stderr_observer2: vec_string_mapper(&f(&self.stderr_observer2, observers)?.stderr),
                 stdout_observer1: vec_string_mapper(&f(&self.stdout_observer1, observers)?.stdout),
@@ -131,6 +136,9 @@ struct DiffStdIOMetadataPseudoFeedbackMetadata {
     input: Option<String>,
     name1: String,
     name2: String,
+    /// the versions of the builds producing the outputs below, to tell builds of the same implementation apart
+    version1: Option<String>,
+    version2: Option<String>,
     exit_kind: String,
     stderr_observer1: String,
     stderr_observer2: String,
@@ -156,3 +164,118 @@ pub fn vec_string_mapper(v: &Option<Vec<u8>>) -> String {
         })
         .unwrap_or("Did not observe anything".to_string())
 }
+
+pub fn args_string_mapper(args: &[Cow<OsStr>]) -> String {
+    let args = args
+        .iter()
+        .map(|a| format!("'{}'", vec_string_mapper(&Some(a.as_bytes().to_vec()))))
+        .collect::<Vec<_>>();
+    format!("[{}]", args.join(", "))
+}
+
+/// How the `stdout` of two implementations is compared, depending on whether they report an error on `stderr`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StdoutPolicy {
+    /// `stdout` needs to be equal, regardless of errors
+    Strict,
+    /// like `strict`, but if both report an error, one `stdout` only needs to be a prefix of the other, since
+    /// implementations may stop at different points, e.g. when decoding invalid input
+    PrefixTolerant,
+    /// `stdout` is only compared if neither reports an error
+    #[default]
+    IgnoreOnError,
+}
+
+impl StdoutPolicy {
+    pub fn equivalent(&self, stdout1: &[u8], error1: bool, stdout2: &[u8], error2: bool) -> bool {
+        match self {
+            StdoutPolicy::IgnoreOnError if error1 || error2 => true,
+            StdoutPolicy::PrefixTolerant if error1 && error2 => {
+                stdout1.starts_with(stdout2) || stdout2.starts_with(stdout1)
+            }
+            _ => stdout1 == stdout2,
+        }
+    }
+}
+
+/// Compares the `stdout` of two implementations according to a [`StdoutPolicy`]. An implementation reports an error if
+/// it writes to `stderr`.
+pub struct StdoutDiffFeedback {
+    stdout_observer1: Handle<StdOutObserver>,
+    stdout_observer2: Handle<StdOutObserver>,
+    stderr_observer1: Handle<StdErrObserver>,
+    stderr_observer2: Handle<StdErrObserver>,
+    policy: StdoutPolicy,
+}
+
+impl StdoutDiffFeedback {
+    pub fn new(
+        stdout_observer1: &StdOutObserver,
+        stdout_observer2: &StdOutObserver,
+        stderr_observer1: &StdErrObserver,
+        stderr_observer2: &StdErrObserver,
+        policy: StdoutPolicy,
+    ) -> Self {
+        Self {
+            stdout_observer1: stdout_observer1.handle(),
+            stdout_observer2: stdout_observer2.handle(),
+            stderr_observer1: stderr_observer1.handle(),
+            stderr_observer2: stderr_observer2.handle(),
+            policy,
+        }
+    }
+}
+
+impl<S> Feedback<S> for StdoutDiffFeedback
+where
+    S: State,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        fn f<'a, T, OT>(handle: &Handle<T>, observers: &'a OT) -> Result<&'a T, Error>
+        where
+            OT: MatchNameRef,
+        {
+            observers.get(handle).ok_or_else(|| {
+                Error::illegal_argument(format!(
+                    "StdoutDiffFeedback: observer {} not found",
+                    handle.name()
+                ))
+            })
+        }
+
+        let stdout1 = f(&self.stdout_observer1, observers)?.stdout.as_deref();
+        let stdout2 = f(&self.stdout_observer2, observers)?.stdout.as_deref();
+        let error1 = f(&self.stderr_observer1, observers)?
+            .stderr
+            .as_ref()
+            .is_some_and(|e| !e.is_empty());
+        let error2 = f(&self.stderr_observer2, observers)?
+            .stderr
+            .as_ref()
+            .is_some_and(|e| !e.is_empty());
+        Ok(!self.policy.equivalent(
+            stdout1.unwrap_or_default(),
+            error1,
+            stdout2.unwrap_or_default(),
+            error2,
+        ))
+    }
+}
+
+impl Named for StdoutDiffFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("StdoutDiffFeedback")
+    }
+}
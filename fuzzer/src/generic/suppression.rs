@@ -0,0 +1,219 @@
+use std::{
+    borrow::Cow, collections::BTreeSet, fs::File, marker::PhantomData, path::Path, sync::Arc,
+};
+
+use libafl::{
+    events::{Event, EventFirer},
+    executors::ExitKind,
+    feedbacks::Feedback,
+    monitors::{AggregatorOps, UserStats, UserStatsValue},
+    observers::ObserversTuple,
+    state::State,
+    Error, HasMetadata,
+};
+use libafl_bolts::Named;
+use regex::{bytes, Regex};
+use serde::Deserialize;
+
+use super::{
+    divergence::{Aspect, Classifier, DivergenceSignature},
+    executor::ExtractsToCommand,
+    exit_status::ExitStatus,
+    spelling::HasArgSpelling,
+};
+
+/// A known divergence, as loaded from a JSON file like `suppressions.example.json`. All given matchers need to match
+/// for a divergence to be suppressed.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct SuppressionEntry {
+    /// shown in the monitor next to the number of suppressed divergences
+    name: String,
+    /// the utility the entry applies to, all if not given
+    #[serde(default)]
+    util: Option<String>,
+    /// regular expression matched against the arguments in their canonical spelling, see
+    /// [`render_args`](super::spelling::render_args), joined by spaces
+    #[serde(default)]
+    args: Option<String>,
+    /// byte regular expression matched against stdin, e.g. `(?-u)\xff`
+    #[serde(default)]
+    stdin: Option<String>,
+    /// the aspects the implementations may differ in, the divergence needs to differ in a subset of them
+    #[serde(default)]
+    differs: Option<BTreeSet<Aspect>>,
+    /// the exit statuses of both implementations, like `exit 1` or `signal 11`, `null` matches any status
+    #[serde(default)]
+    exit_statuses: [Option<String>; 2],
+    /// the stderr classes of both implementations, `null` matches any class
+    #[serde(default)]
+    stderr_classes: [Option<String>; 2],
+}
+
+#[derive(Debug)]
+struct Suppression {
+    name: String,
+    args: Option<Regex>,
+    stdin: Option<bytes::Regex>,
+    differs: Option<BTreeSet<Aspect>>,
+    exit_statuses: [Option<ExitStatus>; 2],
+    stderr_classes: [Option<String>; 2],
+}
+
+impl Suppression {
+    fn matches(&self, args: &str, stdin: &[u8], signature: &DivergenceSignature) -> bool {
+        fn pair_matches<T: PartialEq>(expected: &[Option<T>; 2], actual: &[Option<T>; 2]) -> bool {
+            expected
+                .iter()
+                .zip(actual)
+                .all(|(expected, actual)| expected.is_none() || expected == actual)
+        }
+
+        self.args.as_ref().is_none_or(|r| r.is_match(args))
+            && self.stdin.as_ref().is_none_or(|r| r.is_match(stdin))
+            && self
+                .differs
+                .as_ref()
+                .is_none_or(|differs| signature.differs.is_subset(differs))
+            && pair_matches(&self.exit_statuses, &signature.exit_statuses)
+            && pair_matches(&self.stderr_classes, &signature.stderr_classes)
+    }
+}
+
+/// Known divergences which are not saved as objectives again.
+#[derive(Debug, Default)]
+pub struct SuppressionList {
+    suppressions: Vec<Suppression>,
+}
+
+impl SuppressionList {
+    /// Loads the entries at `path` applying to `util`.
+    pub fn load(path: &Path, util: &str) -> Result<Self, Error> {
+        let entries: Vec<SuppressionEntry> = serde_json::from_reader(
+            File::open(path).map_err(|e| Error::os_error(e, "Could not open suppressions"))?,
+        )
+        .map_err(|e| Error::serialize(format!("Could not parse suppressions: {e}")))?;
+
+        let util_name = Path::new(util)
+            .file_name()
+            .map_or(util.into(), |name| name.to_string_lossy());
+        let invalid = |name: &str, e: &dyn std::fmt::Display| {
+            Error::illegal_argument(format!("Invalid suppression '{name}': {e}"))
+        };
+        let suppressions = entries
+            .into_iter()
+            .filter(|entry| entry.util.as_ref().is_none_or(|u| *u == util_name))
+            .map(|entry| {
+                let [status1, status2] = entry.exit_statuses;
+                let exit_statuses = [
+                    status1.map(|s| s.parse()).transpose()?,
+                    status2.map(|s| s.parse()).transpose()?,
+                ];
+                Ok(Suppression {
+                    args: entry
+                        .args
+                        .map(|r| Regex::new(&r))
+                        .transpose()
+                        .map_err(|e| invalid(&entry.name, &e))?,
+                    stdin: entry
+                        .stdin
+                        .map(|r| bytes::Regex::new(&r))
+                        .transpose()
+                        .map_err(|e| invalid(&entry.name, &e))?,
+                    name: entry.name,
+                    differs: entry.differs,
+                    exit_statuses,
+                    stderr_classes: entry.stderr_classes,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Self { suppressions })
+    }
+
+    /// The index of the first suppression matching the input and the signature of its divergence
+    fn find<I: ExtractsToCommand + HasArgSpelling>(
+        &self,
+        input: &I,
+        signature: &DivergenceSignature,
+    ) -> Option<usize> {
+        // however the input happens to be spelled
+        let canonical = input.canonically_spelled();
+        let args = canonical
+            .as_ref()
+            .unwrap_or(input)
+            .get_args()
+            .iter()
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.suppressions
+            .iter()
+            .position(|s| s.matches(&args, input.get_stdin(), signature))
+    }
+}
+
+/// Not interesting if the divergence found by the preceding objectives is in the [`SuppressionList`]. Counts the
+/// suppressed divergences per entry and reports them as user stats to the monitor.
+pub struct SuppressionFeedback<C> {
+    list: Arc<SuppressionList>,
+    classifier: C,
+    counts: Vec<u64>,
+}
+
+impl<C> SuppressionFeedback<C> {
+    pub fn new(list: Arc<SuppressionList>, classifier: C) -> Self {
+        Self {
+            counts: vec![0; list.suppressions.len()],
+            list,
+            classifier,
+        }
+    }
+}
+
+impl<C, S> Feedback<S> for SuppressionFeedback<C>
+where
+    C: Classifier,
+    S: State + HasMetadata,
+    S::Input: ExtractsToCommand + HasArgSpelling,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &S::Input,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        if self.list.suppressions.is_empty() {
+            return Ok(true);
+        }
+        let signature = self.classifier.signature(state, observers, exit_kind)?;
+        let Some(index) = self.list.find(input, &signature) else {
+            return Ok(true);
+        };
+
+        self.counts[index] += 1;
+        manager.fire(
+            state,
+            Event::UpdateUserStats {
+                name: Cow::Owned(format!("suppressed {}", self.list.suppressions[index].name)),
+                value: UserStats::new(
+                    UserStatsValue::Number(self.counts[index]),
+                    AggregatorOps::Sum,
+                ),
+                phantom: PhantomData,
+            },
+        )?;
+        Ok(false)
+    }
+}
+
+impl<C> Named for SuppressionFeedback<C> {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("SuppressionFeedback")
+    }
+}
@@ -0,0 +1,362 @@
+use core::fmt;
+use std::{
+    borrow::Cow,
+    ffi::{OsStr, OsString},
+    fmt::{Display, Formatter},
+    hash::{DefaultHasher, Hash, Hasher},
+    os::unix::ffi::OsStrExt,
+};
+
+use serde::{Deserialize, Serialize};
+
+use libafl::{
+    corpus::CorpusId,
+    generators::Generator,
+    inputs::Input,
+    mutators::{MutationResult, Mutator},
+    state::HasRand,
+    Error, SerdeAny,
+};
+
+use libafl_bolts::{
+    prelude::Rand,
+    tuples::{tuple_list_type, Append},
+    HasLen, Named,
+};
+
+use crate::generic::{
+    executor::ExtractsToCommand,
+    fixture::{
+        fixture_mutators, generate_fixture, generate_operand, Fixture, FixtureAddEntryMutator,
+        FixtureAttributeMutator, FixtureOperandMutator, FixtureRemoveEntryMutator, HasFixture,
+    },
+    minimise::{without_each, without_set_fields, Shrinkable},
+    spelling::HasArgSpelling,
+    stdio::args_string_mapper,
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum LsSort {
+    None,
+    Size,
+    Time,
+    Version,
+    Extension,
+    Width,
+}
+
+impl LsSort {
+    const ALL: [Self; 6] = [
+        Self::None,
+        Self::Size,
+        Self::Time,
+        Self::Version,
+        Self::Extension,
+        Self::Width,
+    ];
+
+    fn as_arg(&self) -> &'static str {
+        match self {
+            Self::None => "--sort=none",
+            Self::Size => "--sort=size",
+            Self::Time => "--sort=time",
+            Self::Version => "--sort=version",
+            Self::Extension => "--sort=extension",
+            Self::Width => "--sort=width",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum LsTimeStyle {
+    FullIso,
+    LongIso,
+    Iso,
+    Locale,
+    /// `+FORMAT`, passed to strftime by both implementations
+    Format(Vec<u8>),
+}
+
+impl LsTimeStyle {
+    fn as_arg(&self) -> OsString {
+        let mut arg = OsString::from("--time-style=");
+        match self {
+            Self::FullIso => arg.push("full-iso"),
+            Self::LongIso => arg.push("long-iso"),
+            Self::Iso => arg.push("iso"),
+            Self::Locale => arg.push("locale"),
+            Self::Format(format) => {
+                arg.push("+");
+                arg.push(OsStr::from_bytes(format));
+            }
+        }
+        arg
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum LsQuotingStyle {
+    Literal,
+    Locale,
+    Shell,
+    ShellAlways,
+    ShellEscape,
+    ShellEscapeAlways,
+    C,
+    Escape,
+}
+
+impl LsQuotingStyle {
+    const ALL: [Self; 8] = [
+        Self::Literal,
+        Self::Locale,
+        Self::Shell,
+        Self::ShellAlways,
+        Self::ShellEscape,
+        Self::ShellEscapeAlways,
+        Self::C,
+        Self::Escape,
+    ];
+
+    fn as_arg(&self) -> &'static str {
+        match self {
+            Self::Literal => "--quoting-style=literal",
+            Self::Locale => "--quoting-style=locale",
+            Self::Shell => "--quoting-style=shell",
+            Self::ShellAlways => "--quoting-style=shell-always",
+            Self::ShellEscape => "--quoting-style=shell-escape",
+            Self::ShellEscapeAlways => "--quoting-style=shell-escape-always",
+            Self::C => "--quoting-style=c",
+            Self::Escape => "--quoting-style=escape",
+        }
+    }
+}
+
+/// An [`Input`] implementation for coreutils' `ls`
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, SerdeAny)]
+pub struct LsInput {
+    pub fixture: Fixture,
+    pub operands: Vec<Vec<u8>>,
+    pub all: bool,
+    pub long: bool,
+    pub recursive: bool,
+    pub reverse: bool,
+    pub color_never: bool,
+    pub sort: Option<LsSort>,
+    pub time_style: Option<LsTimeStyle>,
+    pub quoting_style: Option<LsQuotingStyle>,
+}
+
+impl Display for LsInput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "fixture: {}, args: {}",
+            self.fixture,
+            args_string_mapper(&self.get_args())
+        )
+    }
+}
+
+impl Input for LsInput {
+    fn generate_name(&self, _id: Option<CorpusId>) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+impl ExtractsToCommand for LsInput {
+    fn get_stdin(&self) -> &Vec<u8> {
+        static EMPTY: Vec<u8> = Vec::new();
+        &EMPTY
+    }
+
+    fn get_args<'a>(&self) -> Vec<Cow<'a, OsStr>> {
+        let mut args = Vec::with_capacity(9 + self.operands.len());
+        if self.all {
+            args.push(Cow::Borrowed(OsStr::new("-a")))
+        }
+        if self.long {
+            args.push(Cow::Borrowed(OsStr::new("-l")))
+        }
+        if self.recursive {
+            args.push(Cow::Borrowed(OsStr::new("-R")))
+        }
+        if self.reverse {
+            args.push(Cow::Borrowed(OsStr::new("-r")))
+        }
+        if self.color_never {
+            args.push(Cow::Borrowed(OsStr::new("--color=never")))
+        }
+        if let Some(sort) = &self.sort {
+            args.push(Cow::Borrowed(OsStr::new(sort.as_arg())))
+        }
+        if let Some(time_style) = &self.time_style {
+            args.push(Cow::Owned(time_style.as_arg()))
+        }
+        if let Some(quoting_style) = &self.quoting_style {
+            args.push(Cow::Borrowed(OsStr::new(quoting_style.as_arg())))
+        }
+        // fixture names may start with a dash
+        args.push(Cow::Borrowed(OsStr::new("--")));
+        args.extend(
+            self.operands
+                .iter()
+                .map(|o| Cow::Owned(OsStr::from_bytes(o).to_os_string())),
+        );
+        args
+    }
+
+    fn get_fixture(&self) -> Option<&Fixture> {
+        Some(&self.fixture)
+    }
+}
+
+impl HasFixture for LsInput {
+    fn fixture(&self) -> &Fixture {
+        &self.fixture
+    }
+
+    fn fixture_mut(&mut self) -> &mut Fixture {
+        &mut self.fixture
+    }
+
+    fn operands_mut(&mut self) -> &mut Vec<Vec<u8>> {
+        &mut self.operands
+    }
+}
+
+impl HasArgSpelling for LsInput {}
+
+impl Shrinkable for LsInput {
+    fn without_parts(&self) -> Vec<Self> {
+        let mut candidates = without_set_fields!(
+            self,
+            flags: [all, long, recursive, reverse, color_never],
+            options: [sort, time_style, quoting_style]
+        );
+        candidates.extend(without_each(&self.operands).map(|operands| Self {
+            operands,
+            ..self.clone()
+        }));
+        candidates
+    }
+}
+
+impl HasLen for LsInput {
+    fn len(&self) -> usize {
+        self.fixture.entries.len()
+    }
+}
+
+const TIME_FORMATS: [&[u8]; 6] = [
+    b"%Y-%m-%d",
+    b"%s",
+    b"%b %e %H:%M",
+    b"%%%n",
+    b"%c",
+    b"%Y-%m-%d\n%H:%M",
+];
+
+fn generate_time_style<R: Rand>(rand: &mut R) -> LsTimeStyle {
+    match rand.below(5) {
+        0 => LsTimeStyle::FullIso,
+        1 => LsTimeStyle::LongIso,
+        2 => LsTimeStyle::Iso,
+        3 => LsTimeStyle::Locale,
+        _ => LsTimeStyle::Format(TIME_FORMATS[rand.below(TIME_FORMATS.len())].to_vec()),
+    }
+}
+
+pub struct LsGenerator {
+    max_entries: usize,
+}
+
+impl LsGenerator {
+    pub fn new(max_entries: usize) -> Self {
+        Self { max_entries }
+    }
+}
+
+impl<S> Generator<LsInput, S> for LsGenerator
+where
+    S: HasRand,
+{
+    fn generate(&mut self, state: &mut S) -> Result<LsInput, Error> {
+        let rand = state.rand_mut();
+        let fixture = generate_fixture(rand, self.max_entries);
+        let operands = (0..rand.below(3))
+            .map(|_| generate_operand(rand, &fixture))
+            .collect();
+        Ok(LsInput {
+            operands,
+            all: rand.coinflip(0.5),
+            long: rand.coinflip(0.5),
+            recursive: rand.coinflip(0.3),
+            reverse: rand.coinflip(0.2),
+            color_never: rand.coinflip(0.5),
+            sort: rand
+                .coinflip(0.5)
+                .then(|| LsSort::ALL[rand.below(LsSort::ALL.len())]),
+            time_style: rand.coinflip(0.5).then(|| generate_time_style(rand)),
+            quoting_style: rand
+                .coinflip(0.5)
+                .then(|| LsQuotingStyle::ALL[rand.below(LsQuotingStyle::ALL.len())]),
+            fixture,
+        })
+    }
+}
+
+/// Flips or regenerates one randomly chosen option of an [`LsInput`]
+pub struct LsOptionMutator;
+impl<S> Mutator<LsInput, S> for LsOptionMutator
+where
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut LsInput) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+        match rand.below(8) {
+            0 => input.all = !input.all,
+            1 => input.long = !input.long,
+            2 => input.recursive = !input.recursive,
+            3 => input.reverse = !input.reverse,
+            4 => input.color_never = !input.color_never,
+            5 => {
+                input.sort = input
+                    .sort
+                    .is_none()
+                    .then(|| LsSort::ALL[rand.below(LsSort::ALL.len())])
+            }
+            6 => {
+                input.time_style = input
+                    .time_style
+                    .is_none()
+                    .then(|| generate_time_style(rand))
+            }
+            _ => {
+                input.quoting_style = input
+                    .quoting_style
+                    .is_none()
+                    .then(|| LsQuotingStyle::ALL[rand.below(LsQuotingStyle::ALL.len())])
+            }
+        }
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for LsOptionMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("LsOptionMutator")
+    }
+}
+
+pub fn ls_mutators() -> tuple_list_type!(
+    FixtureAddEntryMutator,
+    FixtureRemoveEntryMutator,
+    FixtureAttributeMutator,
+    FixtureOperandMutator,
+    LsOptionMutator
+) {
+    fixture_mutators().append(LsOptionMutator)
+}
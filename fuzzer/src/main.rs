@@ -1,22 +1,74 @@
 mod base64;
+mod cli;
+mod du;
+mod fs_altering;
 mod generic;
-
-use std::{path::PathBuf, time::Duration};
-
-use base64::{base64_mutators, Base64Generator, GeneratorType};
+mod ls;
+mod ptx;
+mod spec;
+mod stat;
+mod tsort;
+mod wrapper;
+
+use std::{env, fmt::Display, path::PathBuf, sync::Arc, time::Duration};
+
+use base64::{base64_mutators, Base64Generator};
+use clap::Parser;
+use cli::{Cli, Command, Util};
+use du::{du_mutators, DuGenerator};
+use fs_altering::{fs_altering_mutators, FsAlteringGenerator};
+use ls::{ls_mutators, LsGenerator};
+use ptx::{ptx_mutators, PtxGenerator};
+use spec::{spec_mutators, SpecGenerator, UtilSpec};
+use stat::{stat_mutators, StatGenerator};
+use tsort::{tsort_mutators, TsortGenerator};
+use wrapper::{wrapper_mutators, WrapperGenerator};
 
 use generic::{
-    executor::CoverageCommandExecutor,
-    shmem::{get_coverage_shmem_size, get_shmem},
+    bucket::BucketFeedback,
+    divergence::{Comparison, DivergenceClassifier},
+    equivalence::{SpellingEquivalenceExecutor, SpellingEquivalenceFeedback},
+    executor::{
+        CoverageCommandExecutor, CoverageConfigurator, ExitStatusCommandExecutor, ExtractsToCommand,
+    },
+    exit_status::{ExitStatusDiffFeedback, ExitStatusEquivalence, ExitStatusObserver},
+    forkserver::ForkServerExecutor,
+    implementation::Implementation,
+    majority::{MajorityClassifier, MajorityFeedback, NWayExecutor, NWayObserver},
+    minimise::{MinimisationStage, Shrinkable},
+    namespace::{
+        run_namespace_runner, NamespacedCommandExecutor, TreeSnapshotDiffFeedback,
+        TreeSnapshotObserver, NAMESPACE_RUNNER_ENV,
+    },
+    novelty::OutputNoveltyExecutor,
+    options::GeneratorType,
+    panic::PanicFeedback,
+    recheck::recheck,
+    replay::{replay, ReplaySetup},
+    reproducer::ReproducerExporter,
+    sanitizer::SanitizerFeedback,
+    shmem::get_shmem,
+    spelling::HasArgSpelling,
+    stderr::{StderrClassDiffFeedback, StderrNormaliser},
+    stdio::{DiffStdIOMetadataPseudoFeedback, StdoutDiffFeedback, StdoutPolicy},
+    suppression::{SuppressionFeedback, SuppressionList},
+    timeout::AnyTimeoutFeedback,
 };
 
 use libafl::{
-    corpus::OnDiskCorpus,
+    corpus::{InMemoryCorpus, OnDiskCorpus},
     events::{EventConfig, Launcher, LlmpRestartingEventManager},
-    feedbacks::{AflMapFeedback, CrashFeedback},
-    monitors::OnDiskTomlMonitor,
-    mutators::StdMOptMutator,
-    observers::{StdErrObserver, StdMapObserver, StdOutObserver, TimeObserver},
+    executors::DiffExecutor,
+    feedback_and_fast, feedback_or, feedback_or_fast,
+    feedbacks::{AflMapFeedback, ConstFeedback, CrashFeedback, DiffExitKindFeedback, TimeFeedback},
+    generators::Generator,
+    inputs::Input,
+    monitors::{Monitor, OnDiskTomlMonitor},
+    mutators::{MutatorsTuple, StdMOptMutator},
+    observers::{
+        HitcountsIterableMapObserver, MultiMapObserver, StdErrObserver, StdMapObserver,
+        StdOutObserver, TimeObserver,
+    },
     schedulers::{powersched::PowerSchedule, StdWeightedScheduler},
     stages::StdMutationalStage,
     state::StdState,
@@ -24,28 +76,13 @@ use libafl::{
 };
 
 use libafl_bolts::{
-    cli::parse_args,
     core_affinity::CoreId,
     current_nanos,
+    ownedref::OwnedMutSlice,
     rands::StdRand,
-    shmem::{ShMemProvider, StdShMemProvider},
-    tuples::{tuple_list, Handled},
-    AsSliceMut,
-};
-
-#[cfg(feature = "differential")]
-use {
-    generic::{stdio::DiffStdIOMetadataPseudoFeedback, timeout::AnyTimeoutFeedback},
-    libafl::{
-        executors::DiffExecutor,
-        feedback_and_fast, feedback_or, feedback_or_fast,
-        feedbacks::{
-            differential::DiffResult, ConstFeedback, DiffExitKindFeedback, DiffFeedback,
-            TimeFeedback,
-        },
-        observers::{HitcountsIterableMapObserver, MultiMapObserver},
-    },
-    libafl_bolts::ownedref::OwnedMutSlice,
+    shmem::{ShMemDescription, ShMemProvider, StdShMemProvider},
+    tuples::{tuple_list, tuple_list_type, Handled, NamedTuple},
+    AsSliceMut, HasLen,
 };
 
 #[cfg(feature = "gcov")]
@@ -54,8 +91,6 @@ use generic::cov_feedback::CovFeedback;
 #[cfg(feature = "log_new_corpus_entries")]
 use generic::new_corpus_entry_log_feedback::NewCorpusEntryLogFeedback;
 
-#[cfg(not(feature = "on_disk_corpus"))]
-use libafl::corpus::InMemoryCorpus;
 #[cfg(feature = "on_disk_corpus")]
 use libafl::corpus::InMemoryOnDiskCorpus;
 
@@ -64,16 +99,122 @@ use libafl::monitors::tui::TuiMonitor;
 #[cfg(not(feature = "tui"))]
 use libafl::monitors::MultiMonitor;
 
-#[cfg(feature = "uutils")]
-pub static UUTILS_PREFIX: &str = "./target/uutils_coreutils/target/release/";
-#[cfg(feature = "gnu")]
-pub static GNU_PREFIX: &str = "./target/GNU_coreutils/src/";
-#[cfg(feature = "gnu")]
-pub static GNU_GCOV_PREFIX: &str = "./target/GNU_coreutils_coverage/src/";
+#[cfg(not(feature = "on_disk_corpus"))]
+type FuzzCorpus<I> = InMemoryCorpus<I>;
+#[cfg(feature = "on_disk_corpus")]
+type FuzzCorpus<I> = InMemoryOnDiskCorpus<I>;
+
+/// The maximal number of executions spent minimising a single objective
+const MINIMISATION_EXECUTIONS: usize = 2000;
+
+type FuzzState<I, SC> = StdState<I, FuzzCorpus<I>, StdRand, SC>;
+/// Objectives of a single implementation are stored on disk as they are found
+type OnDiskState<I> = FuzzState<I, OnDiskCorpus<I>>;
+/// Differential objectives, of two implementations or of a majority vote, are stored on disk in buckets by
+/// [`BucketFeedback`]
+type DifferentialState<I> = FuzzState<I, InMemoryCorpus<I>>;
+
+/// The observers of the execution of a single implementation, see [`build_executor`]
+type ImplementationObservers<'a> = tuple_list_type!(
+    StdMapObserver<'a, u8, false>,
+    StdOutObserver,
+    StdErrObserver,
+    TimeObserver,
+    TreeSnapshotObserver,
+    ExitStatusObserver
+);
+/// The executor of a single implementation, see [`build_executor`]
+type ImplementationExecutor<'a, C, S> = SpellingEquivalenceExecutor<
+    OutputNoveltyExecutor<
+        ForkServerExecutor<ExitStatusCommandExecutor<ImplementationObservers<'a>, S, C>>,
+        StdMapObserver<'a, u8, false>,
+    >,
+>;
 
 pub fn main() {
-    let util = "base64";
-    match fuzz(util) {
+    // the fuzzer binary doubles as the runner setting up the namespaces for NamespacedCommandExecutor
+    if let Some(config_path) = env::var_os(NAMESPACE_RUNNER_ENV) {
+        run_namespace_runner(config_path);
+    }
+
+    let cli = Cli::parse();
+    let util = cli.util.binary_name();
+    let stdout_policy = cli.stdout_policy.unwrap_or(cli.util.stdout_policy());
+    let result = if let Some(spec_path) = &cli.spec {
+        UtilSpec::load(spec_path).and_then(|spec| {
+            let spec = Arc::new(spec);
+            fuzz::<_, CoverageCommandExecutor<_>, _, _>(
+                &spec.util,
+                &cli,
+                cli.stdout_policy.or(spec.stdout_policy).unwrap_or_default(),
+                || SpecGenerator::new(spec.clone(), 8),
+                || spec_mutators(&spec),
+            )
+        })
+    } else if let Some(fs_util) = cli.util.fs_altering() {
+        fuzz::<_, NamespacedCommandExecutor<_>, _, _>(
+            util,
+            &cli,
+            stdout_policy,
+            || FsAlteringGenerator::new(fs_util, 8),
+            fs_altering_mutators,
+        )
+    } else if let Some(wrapper_util) = cli.util.wrapper() {
+        fuzz::<_, CoverageCommandExecutor<_>, _, _>(
+            util,
+            &cli,
+            stdout_policy,
+            || WrapperGenerator::new(wrapper_util),
+            wrapper_mutators,
+        )
+    } else {
+        match cli.util {
+            Util::Base64 => fuzz::<_, CoverageCommandExecutor<_>, _, _>(
+                util,
+                &cli,
+                stdout_policy,
+                || Base64Generator::new(1, 10, GeneratorType::Printable),
+                base64_mutators,
+            ),
+            Util::Ls => fuzz::<_, CoverageCommandExecutor<_>, _, _>(
+                util,
+                &cli,
+                stdout_policy,
+                || LsGenerator::new(8),
+                ls_mutators,
+            ),
+            Util::Stat => fuzz::<_, CoverageCommandExecutor<_>, _, _>(
+                util,
+                &cli,
+                stdout_policy,
+                || StatGenerator::new(8, 6),
+                stat_mutators,
+            ),
+            Util::Du => fuzz::<_, CoverageCommandExecutor<_>, _, _>(
+                util,
+                &cli,
+                stdout_policy,
+                || DuGenerator::new(8),
+                du_mutators,
+            ),
+            Util::Tsort => fuzz::<_, CoverageCommandExecutor<_>, _, _>(
+                util,
+                &cli,
+                stdout_policy,
+                || TsortGenerator::new(12),
+                tsort_mutators,
+            ),
+            Util::Ptx => fuzz::<_, CoverageCommandExecutor<_>, _, _>(
+                util,
+                &cli,
+                stdout_policy,
+                || PtxGenerator::new(20),
+                ptx_mutators,
+            ),
+            _ => unreachable!("file system altering and wrapper utilities are handled above"),
+        }
+    };
+    match result {
         Ok(_) => (),
         Err(Error::ShuttingDown) => {
             println!("Orderly shutdown");
@@ -84,130 +225,314 @@ pub fn main() {
     }
 }
 
-fn fuzz(util: &str) -> Result<(), Error> {
-    let options = parse_args();
-
-    #[cfg(not(feature = "tui"))]
-    let base_monitor = MultiMonitor::new(|s| println!("{}", s));
-    #[cfg(feature = "tui")]
-    let base_monitor = TuiMonitor::builder()
-        .title("coreutils differential fuzzer")
-        .build();
-    let monitor = OnDiskTomlMonitor::with_update_interval(
-        "monitor.toml",
-        base_monitor,
-        Duration::from_millis(100),
-    );
+/// Fuzzes `util` with inputs of type `I`, running the implementations selected with `--impl` with the configurator
+/// `C`: a single one on its own, two differentially, or more with a majority vote.
+fn fuzz<I, C, G, MT>(
+    util: &str,
+    cli: &Cli,
+    stdout_policy: StdoutPolicy,
+    generator: impl Fn() -> G,
+    mutators: impl Fn() -> MT,
+) -> Result<(), Error>
+where
+    I: Input + ExtractsToCommand + Display + HasLen + HasArgSpelling + Shrinkable,
+    C: CoverageConfigurator<I>,
+    G: Generator<I, OnDiskState<I>> + Generator<I, DifferentialState<I>>,
+    MT: MutatorsTuple<I, OnDiskState<I>> + MutatorsTuple<I, DifferentialState<I>> + NamedTuple,
+{
+    let implementations = Implementation::load(&cli.implementations, &cli.impls, util)?;
+    match implementations.as_slice() {
+        [] => Err(Error::illegal_argument(
+            "Select at least one implementation with --impl",
+        )),
+        [implementation1, implementation2] => fuzz_differential::<I, C, G, MT>(
+            util,
+            [implementation1, implementation2],
+            cli,
+            stdout_policy,
+            generator,
+            mutators,
+        ),
+        _ if cli.command.is_some() => Err(Error::illegal_argument(
+            "Replaying and rechecking need two implementations, select them with --impl",
+        )),
+        [implementation] => fuzz_single::<I, C, G, MT>(implementation, cli, generator, mutators),
+        _ => fuzz_majority::<I, C, G, MT>(
+            util,
+            &implementations,
+            cli,
+            stdout_policy,
+            generator,
+            mutators,
+        ),
+    }
+}
 
-    #[cfg(feature = "uutils")]
-    let (uutils_coverage_shmem_size, uutils_path) =
-        get_coverage_shmem_size(format!("{UUTILS_PREFIX}{util}"))?;
-    #[cfg(feature = "gnu")]
-    let (gnu_coverage_shmem_size, gnu_path) =
-        get_coverage_shmem_size(format!("{GNU_PREFIX}{util}"))?;
+/// Fuzzes a single implementation, saving crashes and inputs it handles differently for equivalent spellings.
+fn fuzz_single<I, C, G, MT>(
+    implementation: &Implementation,
+    cli: &Cli,
+    generator: impl Fn() -> G,
+    mutators: impl Fn() -> MT,
+) -> Result<(), Error>
+where
+    I: Input + ExtractsToCommand + Display + HasLen + HasArgSpelling + Shrinkable,
+    C: CoverageConfigurator<I>,
+    G: Generator<I, OnDiskState<I>>,
+    MT: MutatorsTuple<I, OnDiskState<I>> + NamedTuple,
+{
+    let options = &cli.options;
+    #[cfg(feature = "gcov")]
+    let gcov = gcov_build(&[implementation])?;
 
     let run_client = |state: Option<_>,
                       mut mgr: LlmpRestartingEventManager<_, _, _>,
                       core_id: CoreId|
      -> Result<(), Error> {
-        #[cfg(feature = "uutils")]
-        let (mut uutils_coverage_shmem, uutils_coverage_shmem_description) =
-            get_shmem(uutils_coverage_shmem_size)?;
+        let id = format!("{}-{:?}", implementation.name, core_id.0);
+        let (mut coverage_shmem, coverage_shmem_description) =
+            get_shmem(implementation.coverage_shmem_size)?;
+
+        let stdout_observer = StdOutObserver::new("stdout-observer");
+        let stderr_observer = StdErrObserver::new("stderr-observer");
+        let time_observer = TimeObserver::new("time-observer");
+        let tree_observer = TreeSnapshotObserver::new("tree-observer", &id);
+        let exit_status_observer = ExitStatusObserver::new("exit-status-observer");
+        let coverage_observer =
+            unsafe { StdMapObserver::new("coverage-observer", coverage_shmem.as_slice_mut()) };
+
+        #[cfg(feature = "gcov")]
+        let mut feedback = feedback_and_fast!(
+            AflMapFeedback::new(&coverage_observer),
+            CovFeedback::<C>::new(
+                true,
+                &gcov,
+                &format!("gcov-{:?}", core_id.0),
+                &coverage_shmem_description
+            )
+        );
+        #[cfg(not(feature = "gcov"))]
+        let mut feedback = AflMapFeedback::new(&coverage_observer);
+        // crashes, and the first input of each sanitizer report and panic location
+        let mut objective = feedback_or_fast!(
+            SanitizerFeedback::crash_objective(&[(&implementation.name, &stderr_observer)]),
+            PanicFeedback::crash_objective(&[(
+                &implementation.name,
+                &stderr_observer,
+                &exit_status_observer
+            )]),
+            SpellingEquivalenceFeedback
+        );
 
-        #[cfg(feature = "gnu")]
-        let (mut gnu_coverage_shmem, gnu_coverage_shmem_description) =
-            get_shmem(gnu_coverage_shmem_size)?;
+        let mut state = state.unwrap_or_else(|| {
+            StdState::new(
+                StdRand::with_seed(current_nanos()),
+                fuzz_corpus(),
+                OnDiskCorpus::new(PathBuf::from(&options.output)).unwrap(),
+                &mut feedback,
+                &mut objective,
+            )
+            .unwrap()
+        });
+
+        let scheduler = StdWeightedScheduler::with_schedule(
+            &mut state,
+            &coverage_observer,
+            Some(PowerSchedule::FAST),
+        );
+
+        let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
+        let mut executor = build_executor::<I, C, _>(
+            implementation,
+            &id,
+            &coverage_shmem_description,
+            tuple_list!(
+                coverage_observer,
+                stdout_observer,
+                stderr_observer,
+                time_observer,
+                tree_observer,
+                exit_status_observer
+            ),
+            cli.exit_status.clone(),
+        );
+
+        if state.must_load_initial_inputs() {
+            state.generate_initial_inputs(
+                &mut fuzzer,
+                &mut executor,
+                &mut generator(),
+                &mut mgr,
+                8,
+            )?
+        }
+
+        let mut stages = tuple_list!(StdMutationalStage::new(StdMOptMutator::new(
+            &mut state,
+            mutators(),
+            7,
+            5
+        )?));
+
+        fuzzer.fuzz_loop(&mut stages, &mut executor, &mut state, &mut mgr)
+    };
+
+    Launcher::builder()
+        .configuration(EventConfig::AlwaysUnique)
+        .shmem_provider(StdShMemProvider::new()?)
+        .monitor(monitor())
+        .run_client(run_client)
+        .cores(&options.cores)
+        .broker_port(options.broker_port)
+        .stdout_file(Some(&options.stdout))
+        .remote_broker_addr(options.remote_broker_addr)
+        .build()
+        .launch()
+}
+
+/// Fuzzes two implementations differentially, saving the inputs they diverge on in buckets, or replays or rechecks
+/// saved inputs if a [`Command`] is given.
+fn fuzz_differential<I, C, G, MT>(
+    util: &str,
+    implementations: [&Implementation; 2],
+    cli: &Cli,
+    stdout_policy: StdoutPolicy,
+    generator: impl Fn() -> G,
+    mutators: impl Fn() -> MT,
+) -> Result<(), Error>
+where
+    I: Input + ExtractsToCommand + Display + HasLen + HasArgSpelling + Shrinkable,
+    C: CoverageConfigurator<I>,
+    G: Generator<I, DifferentialState<I>>,
+    MT: MutatorsTuple<I, DifferentialState<I>> + NamedTuple,
+{
+    let options = &cli.options;
+    let [implementation1, implementation2] = implementations;
+    let reference = match &cli.reference {
+        None => 1,
+        Some(name) => implementations
+            .iter()
+            .position(|implementation| &implementation.name == name)
+            .ok_or_else(|| {
+                Error::illegal_argument(format!(
+                    "The reference '{name}' is none of the implementations selected with --impl"
+                ))
+            })?,
+    };
+    let stderr_normaliser = Arc::new(StderrNormaliser::load(&cli.stderr_rules, util)?);
+    let suppressions = Arc::new(
+        cli.suppressions
+            .as_deref()
+            .map(|path| SuppressionList::load(path, util))
+            .transpose()?
+            .unwrap_or_default(),
+    );
+    #[cfg(feature = "gcov")]
+    let gcov = gcov_build(&implementations)?;
+
+    if let Some(command) = &cli.command {
+        let setup = ReplaySetup {
+            targets: implementations,
+            stdout_policy,
+            exit_status_equivalence: cli.exit_status.clone(),
+            stderr_normaliser,
+        };
+        return match command {
+            Command::Replay { path } => replay::<I, C>(path, setup),
+            Command::Recheck { dir } => {
+                recheck::<I, C>(dir.as_deref().unwrap_or(options.output.as_path()), setup)
+            }
+        };
+    }
+    let reproducer_exporter = ReproducerExporter::new(util, &implementations, Some(reference));
+
+    let run_client = |state: Option<_>,
+                      mut mgr: LlmpRestartingEventManager<_, _, _>,
+                      core_id: CoreId|
+     -> Result<(), Error> {
+        let id1 = format!("{}-{:?}", implementation1.name, core_id.0);
+        let id2 = format!("{}-{:?}", implementation2.name, core_id.0);
+        let (mut coverage_shmem1, coverage_shmem_description1) =
+            get_shmem(implementation1.coverage_shmem_size)?;
+        let (mut coverage_shmem2, coverage_shmem_description2) =
+            get_shmem(implementation2.coverage_shmem_size)?;
 
-        #[cfg(feature = "differential")]
         let combined_coverage_observer = HitcountsIterableMapObserver::new(
             MultiMapObserver::differential("CombinedCoverage", unsafe {
                 vec![
                     OwnedMutSlice::from_raw_parts_mut(
-                        uutils_coverage_shmem.as_mut_ptr(),
-                        uutils_coverage_shmem.len(),
+                        coverage_shmem1.as_mut_ptr(),
+                        coverage_shmem1.len(),
                     ),
                     OwnedMutSlice::from_raw_parts_mut(
-                        gnu_coverage_shmem.as_mut_ptr(),
-                        gnu_coverage_shmem.len(),
+                        coverage_shmem2.as_mut_ptr(),
+                        coverage_shmem2.len(),
                     ),
                 ]
             }),
         );
 
-        #[cfg(feature = "uutils")]
-        let uutils_stdout_observer = StdOutObserver::new("uutils-stdout-observer");
-        #[cfg(feature = "uutils")]
-        let uutils_stderr_observer = StdErrObserver::new("uutils-stderr-observer");
-        #[cfg(feature = "uutils")]
-        let uutils_time_observer = TimeObserver::new("uutils-time-observer");
-        #[cfg(feature = "uutils")]
-        let uutils_coverage_observer = unsafe {
-            StdMapObserver::new(
-                "uutils-coverage-observer",
-                uutils_coverage_shmem.as_slice_mut(),
-            )
-        };
-
-        #[cfg(feature = "gnu")]
-        let gnu_stdout_observer = StdOutObserver::new("gnu-stdout-observer");
-        #[cfg(feature = "gnu")]
-        let gnu_stderr_observer = StdErrObserver::new("gnu-stderr-observer");
-        #[cfg(feature = "gnu")]
-        let gnu_time_observer = TimeObserver::new("gnu-time-observer");
-        #[cfg(feature = "gnu")]
-        let gnu_coverage_observer = unsafe {
-            StdMapObserver::new("gnu-coverage-observer", gnu_coverage_shmem.as_slice_mut())
-        };
+        let stdout_observer1 = StdOutObserver::new("stdout-observer-1");
+        let stderr_observer1 = StdErrObserver::new("stderr-observer-1");
+        let time_observer1 = TimeObserver::new("time-observer-1");
+        let tree_observer1 = TreeSnapshotObserver::new("tree-observer-1", &id1);
+        let exit_status_observer1 = ExitStatusObserver::new("exit-status-observer-1");
+        let coverage_observer1 =
+            unsafe { StdMapObserver::new("coverage-observer-1", coverage_shmem1.as_slice_mut()) };
+
+        let stdout_observer2 = StdOutObserver::new("stdout-observer-2");
+        let stderr_observer2 = StdErrObserver::new("stderr-observer-2");
+        let time_observer2 = TimeObserver::new("time-observer-2");
+        let tree_observer2 = TreeSnapshotObserver::new("tree-observer-2", &id2);
+        let exit_status_observer2 = ExitStatusObserver::new("exit-status-observer-2");
+        let coverage_observer2 =
+            unsafe { StdMapObserver::new("coverage-observer-2", coverage_shmem2.as_slice_mut()) };
+
+        let divergence_classifier = DivergenceClassifier::new(
+            [&stdout_observer1, &stdout_observer2],
+            [&stderr_observer1, &stderr_observer2],
+            [&exit_status_observer1, &exit_status_observer2],
+            [&tree_observer1, &tree_observer2],
+            stdout_policy,
+            cli.exit_status.clone(),
+            stderr_normaliser.clone(),
+        );
 
-        #[cfg(feature = "differential")]
         let (mut feedback, mut objective) = (|| -> Result<_, Error> {
-            let stdout_diff_feedback = DiffFeedback::new(
-                "StdoutEqDiffFeedback",
-                &uutils_stdout_observer,
-                &gnu_stdout_observer,
-                |o1, o2| {
-                    if o1.stdout != o2.stdout {
-                        DiffResult::Diff
-                    } else {
-                        DiffResult::Equal
-                    }
-                },
-            )?;
-
-            let stderr_neither_feedback = DiffFeedback::new(
-                "StderrNeitherDiffFeedback",
-                &uutils_stderr_observer,
-                &gnu_stderr_observer,
-                |o1, o2| {
-                    if let Some(r1) = has_stderr(o1) {
-                        if let Some(r2) = has_stderr(o2) {
-                            if !r1 && !r2 {
-                                return DiffResult::Diff; // trigger the feedback
-                            }
-                        } else {
-                            println!("DIFF: No stderr for GNU");
-                        }
-                    } else {
-                        println!("DIFF: No stderr for uutils");
-                    }
-                    DiffResult::Equal
-                },
-            )?;
+            let stdout_diff_feedback = StdoutDiffFeedback::new(
+                &stdout_observer1,
+                &stdout_observer2,
+                &stderr_observer1,
+                &stderr_observer2,
+                stdout_policy,
+            );
+
+            let tree_diff_feedback =
+                TreeSnapshotDiffFeedback::new(&tree_observer1, &tree_observer2);
 
             #[cfg(feature = "gcov")]
-            let gcov_feedback = CovFeedback::new(
+            let gcov_feedback = CovFeedback::<C>::new(
                 true,
-                format!("{GNU_GCOV_PREFIX}{util}"),
-                format!("cov-{:?}", core_id.0),
+                &gcov,
+                &format!("gcov-{:?}", core_id.0),
+                &coverage_shmem_description1,
             );
 
             let metadata_pseudo_feedback = DiffStdIOMetadataPseudoFeedback::new(
-                &uutils_path,
-                &gnu_path,
-                &uutils_stderr_observer,
-                &gnu_stderr_observer,
-                &uutils_stdout_observer,
-                &gnu_stdout_observer,
+                implementation1,
+                implementation2,
+                &stderr_observer1,
+                &stderr_observer2,
+                &stdout_observer1,
+                &stdout_observer2,
+            );
+
+            let suppression_feedback =
+                SuppressionFeedback::new(suppressions.clone(), divergence_classifier.clone());
+            let bucket_feedback = BucketFeedback::new(
+                PathBuf::from(&options.output),
+                divergence_classifier.clone(),
+                reproducer_exporter.clone(),
             );
 
             let coverage_feedback = AflMapFeedback::new(&combined_coverage_observer);
@@ -226,58 +551,70 @@ fn fuzz(util: &str) -> Result<(), Error> {
             // only add logger feedbacks if something was found
             let objective = feedback_and_fast!(
                 feedback_or_fast!(
+                    // sanitizer reports, bucketed by their error type and location
+                    SanitizerFeedback::new(&[
+                        (&implementation1.name, &stderr_observer1),
+                        (&implementation2.name, &stderr_observer2)
+                    ]),
+                    // panics, failed assertions and aborts, bucketed by their location
+                    PanicFeedback::new(&[
+                        (
+                            &implementation1.name,
+                            &stderr_observer1,
+                            &exit_status_observer1
+                        ),
+                        (
+                            &implementation2.name,
+                            &stderr_observer2,
+                            &exit_status_observer2
+                        )
+                    ]),
                     CrashFeedback::new(),
                     // only check timeout concerning exit_kinds if none include a timeout
                     feedback_and_fast!(
                         AnyTimeoutFeedback,
                         feedback_or_fast!(
+                            // a single implementation behaving differently for equivalent spellings
+                            SpellingEquivalenceFeedback,
                             DiffExitKindFeedback::new(),
-                            // only test stdout equality if neither has a stderr
-                            feedback_and_fast!(stderr_neither_feedback, stdout_diff_feedback)
+                            ExitStatusDiffFeedback::new(
+                                &exit_status_observer1,
+                                &exit_status_observer2,
+                                cli.exit_status.clone()
+                            ),
+                            // failing for different reasons
+                            StderrClassDiffFeedback::new(
+                                &stderr_observer1,
+                                &stderr_observer2,
+                                stderr_normaliser.clone()
+                            ),
+                            // the trees left behind are compared regardless of any output
+                            tree_diff_feedback,
+                            // stdout is compared according to the utility's policy
+                            stdout_diff_feedback
                         )
                     )
                 ),
+                // known divergences are counted instead of saved
+                suppression_feedback,
                 feedback_or!(
                     metadata_pseudo_feedback,
-                    TimeFeedback::new(&uutils_time_observer),
-                    TimeFeedback::new(&gnu_time_observer),
+                    TimeFeedback::new(&time_observer1),
+                    TimeFeedback::new(&time_observer2),
                     ConstFeedback::new(true) // to ensure the whole block to be interesting
-                )
+                ),
+                // only the first and the smallest objective of each bucket are saved
+                bucket_feedback
             );
 
             Ok((feedback, objective))
         })()?;
 
-        #[cfg(all(not(feature = "differential"), feature = "gnu"))]
-        let (mut feedback, mut objective) = {
-            #[cfg(feature = "gcov")]
-            let gcov_feedback = CovFeedback::new(
-                true,
-                format!("{GNU_GCOV_PREFIX}{util}"),
-                format!("cov-{:?}", core_id.0),
-            );
-            #[cfg(feature = "gcov")]
-            let feedback =
-                feedback_and_fast!(AflMapFeedback::new(&gnu_coverage_observer), gcov_feedback);
-            #[cfg(not(feature = "gcov"))]
-            let feedback = AflMapFeedback::new(&gnu_coverage_observer);
-            let objective = CrashFeedback::new();
-            (feedback, objective)
-        };
-        #[cfg(all(not(feature = "differential"), feature = "uutils"))]
-        let (mut feedback, mut objective) = {
-            let feedback = AflMapFeedback::new(&uutils_coverage_observer);
-            let objective = CrashFeedback::new();
-            (feedback, objective)
-        };
         let mut state = state.unwrap_or_else(|| {
             StdState::new(
                 StdRand::with_seed(current_nanos()),
-                #[cfg(feature = "on_disk_corpus")]
-                InMemoryOnDiskCorpus::new(PathBuf::from("corpus")).unwrap(),
-                #[cfg(not(feature = "on_disk_corpus"))]
+                fuzz_corpus(),
                 InMemoryCorpus::new(),
-                OnDiskCorpus::new(PathBuf::from(&options.output)).unwrap(),
                 &mut feedback,
                 &mut objective,
             )
@@ -286,85 +623,293 @@ fn fuzz(util: &str) -> Result<(), Error> {
 
         let scheduler = StdWeightedScheduler::with_schedule(
             &mut state,
-            #[cfg(feature = "differential")]
             &combined_coverage_observer,
-            #[cfg(all(not(feature = "differential"), feature = "uutils"))]
-            &uutils_coverage_observer,
-            #[cfg(all(not(feature = "differential"), feature = "gnu"))]
-            &gnu_coverage_observer,
             Some(PowerSchedule::FAST),
         );
 
         let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
-        #[cfg(feature = "uutils")]
-        let uutils_executor = CoverageCommandExecutor::new(
-            &uutils_coverage_shmem_description,
-            Some(uutils_stdout_observer.handle()),
-            Some(uutils_stderr_observer.handle()),
+        let executor1 = build_executor::<I, C, _>(
+            implementation1,
+            &id1,
+            &coverage_shmem_description1,
             tuple_list!(
-                uutils_coverage_observer,
-                uutils_stdout_observer,
-                uutils_stderr_observer,
-                uutils_time_observer
+                coverage_observer1,
+                stdout_observer1,
+                stderr_observer1,
+                time_observer1,
+                tree_observer1,
+                exit_status_observer1
             ),
-            &uutils_path,
-            format!("uutils-{:?}", core_id.0),
+            cli.exit_status.clone(),
         );
-
-        #[cfg(feature = "gnu")]
-        let gnu_executor = CoverageCommandExecutor::new(
-            &gnu_coverage_shmem_description,
-            Some(gnu_stdout_observer.handle()),
-            Some(gnu_stderr_observer.handle()),
+        let executor2 = build_executor::<I, C, _>(
+            implementation2,
+            &id2,
+            &coverage_shmem_description2,
             tuple_list!(
-                gnu_coverage_observer,
-                gnu_stdout_observer,
-                gnu_stderr_observer,
-                gnu_time_observer
+                coverage_observer2,
+                stdout_observer2,
+                stderr_observer2,
+                time_observer2,
+                tree_observer2,
+                exit_status_observer2
             ),
-            &gnu_path,
-            format!("gnu-{:?}", core_id.0),
+            cli.exit_status.clone(),
         );
 
-        #[cfg(feature = "differential")]
-        let diff_executor = DiffExecutor::new(
-            uutils_executor,
-            gnu_executor,
+        let mut executor = DiffExecutor::new(
+            executor1,
+            executor2,
             tuple_list!(combined_coverage_observer),
         );
-        #[cfg(feature = "differential")]
-        let mut executor = diff_executor;
-        #[cfg(all(not(feature = "differential"), feature = "uutils"))]
-        let mut executor = uutils_executor;
-        #[cfg(all(not(feature = "differential"), feature = "gnu"))]
-        let mut executor = gnu_executor;
 
         if state.must_load_initial_inputs() {
             state.generate_initial_inputs(
                 &mut fuzzer,
                 &mut executor,
-                &mut Base64Generator::new(1, 10, GeneratorType::Printable),
+                &mut generator(),
                 &mut mgr,
                 8,
             )?
         }
 
-        let mut stages = tuple_list!(StdMutationalStage::new(StdMOptMutator::new(
-            &mut state,
-            base64_mutators(),
-            7,
-            5
-        )?));
+        let mut stages = tuple_list!(
+            StdMutationalStage::new(StdMOptMutator::new(&mut state, mutators(), 7, 5)?),
+            // shrinks the representatives of new buckets
+            MinimisationStage::new(
+                PathBuf::from(&options.output),
+                divergence_classifier,
+                reproducer_exporter.clone(),
+                MINIMISATION_EXECUTIONS
+            )
+        );
 
         fuzzer.fuzz_loop(&mut stages, &mut executor, &mut state, &mut mgr)
     };
 
-    let launcher_shmem_provider = StdShMemProvider::new()?;
+    Launcher::builder()
+        .configuration(EventConfig::AlwaysUnique)
+        .shmem_provider(StdShMemProvider::new()?)
+        .monitor(monitor())
+        .run_client(run_client)
+        .cores(&options.cores)
+        .broker_port(options.broker_port)
+        .stdout_file(Some(&options.stdout))
+        .remote_broker_addr(options.remote_broker_addr)
+        .build()
+        .launch()
+}
+
+/// Fuzzes three or more implementations, saving the inputs they diverge on together with the implementations the
+/// divergence is attributed to by a majority vote, see [`MajorityFeedback`]. Objectives are bucketed by the
+/// signature of their outliers, see [`MajorityClassifier`].
+fn fuzz_majority<I, C, G, MT>(
+    util: &str,
+    implementations: &[Implementation],
+    cli: &Cli,
+    stdout_policy: StdoutPolicy,
+    generator: impl Fn() -> G,
+    mutators: impl Fn() -> MT,
+) -> Result<(), Error>
+where
+    I: Input + ExtractsToCommand + Display + HasLen + HasArgSpelling + Shrinkable,
+    C: CoverageConfigurator<I>,
+    G: Generator<I, DifferentialState<I>>,
+    MT: MutatorsTuple<I, DifferentialState<I>> + NamedTuple,
+{
+    let options = &cli.options;
+    let comparison = Comparison {
+        stdout_policy,
+        exit_status_equivalence: cli.exit_status.clone(),
+        stderr_normaliser: Arc::new(StderrNormaliser::load(&cli.stderr_rules, util)?),
+    };
+    let suppressions = Arc::new(
+        cli.suppressions
+            .as_deref()
+            .map(|path| SuppressionList::load(path, util))
+            .transpose()?
+            .unwrap_or_default(),
+    );
+    let implementation_refs = implementations.iter().collect::<Vec<_>>();
+    #[cfg(feature = "gcov")]
+    let gcov = gcov_build(&implementation_refs)?;
+    // the tests of the reproducers expect the behaviour of the majority
+    let reproducer_exporter = ReproducerExporter::new(util, &implementation_refs, None);
+
+    let run_client = |state: Option<_>,
+                      mut mgr: LlmpRestartingEventManager<_, _, _>,
+                      core_id: CoreId|
+     -> Result<(), Error> {
+        let ids = implementations
+            .iter()
+            .map(|implementation| format!("{}-{:?}", implementation.name, core_id.0))
+            .collect::<Vec<_>>();
+        let mut coverage_shmems = Vec::with_capacity(implementations.len());
+        let mut coverage_shmem_descriptions = Vec::with_capacity(implementations.len());
+        for implementation in implementations {
+            let (coverage_shmem, coverage_shmem_description) =
+                get_shmem(implementation.coverage_shmem_size)?;
+            coverage_shmems.push(coverage_shmem);
+            coverage_shmem_descriptions.push(coverage_shmem_description);
+        }
+
+        let combined_coverage_observer =
+            HitcountsIterableMapObserver::new(MultiMapObserver::differential(
+                "CombinedCoverage",
+                coverage_shmems
+                    .iter_mut()
+                    .map(|coverage_shmem| unsafe {
+                        OwnedMutSlice::from_raw_parts_mut(
+                            coverage_shmem.as_mut_ptr(),
+                            coverage_shmem.len(),
+                        )
+                    })
+                    .collect(),
+            ));
+        let outcome_observer = NWayObserver::new(
+            "outcome-observer",
+            implementations
+                .iter()
+                .map(|implementation| implementation.name.clone())
+                .collect(),
+        );
+
+        #[cfg(feature = "gcov")]
+        let mut feedback = feedback_and_fast!(
+            AflMapFeedback::new(&combined_coverage_observer),
+            CovFeedback::<C>::new(
+                true,
+                &gcov,
+                &format!("gcov-{:?}", core_id.0),
+                &coverage_shmem_descriptions[0]
+            )
+        );
+        #[cfg(not(feature = "gcov"))]
+        let mut feedback = AflMapFeedback::new(&combined_coverage_observer);
+        let classifier = MajorityClassifier::new(&outcome_observer, comparison.clone());
+        let mut objective = feedback_and_fast!(
+            feedback_or_fast!(
+                // the vote comes first, so divergences involving a crash are attributed as well, and the reports
+                // are checked regardless of it, so they are attached to the objective
+                feedback_or!(
+                    MajorityFeedback::new(&outcome_observer, comparison.clone()),
+                    // sanitizer reports, bucketed by their error type and location
+                    SanitizerFeedback::n_way(&outcome_observer),
+                    // panics, failed assertions and aborts, bucketed by their location
+                    PanicFeedback::n_way(&outcome_observer)
+                ),
+                CrashFeedback::new(),
+                SpellingEquivalenceFeedback
+            ),
+            // known divergences are counted instead of saved
+            SuppressionFeedback::new(suppressions.clone(), classifier.clone()),
+            // only the first and the smallest objective of each bucket are saved
+            BucketFeedback::new(
+                PathBuf::from(&options.output),
+                classifier.clone(),
+                reproducer_exporter.clone()
+            )
+        );
+
+        let mut state = state.unwrap_or_else(|| {
+            StdState::new(
+                StdRand::with_seed(current_nanos()),
+                fuzz_corpus(),
+                InMemoryCorpus::new(),
+                &mut feedback,
+                &mut objective,
+            )
+            .unwrap()
+        });
+
+        let scheduler = StdWeightedScheduler::with_schedule(
+            &mut state,
+            &combined_coverage_observer,
+            Some(PowerSchedule::FAST),
+        );
+
+        let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
+
+        // the observers of all implementations have the same names, so the same handles find them in each executor
+        let mut handles = None;
+        let mut executors = Vec::with_capacity(implementations.len());
+        for (((implementation, id), coverage_shmem), coverage_shmem_description) in implementations
+            .iter()
+            .zip(&ids)
+            .zip(coverage_shmems.iter_mut())
+            .zip(&coverage_shmem_descriptions)
+        {
+            let stdout_observer = StdOutObserver::new("stdout-observer");
+            let stderr_observer = StdErrObserver::new("stderr-observer");
+            let time_observer = TimeObserver::new("time-observer");
+            let tree_observer = TreeSnapshotObserver::new("tree-observer", id);
+            let exit_status_observer = ExitStatusObserver::new("exit-status-observer");
+            let coverage_observer =
+                unsafe { StdMapObserver::new("coverage-observer", coverage_shmem.as_slice_mut()) };
+
+            handles = Some((
+                stdout_observer.handle(),
+                stderr_observer.handle(),
+                exit_status_observer.handle(),
+                tree_observer.handle(),
+            ));
+            executors.push(build_executor::<I, C, _>(
+                implementation,
+                id,
+                coverage_shmem_description,
+                tuple_list!(
+                    coverage_observer,
+                    stdout_observer,
+                    stderr_observer,
+                    time_observer,
+                    tree_observer,
+                    exit_status_observer
+                ),
+                cli.exit_status.clone(),
+            ));
+        }
+        let (stdout_handle, stderr_handle, exit_status_handle, tree_handle) =
+            handles.expect("fuzz_majority runs at least three implementations");
+
+        let outcome_handle = outcome_observer.handle();
+        let mut executor = NWayExecutor::new(
+            executors,
+            tuple_list!(combined_coverage_observer, outcome_observer),
+            outcome_handle,
+            stdout_handle,
+            stderr_handle,
+            exit_status_handle,
+            tree_handle,
+        );
+
+        if state.must_load_initial_inputs() {
+            state.generate_initial_inputs(
+                &mut fuzzer,
+                &mut executor,
+                &mut generator(),
+                &mut mgr,
+                8,
+            )?
+        }
+
+        let mut stages = tuple_list!(
+            StdMutationalStage::new(StdMOptMutator::new(&mut state, mutators(), 7, 5)?),
+            // shrinks the representatives of new buckets
+            MinimisationStage::new(
+                PathBuf::from(&options.output),
+                classifier,
+                reproducer_exporter.clone(),
+                MINIMISATION_EXECUTIONS
+            )
+        );
+
+        fuzzer.fuzz_loop(&mut stages, &mut executor, &mut state, &mut mgr)
+    };
 
     Launcher::builder()
         .configuration(EventConfig::AlwaysUnique)
-        .shmem_provider(launcher_shmem_provider)
-        .monitor(monitor)
+        .shmem_provider(StdShMemProvider::new()?)
+        .monitor(monitor())
         .run_client(run_client)
         .cores(&options.cores)
         .broker_port(options.broker_port)
@@ -374,6 +919,92 @@ fn fuzz(util: &str) -> Result<(), Error> {
         .launch()
 }
 
-pub fn has_stderr(o: &StdErrObserver) -> Option<bool> {
-    o.stderr.as_ref().map(|e| !e.is_empty())
+/// The corpus of interesting inputs, kept on disk as well with the `on_disk_corpus` feature
+fn fuzz_corpus<I: Input>() -> FuzzCorpus<I> {
+    #[cfg(feature = "on_disk_corpus")]
+    return InMemoryOnDiskCorpus::new(PathBuf::from("corpus")).unwrap();
+    #[cfg(not(feature = "on_disk_corpus"))]
+    InMemoryCorpus::new()
+}
+
+/// Runs `implementation` as `id` with `observers`: spawned by the configurator `C` or forked by a fork server if the
+/// implementation enables it, filling the coverage map from the novelty of its output if it is uninstrumented, and
+/// additionally running inputs in their canonical spelling to compare them under `exit_status_equivalence`.
+fn build_executor<'a, I, C, S>(
+    implementation: &Implementation,
+    id: &str,
+    coverage_shmem_description: &ShMemDescription,
+    observers: ImplementationObservers<'a>,
+    exit_status_equivalence: ExitStatusEquivalence,
+) -> ImplementationExecutor<'a, C, S>
+where
+    C: CoverageConfigurator<I>,
+{
+    let (
+        coverage_observer,
+        (stdout_observer, (stderr_observer, (_, (_, (exit_status_observer, ()))))),
+    ) = &observers;
+    let coverage_handle = coverage_observer.handle();
+    let stdout_handle = stdout_observer.handle();
+    let stderr_handle = stderr_observer.handle();
+    let exit_status_handle = exit_status_observer.handle();
+    SpellingEquivalenceExecutor::new(
+        &implementation.name,
+        OutputNoveltyExecutor::new(
+            ForkServerExecutor::new(
+                C::configure(
+                    coverage_shmem_description,
+                    Some(stdout_handle.clone()),
+                    Some(stderr_handle.clone()),
+                    implementation,
+                    id,
+                )
+                .into_exit_status_executor(observers, exit_status_handle.clone()),
+                coverage_shmem_description,
+                implementation,
+                id,
+                stdout_handle.clone(),
+                stderr_handle.clone(),
+                exit_status_handle.clone(),
+                C::SUPPORTS_FORK_SERVER,
+            ),
+            implementation,
+            coverage_handle,
+            stdout_handle.clone(),
+            stderr_handle.clone(),
+            exit_status_handle.clone(),
+        ),
+        stdout_handle,
+        stderr_handle,
+        exit_status_handle,
+        exit_status_equivalence,
+    )
+}
+
+/// The monitor of all clients, also writing the statistics to `monitor.toml`
+fn monitor() -> impl Monitor + Clone {
+    #[cfg(not(feature = "tui"))]
+    let base_monitor = MultiMonitor::new(|s| println!("{}", s));
+    #[cfg(feature = "tui")]
+    let base_monitor = TuiMonitor::builder()
+        .title("coreutils differential fuzzer")
+        .build();
+    OnDiskTomlMonitor::with_update_interval(
+        "monitor.toml",
+        base_monitor,
+        Duration::from_millis(100),
+    )
+}
+
+/// The `gcov` build of the first of `implementations` which has one, run by [`CovFeedback`]
+#[cfg(feature = "gcov")]
+fn gcov_build(implementations: &[&Implementation]) -> Result<Implementation, Error> {
+    implementations
+        .iter()
+        .find_map(|implementation| implementation.gcov_build())
+        .ok_or_else(|| {
+            Error::illegal_argument(
+                "The gcov feature needs an implementation with a gcov build, see --implementations",
+            )
+        })
 }
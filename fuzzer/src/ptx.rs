@@ -0,0 +1,490 @@
+use core::fmt;
+use std::{
+    borrow::Cow,
+    ffi::{OsStr, OsString},
+    fmt::{Display, Formatter},
+    hash::{DefaultHasher, Hash, Hasher},
+    ops::RangeInclusive,
+    os::unix::ffi::{OsStrExt, OsStringExt},
+};
+
+use serde::{Deserialize, Serialize};
+
+use libafl::{
+    corpus::CorpusId,
+    generators::Generator,
+    inputs::Input,
+    mutators::{MutationResult, Mutator},
+    state::HasRand,
+    Error, SerdeAny,
+};
+
+use libafl_bolts::{
+    prelude::Rand,
+    tuple_list,
+    tuples::{tuple_list_type, Merge},
+    HasLen, Named,
+};
+
+use crate::generic::{
+    executor::ExtractsToCommand,
+    fixture::{Fixture, FixtureEntry, FixtureEntryKind, FIXTURE_EPOCH},
+    mapping::{mapped_havoc_mutations, HasByteFields, MappedHavocMutationsType},
+    minimise::{without_each, without_set_fields, Shrinkable},
+    numeric::NumericArg,
+    options::OptionValue,
+    spelling::HasArgSpelling,
+    stdio::args_string_mapper,
+};
+
+const WORDS: [&[u8]; 16] = [
+    b"the",
+    b"The",
+    b"fuzzer",
+    b"index",
+    b"a",
+    b"of",
+    b"and",
+    b"permuted",
+    b"KWIC",
+    b"x",
+    b"word-word",
+    b"it's",
+    b"42",
+    b"\xc3\xa4rger",
+    b"end.",
+    b"(paren)",
+];
+
+const PUNCTUATION: [&[u8]; 8] = [b" ", b" ", b" ", b", ", b". ", b".\n", b"\n\n", b"\t"];
+
+const REGEXES: [&[u8]; 8] = [
+    b"[.?!][]\"')}]*\\($\\|\t\\|  \\)[ \t\n]*",
+    b"\\w+",
+    b"[a-z]+",
+    b"[^ ]*",
+    b"",
+    b"\\.",
+    b"\\(",
+    b"[[:upper:]][[:alpha:]]*",
+];
+
+// paths of the word lists and input files in the fixture
+const IGNORE_FILE: &[u8] = b"ignore";
+const ONLY_FILE: &[u8] = b"only";
+const BREAK_FILE: &[u8] = b"break";
+const INPUT_FILES: [&[u8]; 2] = [b"input1", b"input2"];
+
+const WIDTHS: RangeInclusive<i128> = 0..=99;
+const GAP_SIZES: RangeInclusive<i128> = 0..=9;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum PtxFormat {
+    /// `-O`
+    Roff,
+    /// `-T`
+    Tex,
+}
+
+/// An [`Input`] implementation for coreutils' `ptx`
+///
+/// Word lists and input files live in a generated [`Fixture`]; `stdin` is used if no input file is given.
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, SerdeAny)]
+pub struct PtxInput {
+    pub fixture: Fixture,
+    pub stdin: Vec<u8>,
+    /// names of files in the fixture read instead of `stdin`
+    pub inputs: Vec<Vec<u8>>,
+    pub auto_reference: bool,
+    pub traditional: bool,
+    pub right_side_refs: bool,
+    pub format: Option<PtxFormat>,
+    pub width: Option<NumericArg>,
+    pub gap_size: Option<NumericArg>,
+    pub sentence_regexp: Option<Vec<u8>>,
+    pub word_regexp: Option<Vec<u8>>,
+    pub break_file: bool,
+    pub ignore_file: bool,
+    pub only_file: bool,
+    pub ignore_case: bool,
+}
+
+impl Display for PtxInput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "fixture: {}, args: {}",
+            self.fixture,
+            args_string_mapper(&self.get_args())
+        )
+    }
+}
+
+impl Input for PtxInput {
+    fn generate_name(&self, _id: Option<CorpusId>) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+impl ExtractsToCommand for PtxInput {
+    fn get_stdin(&self) -> &Vec<u8> {
+        &self.stdin
+    }
+
+    fn get_args<'a>(&self) -> Vec<Cow<'a, OsStr>> {
+        let mut args = Vec::with_capacity(16 + self.inputs.len());
+        for (enabled, flag) in [
+            (self.auto_reference, "-A"),
+            (self.traditional, "-G"),
+            (self.right_side_refs, "-R"),
+            (self.ignore_case, "-f"),
+        ] {
+            if enabled {
+                args.push(Cow::Borrowed(OsStr::new(flag)))
+            }
+        }
+        match self.format {
+            Some(PtxFormat::Roff) => args.push(Cow::Borrowed(OsStr::new("-O"))),
+            Some(PtxFormat::Tex) => args.push(Cow::Borrowed(OsStr::new("-T"))),
+            None => (),
+        }
+        for (value, flag) in [(&self.width, b"-w"), (&self.gap_size, b"-g")] {
+            if let Some(value) = value {
+                let mut arg = flag.to_vec();
+                arg.extend(value.to_arg());
+                args.push(Cow::Owned(OsString::from_vec(arg)))
+            }
+        }
+        for (regexp, flag) in [(&self.sentence_regexp, "-S"), (&self.word_regexp, "-W")] {
+            if let Some(regexp) = regexp {
+                args.push(Cow::Borrowed(OsStr::new(flag)));
+                args.push(Cow::Owned(OsStr::from_bytes(regexp).to_os_string()));
+            }
+        }
+        for (enabled, flag, path) in [
+            (self.break_file, "-b", BREAK_FILE),
+            (self.ignore_file, "-i", IGNORE_FILE),
+            (self.only_file, "-o", ONLY_FILE),
+        ] {
+            if enabled {
+                args.push(Cow::Borrowed(OsStr::new(flag)));
+                args.push(Cow::Borrowed(OsStr::from_bytes(path)));
+            }
+        }
+        args.extend(
+            self.inputs
+                .iter()
+                .map(|i| Cow::Owned(OsStr::from_bytes(i).to_os_string())),
+        );
+        args
+    }
+
+    fn get_fixture(&self) -> Option<&Fixture> {
+        Some(&self.fixture)
+    }
+}
+
+impl HasByteFields for PtxInput {
+    fn byte_fields(&self) -> Vec<(Cow<'static, str>, &Vec<u8>)> {
+        let mut fields = vec![(Cow::Borrowed("stdin"), &self.stdin)];
+        if let Some(regexp) = &self.sentence_regexp {
+            fields.push((Cow::Borrowed("sentence_regexp"), regexp));
+        }
+        if let Some(regexp) = &self.word_regexp {
+            fields.push((Cow::Borrowed("word_regexp"), regexp));
+        }
+        fields.extend(self.fixture.file_fields());
+        fields
+    }
+
+    fn byte_fields_mut(&mut self) -> Vec<(Cow<'static, str>, &mut Vec<u8>)> {
+        let mut fields = vec![(Cow::Borrowed("stdin"), &mut self.stdin)];
+        if let Some(regexp) = &mut self.sentence_regexp {
+            fields.push((Cow::Borrowed("sentence_regexp"), regexp));
+        }
+        if let Some(regexp) = &mut self.word_regexp {
+            fields.push((Cow::Borrowed("word_regexp"), regexp));
+        }
+        fields.extend(self.fixture.file_fields_mut());
+        fields
+    }
+
+    fn byte_field_weight(&self, name: &str) -> usize {
+        match name {
+            "stdin" => 3,
+            "sentence_regexp" | "word_regexp" => 1,
+            _ => 2,
+        }
+    }
+}
+
+impl HasArgSpelling for PtxInput {}
+
+impl Shrinkable for PtxInput {
+    fn without_parts(&self) -> Vec<Self> {
+        let mut candidates = without_set_fields!(
+            self,
+            flags: [
+                auto_reference,
+                traditional,
+                right_side_refs,
+                break_file,
+                ignore_file,
+                only_file,
+                ignore_case
+            ],
+            options: [format, width, gap_size, sentence_regexp, word_regexp]
+        );
+        candidates.extend(without_each(&self.inputs).map(|inputs| Self {
+            inputs,
+            ..self.clone()
+        }));
+        candidates
+    }
+
+    fn shrinkable_fields_mut(&mut self) -> Vec<&mut Vec<u8>> {
+        let mut fields = vec![&mut self.stdin];
+        fields.extend(&mut self.sentence_regexp);
+        fields.extend(&mut self.word_regexp);
+        fields.extend(self.fixture.file_fields_mut().map(|(_, field)| field));
+        fields.extend(self.width.as_mut().map(|width| &mut width.0));
+        fields.extend(self.gap_size.as_mut().map(|gap_size| &mut gap_size.0));
+        fields
+    }
+}
+
+impl HasLen for PtxInput {
+    fn len(&self) -> usize {
+        self.stdin.len()
+    }
+}
+
+fn generate_text<R: Rand>(rand: &mut R, max_words: usize) -> Vec<u8> {
+    let mut text = Vec::new();
+    for _ in 0..rand.between(1, max_words.max(1)) {
+        text.extend(WORDS[rand.below(WORDS.len())]);
+        text.extend(PUNCTUATION[rand.below(PUNCTUATION.len())]);
+    }
+    text
+}
+
+fn generate_word_list<R: Rand>(rand: &mut R) -> Vec<u8> {
+    let mut list = Vec::new();
+    for _ in 0..rand.below(6) {
+        list.extend(WORDS[rand.below(WORDS.len())]);
+        list.push(b'\n');
+    }
+    list
+}
+
+fn generate_regexp<R: Rand>(rand: &mut R) -> Vec<u8> {
+    REGEXES[rand.below(REGEXES.len())].to_vec()
+}
+
+fn file_entry(path: &[u8], content: Vec<u8>) -> FixtureEntry {
+    FixtureEntry {
+        path: path.to_vec(),
+        kind: FixtureEntryKind::File { content, sparse: 0 },
+        mode: 0o644,
+        mtime: FIXTURE_EPOCH,
+    }
+}
+
+/// The files in the fixture of every [`PtxInput`]
+fn fixture_files() -> impl Iterator<Item = &'static [u8]> {
+    [BREAK_FILE, IGNORE_FILE, ONLY_FILE]
+        .into_iter()
+        .chain(INPUT_FILES)
+}
+
+pub struct PtxGenerator {
+    max_words: usize,
+}
+
+impl PtxGenerator {
+    pub fn new(max_words: usize) -> Self {
+        Self { max_words }
+    }
+}
+
+impl<S> Generator<PtxInput, S> for PtxGenerator
+where
+    S: HasRand,
+{
+    fn generate(&mut self, state: &mut S) -> Result<PtxInput, Error> {
+        let rand = state.rand_mut();
+        let fixture = Fixture {
+            entries: fixture_files()
+                .map(|path| {
+                    let content = if INPUT_FILES.contains(&path) {
+                        generate_text(rand, self.max_words)
+                    } else {
+                        generate_word_list(rand)
+                    };
+                    file_entry(path, content)
+                })
+                .collect(),
+        };
+        let inputs = (0..rand.below(3))
+            .map(|_| match rand.below(4) {
+                0 => b"-".to_vec(),
+                _ => INPUT_FILES[rand.below(INPUT_FILES.len())].to_vec(),
+            })
+            .collect();
+        Ok(PtxInput {
+            fixture,
+            stdin: generate_text(rand, self.max_words),
+            inputs,
+            auto_reference: rand.coinflip(0.2),
+            traditional: rand.coinflip(0.3),
+            right_side_refs: rand.coinflip(0.2),
+            format: match rand.below(4) {
+                0 => Some(PtxFormat::Roff),
+                1 => Some(PtxFormat::Tex),
+                _ => None,
+            },
+            width: rand
+                .coinflip(0.3)
+                .then(|| NumericArg::generate(rand, WIDTHS)),
+            gap_size: rand
+                .coinflip(0.2)
+                .then(|| NumericArg::generate(rand, GAP_SIZES)),
+            sentence_regexp: rand.coinflip(0.1).then(|| generate_regexp(rand)),
+            word_regexp: rand.coinflip(0.1).then(|| generate_regexp(rand)),
+            break_file: rand.coinflip(0.1),
+            ignore_file: rand.coinflip(0.2),
+            only_file: rand.coinflip(0.2),
+            ignore_case: rand.coinflip(0.2),
+        })
+    }
+}
+
+/// Flips or regenerates one randomly chosen option of a [`PtxInput`]
+pub struct PtxOptionMutator;
+impl<S> Mutator<PtxInput, S> for PtxOptionMutator
+where
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut PtxInput) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+        match rand.below(13) {
+            0 => input.auto_reference = !input.auto_reference,
+            1 => input.traditional = !input.traditional,
+            2 => input.right_side_refs = !input.right_side_refs,
+            3 => {
+                input.format = match input.format {
+                    None => Some(PtxFormat::Roff),
+                    Some(PtxFormat::Roff) => Some(PtxFormat::Tex),
+                    Some(PtxFormat::Tex) => None,
+                }
+            }
+            4 => match &mut input.width {
+                Some(width) if rand.coinflip(0.5) => width.mutate(rand, WIDTHS),
+                width => *width = width.is_none().then(|| NumericArg::generate(rand, WIDTHS)),
+            },
+            5 => match &mut input.gap_size {
+                Some(gap_size) if rand.coinflip(0.5) => gap_size.mutate(rand, GAP_SIZES),
+                gap_size => {
+                    *gap_size = gap_size
+                        .is_none()
+                        .then(|| NumericArg::generate(rand, GAP_SIZES))
+                }
+            },
+            6 => {
+                input.sentence_regexp = input
+                    .sentence_regexp
+                    .is_none()
+                    .then(|| generate_regexp(rand))
+            }
+            7 => input.word_regexp = input.word_regexp.is_none().then(|| generate_regexp(rand)),
+            8 => input.break_file = !input.break_file,
+            9 => input.ignore_file = !input.ignore_file,
+            10 => input.only_file = !input.only_file,
+            11 => input.ignore_case = !input.ignore_case,
+            _ => {
+                if !input.inputs.is_empty() && rand.coinflip(0.5) {
+                    input.inputs.remove(rand.below(input.inputs.len()));
+                } else {
+                    input
+                        .inputs
+                        .push(INPUT_FILES[rand.below(INPUT_FILES.len())].to_vec());
+                }
+            }
+        }
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for PtxOptionMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("PtxOptionMutator")
+    }
+}
+
+/// Inserts or removes a word in `stdin`, an input file or a word list
+pub struct PtxTextMutator;
+impl<S> Mutator<PtxInput, S> for PtxTextMutator
+where
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut PtxInput) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+        let file_index = rand.below(input.fixture.entries.len() + 1);
+        let text = match input.fixture.entries.get_mut(file_index) {
+            Some(FixtureEntry {
+                kind: FixtureEntryKind::File { content, .. },
+                ..
+            }) => content,
+            _ => &mut input.stdin,
+        };
+        let words = text
+            .split(|b| b.is_ascii_whitespace())
+            .filter(|w| !w.is_empty())
+            .count();
+        if words > 0 && rand.coinflip(0.5) {
+            let victim = rand.below(words);
+            let mut seen = 0;
+            let mut start = None;
+            for (i, b) in text.iter().enumerate() {
+                let is_word = !b.is_ascii_whitespace();
+                let starts_word = is_word && (i == 0 || text[i - 1].is_ascii_whitespace());
+                if starts_word {
+                    if seen == victim {
+                        start = Some(i);
+                        break;
+                    }
+                    seen += 1;
+                }
+            }
+            if let Some(start) = start {
+                let end = text[start..]
+                    .iter()
+                    .position(u8::is_ascii_whitespace)
+                    .map_or(text.len(), |p| start + p);
+                text.drain(start..end);
+            }
+        } else {
+            let position = rand.below(text.len() + 1);
+            let mut word = WORDS[rand.below(WORDS.len())].to_vec();
+            word.extend(PUNCTUATION[rand.below(PUNCTUATION.len())]);
+            text.splice(position..position, word);
+        }
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for PtxTextMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("PtxTextMutator")
+    }
+}
+
+pub type PtxMutatorsType = <tuple_list_type!(PtxOptionMutator, PtxTextMutator) as Merge<
+    MappedHavocMutationsType,
+>>::MergeResult;
+
+pub fn ptx_mutators() -> PtxMutatorsType {
+    tuple_list!(PtxOptionMutator, PtxTextMutator).merge(mapped_havoc_mutations())
+}
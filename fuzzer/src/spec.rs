@@ -0,0 +1,742 @@
+use core::fmt;
+use std::{
+    borrow::Cow,
+    ffi::{OsStr, OsString},
+    fmt::{Display, Formatter},
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    os::unix::ffi::OsStringExt,
+    path::Path,
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use libafl::{
+    corpus::CorpusId,
+    generators::Generator,
+    inputs::Input,
+    mutators::{MutationResult, Mutator},
+    state::HasRand,
+    Error, SerdeAny,
+};
+
+use libafl_bolts::{
+    prelude::Rand,
+    tuple_list,
+    tuples::{tuple_list_type, Merge},
+    HasLen, Named,
+};
+
+use crate::generic::{
+    executor::ExtractsToCommand,
+    fixture::{
+        generate_fixture, generate_operand, Fixture, FixtureAddEntryMutator,
+        FixtureAttributeMutator, FixtureRemoveEntryMutator, HasFixture,
+    },
+    mapping::{mapped_havoc_mutations, HasByteFields, MappedHavocMutationsType},
+    minimise::{without_each, Shrinkable},
+    numeric::{generate_numeric, mutate_numeric},
+    spelling::{render_args, unique_prefix_len, HasArgSpelling, OptionArg, SpellingMutator},
+    stdio::{args_string_mapper, vec_string_mapper, StdoutPolicy},
+};
+
+/// The command line of a utility, loaded from a JSON file (see `specs/`). Specs allow fuzzing a utility without
+/// writing a dedicated input.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct UtilSpec {
+    /// the name of the binary
+    pub util: String,
+    #[serde(default)]
+    pub options: Vec<OptionSpec>,
+    /// all long options the utility accepts in GNU or uutils, including those not in `options`, which long options are
+    /// only abbreviated as far as is unambiguous among
+    pub long_options: Vec<String>,
+    /// groups of option names of which at most one is passed at a time
+    #[serde(default)]
+    pub exclusive: Vec<Vec<String>>,
+    #[serde(default)]
+    pub operands: Option<OperandSpec>,
+    #[serde(default)]
+    pub stdin: StdinKind,
+    /// how stdout is compared if an implementation writes to stderr, see [`StdoutPolicy`]
+    #[serde(default)]
+    pub stdout_policy: Option<StdoutPolicy>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct OptionSpec {
+    /// identifies the option in [`UtilSpec::exclusive`]
+    pub name: String,
+    pub short: Option<String>,
+    pub long: Option<String>,
+    /// the option's argument, if it takes one
+    pub arg: Option<ArgSpec>,
+}
+
+/// The type of an option's argument or an operand.
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ArgSpec {
+    Int {
+        min: i64,
+        max: i64,
+    },
+    /// an integer followed by one of the suffixes, like `1K`
+    Size {
+        min: i64,
+        max: i64,
+        #[serde(default = "default_size_suffixes")]
+        suffixes: Vec<String>,
+    },
+    Enum {
+        values: Vec<String>,
+    },
+    String {
+        #[serde(default = "default_max_len")]
+        max_len: usize,
+    },
+    Regex,
+    /// a path in the fixture
+    File,
+}
+
+fn default_size_suffixes() -> Vec<String> {
+    [
+        "", "b", "K", "KB", "KiB", "M", "MB", "G", "T", "E", "Z", "Y", "Q",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+fn default_max_len() -> usize {
+    16
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct OperandSpec {
+    pub arg: ArgSpec,
+    #[serde(default)]
+    pub min: usize,
+    pub max: usize,
+}
+
+/// What is passed to `stdin`
+#[derive(Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StdinKind {
+    #[default]
+    None,
+    Bytes,
+    Text,
+    Lines,
+}
+
+impl UtilSpec {
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            Error::os_error(e, format!("Could not read spec {}", path.to_string_lossy()))
+        })?;
+        let spec: Self = serde_json::from_str(&content).map_err(|e| {
+            Error::serialize(format!(
+                "Could not parse spec {}: {e}",
+                path.to_string_lossy()
+            ))
+        })?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        for option in &self.options {
+            if option.short.is_none() && option.long.is_none() {
+                return Err(Error::illegal_argument(format!(
+                    "Option {} has neither a short nor a long spelling",
+                    option.name
+                )));
+            }
+            if let Some(arg) = &option.arg {
+                arg.validate(&option.name)?;
+            }
+        }
+        for name in self.exclusive.iter().flatten() {
+            if self.option(name).is_none() {
+                return Err(Error::illegal_argument(format!(
+                    "Exclusive group names unknown option {name}"
+                )));
+            }
+        }
+        if let Some(operands) = &self.operands {
+            operands.arg.validate("operands")?;
+            if operands.min > operands.max {
+                return Err(Error::illegal_argument(
+                    "Operands have a minimum larger than their maximum",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn long_options(&self) -> Vec<&str> {
+        self.options
+            .iter()
+            .filter_map(|o| o.long.as_deref())
+            .chain(self.long_options.iter().map(String::as_str))
+            .collect()
+    }
+
+    fn option(&self, name: &str) -> Option<&OptionSpec> {
+        self.options.iter().find(|o| o.name == name)
+    }
+
+    /// Whether any argument names a file, so the input needs a [`Fixture`].
+    fn uses_files(&self) -> bool {
+        self.options
+            .iter()
+            .filter_map(|o| o.arg.as_ref())
+            .chain(self.operands.iter().map(|o| &o.arg))
+            .any(|a| matches!(a, ArgSpec::File))
+    }
+
+    /// Whether `name` may be added to the options already present.
+    fn compatible(&self, name: &str, present: &[SpecOption]) -> bool {
+        self.exclusive
+            .iter()
+            .filter(|group| group.iter().any(|n| n == name))
+            .all(|group| {
+                !present
+                    .iter()
+                    .any(|o| o.name != name && group.contains(&o.name))
+            })
+    }
+}
+
+impl ArgSpec {
+    fn validate(&self, name: &str) -> Result<(), Error> {
+        match self {
+            ArgSpec::Int { min, max } | ArgSpec::Size { min, max, .. } if min > max => Err(
+                Error::illegal_argument(format!("{name} has a minimum larger than its maximum")),
+            ),
+            ArgSpec::Size { suffixes, .. } if suffixes.is_empty() => Err(Error::illegal_argument(
+                format!("{name} has no size suffixes"),
+            )),
+            ArgSpec::Enum { values } if values.is_empty() => Err(Error::illegal_argument(format!(
+                "{name} has no enum values"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn generate<R: Rand>(&self, rand: &mut R, fixture: &Fixture) -> Vec<u8> {
+        match self {
+            ArgSpec::Int { min, max } => {
+                generate_numeric(rand, &(i128::from(*min)..=i128::from(*max)), &[])
+            }
+            ArgSpec::Size { min, max, suffixes } => generate_numeric(
+                rand,
+                &(i128::from(*min)..=i128::from(*max)),
+                &suffixes.iter().map(String::as_str).collect::<Vec<_>>(),
+            ),
+            ArgSpec::Enum { values } => values[rand.below(values.len())].as_bytes().to_vec(),
+            ArgSpec::String { max_len } => (0..rand.between(0, *max_len))
+                .map(|_| rand.between(0x20, 0x7E) as u8)
+                .collect(),
+            ArgSpec::Regex => (0..rand.between(1, 4))
+                .flat_map(|_| REGEX_PARTS[rand.below(REGEX_PARTS.len())])
+                .copied()
+                .collect(),
+            ArgSpec::File => generate_operand(rand, fixture),
+        }
+    }
+
+    /// Changes numbers in place, any other value is replaced by a newly generated one.
+    fn mutate<R: Rand>(&self, rand: &mut R, value: &mut Vec<u8>, fixture: &Fixture) {
+        match self {
+            ArgSpec::Int { min, max } => {
+                mutate_numeric(rand, value, &(i128::from(*min)..=i128::from(*max)), &[]);
+            }
+            ArgSpec::Size { min, max, suffixes } => mutate_numeric(
+                rand,
+                value,
+                &(i128::from(*min)..=i128::from(*max)),
+                &suffixes.iter().map(String::as_str).collect::<Vec<_>>(),
+            ),
+            _ => *value = self.generate(rand, fixture),
+        }
+    }
+}
+
+const REGEX_PARTS: [&[u8]; 16] = [
+    b"a",
+    b".",
+    b"*",
+    b"[a-z]",
+    b"[^ ]",
+    b"\\(",
+    b"\\)",
+    b"^",
+    b"$",
+    b"\\|",
+    b"+",
+    b"?",
+    b"\\{2\\}",
+    b"[[:alpha:]]",
+    b"\\w",
+    b"\\",
+];
+
+fn generate_stdin<R: Rand>(rand: &mut R, kind: &StdinKind) -> Option<Vec<u8>> {
+    let len = rand.between(0, 64);
+    match kind {
+        StdinKind::None => None,
+        StdinKind::Bytes => Some((0..len).map(|_| rand.below(256) as u8).collect()),
+        StdinKind::Text => Some(
+            (0..len)
+                .map(|_| match rand.below(10) {
+                    0 => b'\n',
+                    1 => b' ',
+                    2 => b'\t',
+                    _ => rand.between(0x21, 0x7E) as u8,
+                })
+                .collect(),
+        ),
+        StdinKind::Lines => Some(
+            (0..rand.between(0, 8))
+                .flat_map(|_| {
+                    let mut line = (0..rand.between(0, 12))
+                        .map(|_| match rand.below(6) {
+                            0 => b' ',
+                            1 => b',',
+                            2 => b'\t',
+                            _ => rand.between(b'a'.into(), b'e'.into()) as u8,
+                        })
+                        .collect::<Vec<_>>();
+                    line.push(b'\n');
+                    line
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// An option passed to the utility. Its spellings are copied from the spec, so the input can be rendered on its own.
+#[derive(Serialize, Deserialize, Clone, Debug, Hash)]
+pub struct SpecOption {
+    /// the [`OptionSpec::name`]
+    pub name: String,
+    pub short: Option<String>,
+    pub long: Option<String>,
+    /// see [`OptionArg::unique_prefix`]
+    pub unique_prefix: usize,
+    pub value: Option<Vec<u8>>,
+}
+
+impl SpecOption {
+    fn generate<R: Rand>(
+        rand: &mut R,
+        spec: &UtilSpec,
+        option: &OptionSpec,
+        fixture: &Fixture,
+    ) -> Self {
+        Self {
+            name: option.name.clone(),
+            short: option.short.clone(),
+            long: option.long.clone(),
+            unique_prefix: option
+                .long
+                .as_ref()
+                .map_or(0, |long| unique_prefix_len(long, &spec.long_options())),
+            value: option.arg.as_ref().map(|a| a.generate(rand, fixture)),
+        }
+    }
+}
+
+/// An [`Input`] implementation for utilities described by a [`UtilSpec`]
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, SerdeAny)]
+pub struct SpecInput {
+    pub options: Vec<SpecOption>,
+    pub operands: Vec<Vec<u8>>,
+    pub stdin: Option<Vec<u8>>,
+    /// only present if the spec names files
+    pub fixture: Option<Fixture>,
+    /// see [`render_args`]
+    pub spelling: u64,
+}
+
+impl Display for SpecInput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        if let Some(fixture) = &self.fixture {
+            write!(f, "fixture: {}, ", fixture)?;
+        }
+        if let Some(stdin) = &self.stdin {
+            write!(f, "stdin: '{}', ", vec_string_mapper(&Some(stdin.clone())))?;
+        }
+        write!(f, "args: {}", args_string_mapper(&self.get_args()))
+    }
+}
+
+impl Input for SpecInput {
+    fn generate_name(&self, _id: Option<CorpusId>) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+impl ExtractsToCommand for SpecInput {
+    fn get_stdin(&self) -> &Vec<u8> {
+        static EMPTY: Vec<u8> = Vec::new();
+        self.stdin.as_ref().unwrap_or(&EMPTY)
+    }
+
+    fn get_args<'a>(&self) -> Vec<Cow<'a, OsStr>> {
+        let options = self
+            .options
+            .iter()
+            .map(|o| OptionArg {
+                short: o.short.as_deref(),
+                long: o.long.as_deref(),
+                unique_prefix: o.unique_prefix,
+                value: o.value.as_deref(),
+            })
+            .collect::<Vec<_>>();
+        let operands = self.operands.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        render_args(&options, &operands, self.spelling)
+            .into_iter()
+            .map(|a| Cow::Owned(OsString::from_vec(a)))
+            .collect()
+    }
+
+    fn get_fixture(&self) -> Option<&Fixture> {
+        self.fixture.as_ref()
+    }
+}
+
+impl HasArgSpelling for SpecInput {
+    fn spelling_mut(&mut self) -> Option<&mut u64> {
+        Some(&mut self.spelling)
+    }
+}
+
+impl Shrinkable for SpecInput {
+    fn without_parts(&self) -> Vec<Self> {
+        without_each(&self.options)
+            .map(|options| Self {
+                options,
+                ..self.clone()
+            })
+            .chain(without_each(&self.operands).map(|operands| Self {
+                operands,
+                ..self.clone()
+            }))
+            .collect()
+    }
+
+    fn shrinkable_fields_mut(&mut self) -> Vec<&mut Vec<u8>> {
+        self.byte_fields_mut()
+            .into_iter()
+            .map(|(_, field)| field)
+            .collect()
+    }
+}
+
+impl HasFixture for SpecInput {
+    fn fixture(&self) -> &Fixture {
+        self.fixture
+            .as_ref()
+            .expect("fixture mutators are skipped without a fixture")
+    }
+
+    fn fixture_mut(&mut self) -> &mut Fixture {
+        self.fixture
+            .as_mut()
+            .expect("fixture mutators are skipped without a fixture")
+    }
+
+    fn operands_mut(&mut self) -> &mut Vec<Vec<u8>> {
+        &mut self.operands
+    }
+}
+
+impl HasByteFields for SpecInput {
+    fn byte_fields(&self) -> Vec<(Cow<'static, str>, &Vec<u8>)> {
+        let mut fields = Vec::new();
+        fields.extend(self.stdin.iter().map(|s| (Cow::Borrowed("stdin"), s)));
+        fields.extend(self.options.iter().filter_map(|o| {
+            o.value
+                .as_ref()
+                .map(|v| (Cow::Owned(format!("option:{}", o.name)), v))
+        }));
+        if let Some(fixture) = &self.fixture {
+            fields.extend(fixture.file_fields());
+        }
+        fields
+    }
+
+    fn byte_fields_mut(&mut self) -> Vec<(Cow<'static, str>, &mut Vec<u8>)> {
+        let mut fields = Vec::new();
+        fields.extend(self.stdin.iter_mut().map(|s| (Cow::Borrowed("stdin"), s)));
+        fields.extend(self.options.iter_mut().filter_map(|o| {
+            o.value
+                .as_mut()
+                .map(|v| (Cow::Owned(format!("option:{}", o.name)), v))
+        }));
+        if let Some(fixture) = &mut self.fixture {
+            fields.extend(fixture.file_fields_mut());
+        }
+        fields
+    }
+
+    fn byte_field_weight(&self, name: &str) -> usize {
+        if name == "stdin" {
+            3
+        } else if name.starts_with("option:") {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+impl HasLen for SpecInput {
+    fn len(&self) -> usize {
+        self.options.len() + self.operands.len() + self.stdin.as_ref().map_or(0, Vec::len)
+    }
+}
+
+pub struct SpecGenerator {
+    spec: Arc<UtilSpec>,
+    max_entries: usize,
+}
+
+impl SpecGenerator {
+    pub fn new(spec: Arc<UtilSpec>, max_entries: usize) -> Self {
+        Self { spec, max_entries }
+    }
+}
+
+impl<S> Generator<SpecInput, S> for SpecGenerator
+where
+    S: HasRand,
+{
+    fn generate(&mut self, state: &mut S) -> Result<SpecInput, Error> {
+        let rand = state.rand_mut();
+        let fixture = self
+            .spec
+            .uses_files()
+            .then(|| generate_fixture(rand, self.max_entries));
+        let empty = Fixture::default();
+        let files = fixture.as_ref().unwrap_or(&empty);
+
+        let mut options: Vec<SpecOption> = Vec::new();
+        if !self.spec.options.is_empty() {
+            for _ in 0..rand.below(4) {
+                let option = &self.spec.options[rand.below(self.spec.options.len())];
+                if self.spec.compatible(&option.name, &options) {
+                    options.push(SpecOption::generate(rand, &self.spec, option, files));
+                }
+            }
+        }
+        let operands = match &self.spec.operands {
+            Some(spec) => (0..rand.between(spec.min, spec.max))
+                .map(|_| spec.arg.generate(rand, files))
+                .collect(),
+            None => Vec::new(),
+        };
+        Ok(SpecInput {
+            options,
+            operands,
+            stdin: generate_stdin(rand, &self.spec.stdin),
+            fixture,
+            spelling: if rand.coinflip(0.5) { rand.next() } else { 0 },
+        })
+    }
+}
+
+/// Adds, removes or swaps options, keeping exclusive options apart
+pub struct SpecOptionMutator {
+    spec: Arc<UtilSpec>,
+}
+
+impl<S> Mutator<SpecInput, S> for SpecOptionMutator
+where
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut SpecInput) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+        match rand.below(3) {
+            0 if !input.options.is_empty() => {
+                input.options.remove(rand.below(input.options.len()));
+            }
+            1 if input.options.len() > 1 => {
+                let index = rand.below(input.options.len() - 1);
+                input.options.swap(index, index + 1);
+            }
+            _ => {
+                if self.spec.options.is_empty() {
+                    return Ok(MutationResult::Skipped);
+                }
+                let spec = &self.spec.options[rand.below(self.spec.options.len())];
+                if !self.spec.compatible(&spec.name, &input.options) {
+                    return Ok(MutationResult::Skipped);
+                }
+                let empty = Fixture::default();
+                let option = SpecOption::generate(
+                    rand,
+                    &self.spec,
+                    spec,
+                    input.fixture.as_ref().unwrap_or(&empty),
+                );
+                input
+                    .options
+                    .insert(rand.below(input.options.len() + 1), option);
+            }
+        }
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for SpecOptionMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("SpecOptionMutator")
+    }
+}
+
+/// Mutates the value of an option or an operand, see [`ArgSpec::mutate`]
+pub struct SpecValueMutator {
+    spec: Arc<UtilSpec>,
+}
+
+impl<S> Mutator<SpecInput, S> for SpecValueMutator
+where
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut SpecInput) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+        let empty = Fixture::default();
+        let fixture = input.fixture.as_ref().unwrap_or(&empty);
+        let with_values = input
+            .options
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| o.value.is_some())
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        if !input.operands.is_empty() && (with_values.is_empty() || rand.coinflip(0.5)) {
+            let Some(spec) = &self.spec.operands else {
+                return Ok(MutationResult::Skipped);
+            };
+            let index = rand.below(input.operands.len());
+            spec.arg.mutate(rand, &mut input.operands[index], fixture);
+            return Ok(MutationResult::Mutated);
+        }
+        if with_values.is_empty() {
+            return Ok(MutationResult::Skipped);
+        }
+        let option = &mut input.options[with_values[rand.below(with_values.len())]];
+        let Some(arg) = self.spec.option(&option.name).and_then(|o| o.arg.as_ref()) else {
+            return Ok(MutationResult::Skipped);
+        };
+        if let Some(value) = &mut option.value {
+            arg.mutate(rand, value, fixture);
+        }
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for SpecValueMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("SpecValueMutator")
+    }
+}
+
+/// Adds or removes an operand within the bounds of the spec
+pub struct SpecOperandMutator {
+    spec: Arc<UtilSpec>,
+}
+
+impl<S> Mutator<SpecInput, S> for SpecOperandMutator
+where
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut SpecInput) -> Result<MutationResult, Error> {
+        let Some(spec) = &self.spec.operands else {
+            return Ok(MutationResult::Skipped);
+        };
+        let rand = state.rand_mut();
+        let len = input.operands.len();
+        if len > spec.min && (len >= spec.max || rand.coinflip(0.5)) {
+            input.operands.remove(rand.below(len));
+        } else if len < spec.max {
+            let empty = Fixture::default();
+            let operand = spec
+                .arg
+                .generate(rand, input.fixture.as_ref().unwrap_or(&empty));
+            input.operands.insert(rand.below(len + 1), operand);
+        } else {
+            return Ok(MutationResult::Skipped);
+        }
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for SpecOperandMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("SpecOperandMutator")
+    }
+}
+
+/// Runs a fixture mutator only on inputs that have a fixture
+pub struct SpecFixtureMutator<M> {
+    inner: M,
+}
+
+impl<M, S> Mutator<SpecInput, S> for SpecFixtureMutator<M>
+where
+    M: Mutator<SpecInput, S>,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut SpecInput) -> Result<MutationResult, Error> {
+        if input.fixture.is_none() {
+            return Ok(MutationResult::Skipped);
+        }
+        self.inner.mutate(state, input)
+    }
+}
+
+impl<M: Named> Named for SpecFixtureMutator<M> {
+    fn name(&self) -> &Cow<'static, str> {
+        self.inner.name()
+    }
+}
+
+pub type SpecMutatorsType = <tuple_list_type!(
+    SpecOptionMutator,
+    SpecValueMutator,
+    SpecOperandMutator,
+    SpellingMutator,
+    SpecFixtureMutator<FixtureAddEntryMutator>,
+    SpecFixtureMutator<FixtureRemoveEntryMutator>,
+    SpecFixtureMutator<FixtureAttributeMutator>
+) as Merge<MappedHavocMutationsType>>::MergeResult;
+
+pub fn spec_mutators(spec: &Arc<UtilSpec>) -> SpecMutatorsType {
+    tuple_list!(
+        SpecOptionMutator { spec: spec.clone() },
+        SpecValueMutator { spec: spec.clone() },
+        SpecOperandMutator { spec: spec.clone() },
+        SpellingMutator,
+        SpecFixtureMutator {
+            inner: FixtureAddEntryMutator
+        },
+        SpecFixtureMutator {
+            inner: FixtureRemoveEntryMutator
+        },
+        SpecFixtureMutator {
+            inner: FixtureAttributeMutator
+        }
+    )
+    .merge(mapped_havoc_mutations())
+}
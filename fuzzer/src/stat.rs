@@ -0,0 +1,275 @@
+use core::fmt;
+use std::{
+    borrow::Cow,
+    ffi::OsStr,
+    fmt::{Display, Formatter},
+    hash::{DefaultHasher, Hash, Hasher},
+    os::unix::ffi::OsStrExt,
+};
+
+use serde::{Deserialize, Serialize};
+
+use libafl::{
+    corpus::CorpusId,
+    generators::Generator,
+    inputs::Input,
+    mutators::{MutationResult, Mutator},
+    state::HasRand,
+    Error, SerdeAny,
+};
+
+use libafl_bolts::{
+    prelude::Rand,
+    tuples::{tuple_list_type, Append, Merge},
+    HasLen, Named,
+};
+
+use crate::generic::{
+    executor::ExtractsToCommand,
+    fixture::{
+        fixture_mutators, generate_fixture, generate_operand, Fixture, FixtureAddEntryMutator,
+        FixtureAttributeMutator, FixtureOperandMutator, FixtureRemoveEntryMutator, HasFixture,
+    },
+    mapping::{mapped_havoc_mutations, HasByteFields, MappedHavocMutationsType},
+    minimise::{without_each, without_set_fields, Shrinkable},
+    spelling::HasArgSpelling,
+    stdio::args_string_mapper,
+};
+
+/// Format directives that produce the same output for both implementations on identical fixtures.
+///
+/// Inode numbers, device numbers, access, change and birth times are left out, since they differ between executions.
+const DIRECTIVES: [&[u8]; 18] = [
+    b"%n", b"%N", b"%s", b"%F", b"%a", b"%A", b"%f", b"%h", b"%u", b"%U", b"%g", b"%G", b"%y",
+    b"%Y", b"%b", b"%B", b"%%", b"%Q",
+];
+
+const LITERALS: [&[u8]; 7] = [b" ", b"\n", b"\\n", b"\\t", b"\\x41", b"\\0", b"-"];
+
+/// An [`Input`] implementation for coreutils' `stat`
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, SerdeAny)]
+pub struct StatInput {
+    pub fixture: Fixture,
+    pub operands: Vec<Vec<u8>>,
+    pub format: Vec<u8>,
+    /// use `--printf` (interpreting backslash escapes, no trailing newline) instead of `-c`
+    pub printf: bool,
+    pub dereference: bool,
+}
+
+impl Display for StatInput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "fixture: {}, args: {}",
+            self.fixture,
+            args_string_mapper(&self.get_args())
+        )
+    }
+}
+
+impl Input for StatInput {
+    fn generate_name(&self, _id: Option<CorpusId>) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+impl ExtractsToCommand for StatInput {
+    fn get_stdin(&self) -> &Vec<u8> {
+        static EMPTY: Vec<u8> = Vec::new();
+        &EMPTY
+    }
+
+    fn get_args<'a>(&self) -> Vec<Cow<'a, OsStr>> {
+        let mut args = Vec::with_capacity(4 + self.operands.len());
+        if self.dereference {
+            args.push(Cow::Borrowed(OsStr::new("-L")))
+        }
+        args.push(Cow::Borrowed(OsStr::new(if self.printf {
+            "--printf"
+        } else {
+            "-c"
+        })));
+        args.push(Cow::Owned(OsStr::from_bytes(&self.format).to_os_string()));
+        // fixture names may start with a dash
+        args.push(Cow::Borrowed(OsStr::new("--")));
+        args.extend(
+            self.operands
+                .iter()
+                .map(|o| Cow::Owned(OsStr::from_bytes(o).to_os_string())),
+        );
+        args
+    }
+
+    fn get_fixture(&self) -> Option<&Fixture> {
+        Some(&self.fixture)
+    }
+}
+
+impl HasFixture for StatInput {
+    fn fixture(&self) -> &Fixture {
+        &self.fixture
+    }
+
+    fn fixture_mut(&mut self) -> &mut Fixture {
+        &mut self.fixture
+    }
+
+    fn operands_mut(&mut self) -> &mut Vec<Vec<u8>> {
+        &mut self.operands
+    }
+}
+
+impl HasByteFields for StatInput {
+    fn byte_fields(&self) -> Vec<(Cow<'static, str>, &Vec<u8>)> {
+        vec![(Cow::Borrowed("format"), &self.format)]
+    }
+
+    fn byte_fields_mut(&mut self) -> Vec<(Cow<'static, str>, &mut Vec<u8>)> {
+        vec![(Cow::Borrowed("format"), &mut self.format)]
+    }
+}
+
+impl HasArgSpelling for StatInput {}
+
+impl Shrinkable for StatInput {
+    fn without_parts(&self) -> Vec<Self> {
+        let mut candidates = without_set_fields!(self, flags: [dereference], options: []);
+        candidates.extend(without_each(&self.operands).map(|operands| Self {
+            operands,
+            ..self.clone()
+        }));
+        candidates
+    }
+
+    fn shrinkable_fields_mut(&mut self) -> Vec<&mut Vec<u8>> {
+        vec![&mut self.format]
+    }
+}
+
+impl HasLen for StatInput {
+    fn len(&self) -> usize {
+        self.format.len()
+    }
+}
+
+fn generate_format_part<R: Rand>(rand: &mut R) -> Vec<u8> {
+    if rand.coinflip(0.7) {
+        let directive = DIRECTIVES[rand.below(DIRECTIVES.len())];
+        if rand.coinflip(0.2) {
+            // field width and alignment modifiers, e.g. `%-10s`
+            let mut modified = vec![b'%'];
+            if rand.coinflip(0.5) {
+                modified.push(b'-');
+            }
+            modified.extend(rand.below(20).to_string().as_bytes());
+            modified.extend_from_slice(&directive[1..]);
+            return modified;
+        }
+        directive.to_vec()
+    } else {
+        LITERALS[rand.below(LITERALS.len())].to_vec()
+    }
+}
+
+pub struct StatGenerator {
+    max_entries: usize,
+    max_format_parts: usize,
+}
+
+impl StatGenerator {
+    pub fn new(max_entries: usize, max_format_parts: usize) -> Self {
+        Self {
+            max_entries,
+            max_format_parts,
+        }
+    }
+}
+
+impl<S> Generator<StatInput, S> for StatGenerator
+where
+    S: HasRand,
+{
+    fn generate(&mut self, state: &mut S) -> Result<StatInput, Error> {
+        let rand = state.rand_mut();
+        let fixture = generate_fixture(rand, self.max_entries);
+        let operands = (0..rand.between(1, 3))
+            .map(|_| generate_operand(rand, &fixture))
+            .collect();
+        let format = (0..rand.between(1, self.max_format_parts))
+            .flat_map(|_| generate_format_part(rand))
+            .collect();
+        Ok(StatInput {
+            fixture,
+            operands,
+            format,
+            printf: rand.coinflip(0.5),
+            dereference: rand.coinflip(0.3),
+        })
+    }
+}
+
+/// Inserts or removes a directive or literal in the format string
+pub struct StatFormatMutator;
+impl<S> Mutator<StatInput, S> for StatFormatMutator
+where
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut StatInput) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+        let position = rand.below(input.format.len() + 1);
+        if rand.coinflip(0.5) && position < input.format.len() {
+            let end = rand.between(position + 1, input.format.len());
+            input.format.drain(position..end);
+        } else {
+            let part = generate_format_part(rand);
+            input.format.splice(position..position, part);
+        }
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for StatFormatMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("StatFormatMutator")
+    }
+}
+
+pub struct StatFlipOptionMutator;
+impl<S> Mutator<StatInput, S> for StatFlipOptionMutator
+where
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut StatInput) -> Result<MutationResult, Error> {
+        if state.rand_mut().coinflip(0.5) {
+            input.printf = !input.printf;
+        } else {
+            input.dereference = !input.dereference;
+        }
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for StatFlipOptionMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("StatFlipOptionMutator")
+    }
+}
+
+pub type StatMutatorsType = <tuple_list_type!(
+    FixtureAddEntryMutator,
+    FixtureRemoveEntryMutator,
+    FixtureAttributeMutator,
+    FixtureOperandMutator,
+    StatFormatMutator,
+    StatFlipOptionMutator
+) as Merge<MappedHavocMutationsType>>::MergeResult;
+
+pub fn stat_mutators() -> StatMutatorsType {
+    fixture_mutators()
+        .append(StatFormatMutator)
+        .append(StatFlipOptionMutator)
+        .merge(mapped_havoc_mutations())
+}
@@ -0,0 +1,303 @@
+use core::fmt;
+use std::{
+    borrow::Cow,
+    ffi::OsStr,
+    fmt::{Display, Formatter},
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use serde::{Deserialize, Serialize};
+
+use libafl::{
+    corpus::CorpusId,
+    generators::Generator,
+    inputs::Input,
+    mutators::{MutationResult, Mutator},
+    state::HasRand,
+    Error, SerdeAny,
+};
+
+use libafl_bolts::{prelude::Rand, tuple_list, tuples::tuple_list_type, HasLen, Named};
+
+use crate::generic::{
+    executor::ExtractsToCommand, minimise::Shrinkable, spelling::HasArgSpelling,
+    stdio::vec_string_mapper,
+};
+
+/// Node names, including some that look like options or contain non-ASCII bytes.
+const NODES: [&[u8]; 12] = [
+    b"a",
+    b"b",
+    b"c",
+    b"d",
+    b"e",
+    b"f",
+    b"main",
+    b"lib",
+    b"-",
+    b"--",
+    b"\xc3\xa4",
+    b"\xff",
+];
+
+/// Whitespace separating two tokens, all of which tsort accepts.
+const SEPARATORS: [&[u8]; 5] = [b" ", b"\n", b"\t", b"  \n", b"\r\n"];
+
+/// An [`Input`] implementation for coreutils' `tsort`
+///
+/// The graph is stored as a flat list of tokens, where consecutive pairs form an edge. An odd number of tokens is
+/// therefore an invalid input.
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, SerdeAny)]
+pub struct TsortInput {
+    pub tokens: Vec<Vec<u8>>,
+    /// the whitespace following each token
+    pub separators: Vec<Vec<u8>>,
+    /// the rendered tokens and separators
+    stdin: Vec<u8>,
+}
+
+impl TsortInput {
+    pub fn new(tokens: Vec<Vec<u8>>, separators: Vec<Vec<u8>>) -> Self {
+        let mut input = Self {
+            tokens,
+            separators,
+            stdin: Vec::new(),
+        };
+        input.render();
+        input
+    }
+
+    /// Updates the data passed to `stdin` after the tokens or separators were changed.
+    pub fn render(&mut self) {
+        self.separators.resize(self.tokens.len(), b" ".to_vec());
+        self.stdin = self
+            .tokens
+            .iter()
+            .zip(&self.separators)
+            .flat_map(|(t, s)| t.iter().chain(s.iter()))
+            .copied()
+            .collect();
+    }
+}
+
+impl Display for TsortInput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "input: '{}'",
+            vec_string_mapper(&Some(self.stdin.clone()))
+        )
+    }
+}
+
+impl Input for TsortInput {
+    fn generate_name(&self, _id: Option<CorpusId>) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+impl ExtractsToCommand for TsortInput {
+    fn get_stdin(&self) -> &Vec<u8> {
+        &self.stdin
+    }
+
+    fn get_args<'a>(&self) -> Vec<Cow<'a, OsStr>> {
+        Vec::new()
+    }
+}
+
+impl HasArgSpelling for TsortInput {}
+
+/// Drops pairs of tokens, which form an edge, before single tokens.
+impl Shrinkable for TsortInput {
+    fn without_parts(&self) -> Vec<Self> {
+        let pairs = (0..self.tokens.len().saturating_sub(1))
+            .step_by(2)
+            .map(|index| {
+                let mut candidate = self.clone();
+                candidate.tokens.drain(index..index + 2);
+                candidate.separators.drain(index..index + 2);
+                candidate
+            });
+        let singles = (0..self.tokens.len()).map(|index| {
+            let mut candidate = self.clone();
+            candidate.tokens.remove(index);
+            candidate.separators.remove(index);
+            candidate
+        });
+        pairs
+            .chain(singles)
+            .map(|mut candidate| {
+                candidate.render();
+                candidate
+            })
+            .collect()
+    }
+}
+
+impl HasLen for TsortInput {
+    fn len(&self) -> usize {
+        self.tokens.len()
+    }
+}
+
+fn generate_node<R: Rand>(rand: &mut R) -> Vec<u8> {
+    NODES[rand.below(NODES.len())].to_vec()
+}
+
+fn generate_separator<R: Rand>(rand: &mut R) -> Vec<u8> {
+    if rand.coinflip(0.7) {
+        b" ".to_vec()
+    } else {
+        SEPARATORS[rand.below(SEPARATORS.len())].to_vec()
+    }
+}
+
+/// Shapes of graphs the [`TsortGenerator`] creates.
+enum GraphShape {
+    /// edges only point from lower to higher node indices
+    Dag,
+    /// a single cycle through all nodes
+    Cycle,
+    /// arbitrary edges, including self-loops
+    Random,
+}
+
+pub struct TsortGenerator {
+    max_edges: usize,
+}
+
+impl TsortGenerator {
+    pub fn new(max_edges: usize) -> Self {
+        Self { max_edges }
+    }
+}
+
+impl<S> Generator<TsortInput, S> for TsortGenerator
+where
+    S: HasRand,
+{
+    fn generate(&mut self, state: &mut S) -> Result<TsortInput, Error> {
+        let rand = state.rand_mut();
+        let shape = match rand.below(3) {
+            0 => GraphShape::Dag,
+            1 => GraphShape::Cycle,
+            _ => GraphShape::Random,
+        };
+        let edge_count = rand.between(1, self.max_edges.max(1));
+        let mut tokens = Vec::with_capacity(2 * edge_count + 1);
+        match shape {
+            GraphShape::Dag => {
+                for _ in 0..edge_count {
+                    let from = rand.below(NODES.len() - 1);
+                    let to = rand.between(from + 1, NODES.len() - 1);
+                    tokens.push(NODES[from].to_vec());
+                    tokens.push(NODES[to].to_vec());
+                }
+            }
+            GraphShape::Cycle => {
+                let nodes = (0..edge_count)
+                    .map(|_| generate_node(rand))
+                    .collect::<Vec<_>>();
+                for (i, node) in nodes.iter().enumerate() {
+                    tokens.push(node.clone());
+                    tokens.push(nodes[(i + 1) % nodes.len()].clone());
+                }
+            }
+            GraphShape::Random => {
+                for _ in 0..edge_count {
+                    let from = generate_node(rand);
+                    let to = if rand.coinflip(0.2) {
+                        from.clone()
+                    } else {
+                        generate_node(rand)
+                    };
+                    tokens.push(from);
+                    tokens.push(to);
+                }
+            }
+        }
+        if rand.coinflip(0.1) {
+            // odd token count
+            tokens.push(generate_node(rand));
+        }
+        let separators = tokens.iter().map(|_| generate_separator(rand)).collect();
+        Ok(TsortInput::new(tokens, separators))
+    }
+}
+
+/// Adds, removes or replaces an edge, keeping the token count's parity
+pub struct TsortEdgeMutator;
+impl<S> Mutator<TsortInput, S> for TsortEdgeMutator
+where
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut TsortInput) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+        let edges = input.tokens.len() / 2;
+        let index = 2 * rand.below(edges + 1);
+        match rand.below(3) {
+            0 if edges > 0 && index < 2 * edges => {
+                input.tokens.drain(index..index + 2);
+                input.separators.drain(index..index + 2);
+            }
+            1 if edges > 0 && index < 2 * edges => {
+                input.tokens[index + rand.below(2)] = generate_node(rand);
+            }
+            _ => {
+                let from = generate_node(rand);
+                let to = generate_node(rand);
+                let separators = [generate_separator(rand), generate_separator(rand)];
+                input.tokens.splice(index..index, [from, to]);
+                input.separators.splice(index..index, separators);
+            }
+        }
+        input.render();
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for TsortEdgeMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("TsortEdgeMutator")
+    }
+}
+
+/// Inserts or removes a single token, changing the parity of the token count, or replaces a separator
+pub struct TsortTokenMutator;
+impl<S> Mutator<TsortInput, S> for TsortTokenMutator
+where
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut TsortInput) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+        let index = rand.below(input.tokens.len() + 1);
+        match rand.below(3) {
+            0 if index < input.tokens.len() => {
+                input.tokens.remove(index);
+                input.separators.remove(index);
+            }
+            1 if index < input.tokens.len() => {
+                input.separators[index] = generate_separator(rand);
+            }
+            _ => {
+                input.tokens.insert(index, generate_node(rand));
+                input.separators.insert(index, generate_separator(rand));
+            }
+        }
+        input.render();
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for TsortTokenMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("TsortTokenMutator")
+    }
+}
+
+pub fn tsort_mutators() -> tuple_list_type!(TsortEdgeMutator, TsortTokenMutator) {
+    tuple_list!(TsortEdgeMutator, TsortTokenMutator)
+}
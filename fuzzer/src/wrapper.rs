@@ -0,0 +1,408 @@
+use core::fmt;
+use std::{
+    borrow::Cow,
+    ffi::{OsStr, OsString},
+    fmt::{Display, Formatter},
+    hash::{DefaultHasher, Hash, Hasher},
+    os::unix::ffi::OsStrExt,
+    path::PathBuf,
+    sync::OnceLock,
+};
+
+use serde::{Deserialize, Serialize};
+
+use libafl::{
+    corpus::CorpusId,
+    generators::Generator,
+    inputs::Input,
+    mutators::{MutationResult, Mutator},
+    state::HasRand,
+    Error, SerdeAny,
+};
+
+use libafl_bolts::{prelude::Rand, tuple_list, tuples::tuple_list_type, HasLen, Named};
+
+use crate::generic::{
+    executor::{absolute_path, ExtractsToCommand},
+    minimise::{without_each, Shrinkable},
+    spelling::HasArgSpelling,
+    stdio::args_string_mapper,
+};
+
+/// The helper binary run as the inner command of the wrappers, see `wrapper_probe/src/main.rs`.
+pub static PROBE_PATH: &str = "./target/release/wrapper_probe";
+
+const NAMES: [&[u8]; 8] = [b"A", b"B", b"PATH", b"HOME", b"_x", b"1X", b"A=B", b""];
+
+const VALUES: [&[u8]; 8] = [b"", b"x", b"a b", b"$A", b"${A}", b"\\c", b"=", b"\n"];
+
+const SPLIT_STRINGS: [&[u8]; 12] = [
+    b"A=1 B=2",
+    b"-i",
+    b"-u A",
+    b"-C /",
+    b"'a b'",
+    b"\"a\\tb\"",
+    b"a\\_b",
+    b"#comment",
+    b"${HOME}",
+    b"a\\c b",
+    b"-S x",
+    b"\\",
+];
+
+const DIRECTORIES: [&[u8]; 4] = [b"/", b"/tmp", b".", b"does-not-exist"];
+
+const ADJUSTMENTS: [&[u8]; 10] = [
+    b"0", b"3", b"-1", b"19", b"20", b"+5", b"-100", b"abc", b"", b"1.5",
+];
+
+/// Durations long enough for the probe to always finish before the timeout expires
+const DURATIONS: [&[u8]; 8] = [b"10", b"10s", b"0.5m", b"1e1", b"inf", b"1d", b"0", b"10x"];
+
+const SIGNALS: [&[u8]; 10] = [
+    b"TERM", b"KILL", b"9", b"SIGINT", b"hup", b"0", b"64", b"FOO", b"RTMIN+1", b"",
+];
+
+const KILL_AFTERS: [&[u8]; 5] = [b"1", b"0", b"5s", b"-1", b"inf"];
+
+const BUFFER_MODES: [&[u8]; 8] = [b"0", b"L", b"1K", b"1M", b"64", b"", b"X", b"L1"];
+
+const PROBE_ARGS: [&[u8]; 6] = [b"a", b"-", b"--", b"-i", b"A=B", b"a b"];
+
+/// The wrapper utilities. `chroot` is deliberately missing, since it requires root.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum WrapperUtil {
+    Env,
+    Nice,
+    Nohup,
+    Timeout,
+    Stdbuf,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Hash)]
+pub enum Wrapper {
+    Env(EnvOptions),
+    Nice {
+        adjustment: Option<Vec<u8>>,
+    },
+    Nohup,
+    Timeout(TimeoutOptions),
+    Stdbuf {
+        input: Option<Vec<u8>>,
+        output: Option<Vec<u8>>,
+        error: Option<Vec<u8>>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, Default)]
+pub struct EnvOptions {
+    pub ignore_environment: bool,
+    pub unset: Vec<Vec<u8>>,
+    pub null: bool,
+    pub chdir: Option<Vec<u8>>,
+    pub split_string: Option<Vec<u8>>,
+    /// `NAME=VALUE` pairs
+    pub assignments: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Hash)]
+pub struct TimeoutOptions {
+    pub signal: Option<Vec<u8>>,
+    pub kill_after: Option<Vec<u8>>,
+    pub preserve_status: bool,
+    pub foreground: bool,
+    pub duration: Vec<u8>,
+}
+
+/// An [`Input`] implementation for coreutils' wrapper utilities
+///
+/// The wrapped command is always the probe at [`PROBE_PATH`], which prints what it observes of its environment.
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, SerdeAny)]
+pub struct WrapperInput {
+    pub wrapper: Wrapper,
+    pub probe_args: Vec<Vec<u8>>,
+}
+
+impl Display for WrapperInput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "args: {}", args_string_mapper(&self.get_args()))
+    }
+}
+
+impl Input for WrapperInput {
+    fn generate_name(&self, _id: Option<CorpusId>) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+fn bytes_arg<'a>(bytes: &[u8]) -> Cow<'a, OsStr> {
+    Cow::Owned(OsStr::from_bytes(bytes).to_os_string())
+}
+
+fn probe_path() -> &'static PathBuf {
+    static PROBE: OnceLock<PathBuf> = OnceLock::new();
+    // absolute, since `env -C` changes the directory before running it
+    PROBE.get_or_init(|| absolute_path(PROBE_PATH))
+}
+
+impl ExtractsToCommand for WrapperInput {
+    fn get_stdin(&self) -> &Vec<u8> {
+        static EMPTY: Vec<u8> = Vec::new();
+        &EMPTY
+    }
+
+    fn get_args<'a>(&self) -> Vec<Cow<'a, OsStr>> {
+        let mut args = Vec::new();
+        match &self.wrapper {
+            Wrapper::Env(options) => {
+                if options.ignore_environment {
+                    args.push(Cow::Borrowed(OsStr::new("-i")))
+                }
+                if options.null {
+                    args.push(Cow::Borrowed(OsStr::new("-0")))
+                }
+                for name in &options.unset {
+                    args.push(Cow::Borrowed(OsStr::new("-u")));
+                    args.push(bytes_arg(name));
+                }
+                if let Some(dir) = &options.chdir {
+                    args.push(Cow::Borrowed(OsStr::new("-C")));
+                    args.push(bytes_arg(dir));
+                }
+                if let Some(split_string) = &options.split_string {
+                    args.push(Cow::Borrowed(OsStr::new("-S")));
+                    args.push(bytes_arg(split_string));
+                }
+                for (name, value) in &options.assignments {
+                    args.push(bytes_arg(&[name.as_slice(), b"=", value].concat()));
+                }
+            }
+            Wrapper::Nice { adjustment } => {
+                if let Some(adjustment) = adjustment {
+                    args.push(Cow::Borrowed(OsStr::new("-n")));
+                    args.push(bytes_arg(adjustment));
+                }
+            }
+            Wrapper::Nohup => (),
+            Wrapper::Timeout(options) => {
+                if let Some(signal) = &options.signal {
+                    args.push(Cow::Borrowed(OsStr::new("-s")));
+                    args.push(bytes_arg(signal));
+                }
+                if let Some(kill_after) = &options.kill_after {
+                    args.push(Cow::Borrowed(OsStr::new("-k")));
+                    args.push(bytes_arg(kill_after));
+                }
+                if options.preserve_status {
+                    args.push(Cow::Borrowed(OsStr::new("--preserve-status")))
+                }
+                if options.foreground {
+                    args.push(Cow::Borrowed(OsStr::new("--foreground")))
+                }
+                args.push(bytes_arg(&options.duration));
+            }
+            Wrapper::Stdbuf {
+                input,
+                output,
+                error,
+            } => {
+                for (mode, flag) in [(input, "-i"), (output, "-o"), (error, "-e")] {
+                    if let Some(mode) = mode {
+                        args.push(Cow::Owned(OsString::from_iter([
+                            OsStr::new(flag),
+                            OsStr::from_bytes(mode),
+                        ])))
+                    }
+                }
+            }
+        }
+        args.push(Cow::Owned(probe_path().clone().into_os_string()));
+        args.extend(self.probe_args.iter().map(|a| bytes_arg(a)));
+        args
+    }
+}
+
+impl HasArgSpelling for WrapperInput {}
+
+impl Shrinkable for WrapperInput {
+    fn without_parts(&self) -> Vec<Self> {
+        without_each(&self.probe_args)
+            .map(|probe_args| Self {
+                probe_args,
+                ..self.clone()
+            })
+            .collect()
+    }
+}
+
+impl HasLen for WrapperInput {
+    fn len(&self) -> usize {
+        self.probe_args.len()
+    }
+}
+
+fn choose<R: Rand, const N: usize>(rand: &mut R, values: &[&[u8]; N]) -> Vec<u8> {
+    values[rand.below(N)].to_vec()
+}
+
+fn maybe_choose<R: Rand, const N: usize>(
+    rand: &mut R,
+    probability: f64,
+    values: &[&[u8]; N],
+) -> Option<Vec<u8>> {
+    rand.coinflip(probability).then(|| choose(rand, values))
+}
+
+fn generate_assignment<R: Rand>(rand: &mut R) -> (Vec<u8>, Vec<u8>) {
+    (choose(rand, &NAMES), choose(rand, &VALUES))
+}
+
+fn generate_wrapper<R: Rand>(rand: &mut R, util: WrapperUtil) -> Wrapper {
+    match util {
+        WrapperUtil::Env => Wrapper::Env(EnvOptions {
+            ignore_environment: rand.coinflip(0.3),
+            unset: (0..rand.below(3)).map(|_| choose(rand, &NAMES)).collect(),
+            null: rand.coinflip(0.05),
+            chdir: maybe_choose(rand, 0.2, &DIRECTORIES),
+            split_string: maybe_choose(rand, 0.3, &SPLIT_STRINGS),
+            assignments: (0..rand.below(4))
+                .map(|_| generate_assignment(rand))
+                .collect(),
+        }),
+        WrapperUtil::Nice => Wrapper::Nice {
+            adjustment: maybe_choose(rand, 0.8, &ADJUSTMENTS),
+        },
+        WrapperUtil::Nohup => Wrapper::Nohup,
+        WrapperUtil::Timeout => Wrapper::Timeout(TimeoutOptions {
+            signal: maybe_choose(rand, 0.4, &SIGNALS),
+            kill_after: maybe_choose(rand, 0.3, &KILL_AFTERS),
+            preserve_status: rand.coinflip(0.3),
+            foreground: rand.coinflip(0.2),
+            duration: choose(rand, &DURATIONS),
+        }),
+        WrapperUtil::Stdbuf => Wrapper::Stdbuf {
+            input: maybe_choose(rand, 0.4, &BUFFER_MODES),
+            output: maybe_choose(rand, 0.5, &BUFFER_MODES),
+            error: maybe_choose(rand, 0.4, &BUFFER_MODES),
+        },
+    }
+}
+
+pub struct WrapperGenerator {
+    util: WrapperUtil,
+}
+
+impl WrapperGenerator {
+    pub fn new(util: WrapperUtil) -> Self {
+        Self { util }
+    }
+}
+
+impl<S> Generator<WrapperInput, S> for WrapperGenerator
+where
+    S: HasRand,
+{
+    fn generate(&mut self, state: &mut S) -> Result<WrapperInput, Error> {
+        let rand = state.rand_mut();
+        Ok(WrapperInput {
+            wrapper: generate_wrapper(rand, self.util),
+            probe_args: (0..rand.below(3))
+                .map(|_| choose(rand, &PROBE_ARGS))
+                .collect(),
+        })
+    }
+}
+
+/// Flips or regenerates one randomly chosen option of the wrapper
+pub struct WrapperOptionMutator;
+impl<S> Mutator<WrapperInput, S> for WrapperOptionMutator
+where
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut WrapperInput) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+        match &mut input.wrapper {
+            Wrapper::Env(options) => match rand.below(6) {
+                0 => options.ignore_environment = !options.ignore_environment,
+                1 => options.null = !options.null,
+                2 => {
+                    if !options.unset.is_empty() && rand.coinflip(0.5) {
+                        options.unset.remove(rand.below(options.unset.len()));
+                    } else {
+                        options.unset.push(choose(rand, &NAMES));
+                    }
+                }
+                3 => options.chdir = maybe_choose(rand, 0.5, &DIRECTORIES),
+                4 => options.split_string = maybe_choose(rand, 0.5, &SPLIT_STRINGS),
+                _ => {
+                    if !options.assignments.is_empty() && rand.coinflip(0.5) {
+                        options
+                            .assignments
+                            .remove(rand.below(options.assignments.len()));
+                    } else {
+                        options.assignments.push(generate_assignment(rand));
+                    }
+                }
+            },
+            Wrapper::Nice { adjustment } => *adjustment = maybe_choose(rand, 0.8, &ADJUSTMENTS),
+            Wrapper::Nohup => return Ok(MutationResult::Skipped),
+            Wrapper::Timeout(options) => match rand.below(5) {
+                0 => options.signal = maybe_choose(rand, 0.5, &SIGNALS),
+                1 => options.kill_after = maybe_choose(rand, 0.5, &KILL_AFTERS),
+                2 => options.preserve_status = !options.preserve_status,
+                3 => options.foreground = !options.foreground,
+                _ => options.duration = choose(rand, &DURATIONS),
+            },
+            Wrapper::Stdbuf {
+                input,
+                output,
+                error,
+            } => {
+                let mode = match rand.below(3) {
+                    0 => input,
+                    1 => output,
+                    _ => error,
+                };
+                *mode = maybe_choose(rand, 0.5, &BUFFER_MODES);
+            }
+        }
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for WrapperOptionMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("WrapperOptionMutator")
+    }
+}
+
+/// Adds or removes an argument passed on to the probe
+pub struct WrapperProbeArgMutator;
+impl<S> Mutator<WrapperInput, S> for WrapperProbeArgMutator
+where
+    S: HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut WrapperInput) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+        if !input.probe_args.is_empty() && rand.coinflip(0.5) {
+            input.probe_args.remove(rand.below(input.probe_args.len()));
+        } else {
+            let position = rand.below(input.probe_args.len() + 1);
+            input.probe_args.insert(position, choose(rand, &PROBE_ARGS));
+        }
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for WrapperProbeArgMutator {
+    fn name(&self) -> &Cow<'static, str> {
+        &Cow::Borrowed("WrapperProbeArgMutator")
+    }
+}
+
+pub fn wrapper_mutators() -> tuple_list_type!(WrapperOptionMutator, WrapperProbeArgMutator) {
+    tuple_list!(WrapperOptionMutator, WrapperProbeArgMutator)
+}
@@ -0,0 +1,114 @@
+//! Deterministic helper run as the inner command of wrapper utilities such as `env`, `nice` or `timeout`.
+//!
+//! Prints everything about its execution environment a wrapper may influence, so the fuzzer can compare the
+//! wrappers by what the probe observes.
+
+use std::{
+    env,
+    ffi::OsStr,
+    io::{stdout, Write},
+    mem::MaybeUninit,
+    os::unix::ffi::OsStrExt,
+    path::Path,
+    ptr::null,
+};
+
+const IGNORED: u8 = 1;
+const HANDLED: u8 = 2;
+const BLOCKED: u8 = 4;
+
+/// Disposition of every signal as inherited from the wrapper.
+static mut SIGNALS: [u8; 32] = [0; 32];
+
+/// Records the signal dispositions before the Rust runtime is initialized, since it ignores `SIGPIPE`.
+extern "C" fn record_signals() {
+    unsafe {
+        let mut blocked = MaybeUninit::<libc::sigset_t>::uninit();
+        libc::sigemptyset(blocked.as_mut_ptr());
+        libc::sigprocmask(libc::SIG_BLOCK, null(), blocked.as_mut_ptr());
+        for signal in 1..32 {
+            let mut action = MaybeUninit::<libc::sigaction>::zeroed();
+            if libc::sigaction(signal, null(), action.as_mut_ptr()) != 0 {
+                continue;
+            }
+            let mut state = match action.assume_init().sa_sigaction {
+                libc::SIG_DFL => 0,
+                libc::SIG_IGN => IGNORED,
+                _ => HANDLED,
+            };
+            if libc::sigismember(blocked.as_ptr(), signal) == 1 {
+                state |= BLOCKED;
+            }
+            SIGNALS[signal as usize] = state;
+        }
+    }
+}
+
+#[used]
+#[link_section = ".init_array"]
+static RECORD_SIGNALS: extern "C" fn() = record_signals;
+
+fn escape(s: &OsStr) -> String {
+    s.as_bytes().escape_ascii().to_string()
+}
+
+fn main() {
+    let mut out = Vec::new();
+
+    for (i, arg) in env::args_os().enumerate() {
+        writeln!(out, "argv[{i}]: '{}'", escape(&arg)).unwrap();
+    }
+
+    match env::current_dir() {
+        Ok(cwd) => writeln!(out, "cwd: '{}'", escape(cwd.as_os_str())).unwrap(),
+        Err(e) => writeln!(out, "cwd: error {:?}", e.kind()).unwrap(),
+    }
+
+    // the order of the environment is an implementation detail
+    let mut vars = env::vars_os().collect::<Vec<_>>();
+    vars.sort();
+    for (name, value) in vars {
+        // preloaded libraries (e.g. by `stdbuf`) are installed in implementation specific locations
+        let value = if name == "LD_PRELOAD" {
+            env::split_paths(&value)
+                .filter_map(|p| Path::new(&p).file_name().map(OsStr::to_os_string))
+                .collect::<Vec<_>>()
+                .join(OsStr::new(":"))
+        } else {
+            value
+        };
+        writeln!(out, "env: '{}'='{}'", escape(&name), escape(&value)).unwrap();
+    }
+
+    let (niceness, is_group_leader) = unsafe {
+        *libc::__errno_location() = 0;
+        (
+            libc::getpriority(libc::PRIO_PROCESS, 0),
+            libc::getpgrp() == libc::getpid(),
+        )
+    };
+    writeln!(out, "niceness: {niceness}").unwrap();
+    writeln!(out, "process group leader: {is_group_leader}").unwrap();
+
+    for (signal, state) in unsafe { SIGNALS }.iter().enumerate() {
+        if *state != 0 {
+            writeln!(
+                out,
+                "signal {signal}: {}{}",
+                match state & !BLOCKED {
+                    IGNORED => "ignored",
+                    HANDLED => "handled",
+                    _ => "default",
+                },
+                if state & BLOCKED != 0 {
+                    ", blocked"
+                } else {
+                    ""
+                }
+            )
+            .unwrap();
+        }
+    }
+
+    stdout().write_all(&out).unwrap();
+}